@@ -1,24 +1,119 @@
+//! The abstract semantic graph of a parsed document, which
+//! [`Parser::parse_to_asg`](crate::Parser::parse_to_asg) returns.
+//!
+//! The nodes are read through their accessors and matched through their
+//! enums, starting from [`Document::blocks`].
+
+mod attributes;
 pub(crate) mod block;
 mod inlines;
+mod visit;
 
-pub(crate) use inlines::*;
+pub(crate) use attributes::*;
+pub use block::{
+    AdmonitionVariant, AnyList, Block, BlockBreak, BlockBreakVariant, BlockForm, BlockLeaf,
+    BlockLeafBody, BlockMacro, BlockMacroBody, BlockMetadata, BlockParent, BlockParentBody,
+    Callout, CellStyle, DlistItem, DlistStyle, Document, DocumentHeader, HorizontalAlignment,
+    ListItem, ListVariant, NumberingStyle, Section, SectionBody, Table, TableCell, TableColumn,
+    VerticalAlignment,
+};
+pub use inlines::*;
+pub use visit::*;
 
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
+/// The range of a node in the source, serialized as the `[start, end]` pair of
+/// the TCK schema.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "[LocationBoundary; 2]", into = "[LocationBoundary; 2]")]
 pub struct Location {
     start: LocationBoundary,
     end: LocationBoundary,
 }
+impl Location {
+    pub(crate) fn new(start: LocationBoundary, end: LocationBoundary) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> &LocationBoundary {
+        &self.start
+    }
+
+    pub fn end(&self) -> &LocationBoundary {
+        &self.end
+    }
+
+    pub(crate) fn set_end(&mut self, end: LocationBoundary) {
+        self.end = end;
+    }
+}
 
+impl From<[LocationBoundary; 2]> for Location {
+    fn from([start, end]: [LocationBoundary; 2]) -> Self {
+        Self { start, end }
+    }
+}
+impl From<Location> for [LocationBoundary; 2] {
+    fn from(location: Location) -> Self {
+        [location.start, location.end]
+    }
+}
+
+/// A position in the source, serialized as `{line, col, file}` like in the TCK
+/// schema, where `file` is the path of an included file as an array of one path.
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-struct LocationBoundary {
+pub struct LocationBoundary {
     line: usize,
+    #[serde(rename = "col")]
     column: usize,
+    #[serde(rename = "file", default, with = "file_path")]
     source: Option<String>,
 }
+impl LocationBoundary {
+    pub(crate) fn new(line: usize, column: usize, source: Option<String>) -> Self {
+        Self {
+            line,
+            column,
+            source,
+        }
+    }
+
+    /// Returns the line, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column, starting at 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns the path of the file, for a document with a source or an included file.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+}
+
+mod file_path {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        source: &Option<String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        source.as_ref().map(|s| [s]).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error> {
+        let file = Option::<Vec<String>>::deserialize(deserializer)?;
+
+        Ok(file.and_then(|f| f.last().cloned()))
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,538 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+
+const INTRINSIC_ATTRIBUTES: [(&str, &str); 29] = [
+    ("amp", "&"),
+    ("apos", "'"),
+    ("asterisk", "*"),
+    ("backslash", "\\"),
+    ("backtick", "`"),
+    ("blank", ""),
+    ("brvbar", "\u{a6}"),
+    ("caret", "^"),
+    ("cpp", "C++"),
+    ("deg", "\u{b0}"),
+    ("empty", ""),
+    ("endsb", "]"),
+    ("gt", ">"),
+    ("ldquo", "\u{201c}"),
+    ("lsquo", "\u{2018}"),
+    ("lt", "<"),
+    ("nbsp", "\u{a0}"),
+    ("plus", "+"),
+    ("pp", "++"),
+    ("quot", "\""),
+    ("rdquo", "\u{201d}"),
+    ("rsquo", "\u{2019}"),
+    ("sp", " "),
+    ("startsb", "["),
+    ("tilde", "~"),
+    ("two-colons", "::"),
+    ("two-semicolons", ";;"),
+    ("vbar", "|"),
+    ("zwsp", "\u{200b}"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttributeMissing {
+    Skip,
+    Drop,
+    DropLine,
+    Warn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttributeUndefined {
+    Drop,
+    DropLine,
+}
+
+/// A piece of a line after attribute references have been resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AttributeSegment {
+    /// Characters `start..end` of the original line, taken as is.
+    Text(usize, usize),
+    /// Replacement text of the reference spanning characters `start..end` of the original line.
+    Value(usize, usize, String),
+}
+
+/// The document attributes in effect at the current position of the parser.
+#[derive(Debug, Clone)]
+pub(crate) struct Attributes {
+    values: HashMap<String, String>,
+    /// Warnings about the references skipped with `attribute-missing: warn`.
+    warnings: Vec<String>,
+}
+impl Default for Attributes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Attributes {
+    pub(crate) fn new() -> Self {
+        Self::new_at(SystemTime::now())
+    }
+
+    /// Starts with the intrinsic attributes, with the dates and times of `now`.
+    ///
+    /// The parser knows neither the local time zone nor the source file, so
+    /// `localdate` and `localtime` are in UTC, and `docdate` and `doctime` are
+    /// the same time of the parse rather than the modification time of the file.
+    pub(crate) fn new_at(now: SystemTime) -> Self {
+        let mut values: HashMap<String, String> = INTRINSIC_ATTRIBUTES
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        let (date, time, year) = utc_date_time(now);
+        values.insert("localdate".to_owned(), date.clone());
+        values.insert("localtime".to_owned(), time.clone());
+        values.insert("localyear".to_owned(), year.clone());
+        values.insert("docdate".to_owned(), date);
+        values.insert("doctime".to_owned(), time);
+        values.insert("docyear".to_owned(), year);
+        values.insert("attribute-missing".to_owned(), "skip".to_owned());
+        values.insert("attribute-undefined".to_owned(), "drop-line".to_owned());
+
+        Self {
+            values,
+            warnings: Vec::with_capacity(0),
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(&name.to_lowercase()).map(|v| v.as_str())
+    }
+
+    pub(crate) fn set(&mut self, name: &str, value: &str) {
+        self.values.insert(name.to_lowercase(), value.to_owned());
+    }
+
+    pub(crate) fn unset(&mut self, name: &str) {
+        self.values.remove(&name.to_lowercase());
+    }
+
+    /// Takes the warnings of the references substituted since the last call.
+    pub(crate) fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn attribute_missing(&self) -> AttributeMissing {
+        match self.get("attribute-missing") {
+            Some("drop") => AttributeMissing::Drop,
+            Some("drop-line") => AttributeMissing::DropLine,
+            Some("warn") => AttributeMissing::Warn,
+            _ => AttributeMissing::Skip,
+        }
+    }
+
+    fn attribute_undefined(&self) -> AttributeUndefined {
+        match self.get("attribute-undefined") {
+            Some("drop") => AttributeUndefined::Drop,
+            _ => AttributeUndefined::DropLine,
+        }
+    }
+
+    /// Replaces the attribute references in `text`, dropping the lines that
+    /// `attribute-missing` or `attribute-undefined` ask to drop.
+    pub(crate) fn substitute(&mut self, text: &str) -> String {
+        let mut lines = Vec::with_capacity(0);
+        for line in text.split('\n') {
+            let chars: Vec<char> = line.chars().collect();
+            let Some(segments) = self.substitute_line(line) else {
+                continue;
+            };
+
+            let mut substituted = String::new();
+            for segment in segments {
+                match segment {
+                    AttributeSegment::Text(start, end) => substituted.extend(&chars[start..end]),
+                    AttributeSegment::Value(_, _, value) => substituted.push_str(&value),
+                }
+            }
+            lines.push(substituted);
+        }
+
+        lines.join("\n")
+    }
+
+    /// Resolves the attribute references in a single line.
+    ///
+    /// Returns `None` when the whole line has to be dropped.
+    pub(crate) fn substitute_line(&mut self, line: &str) -> Option<Vec<AttributeSegment>> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut segments = Vec::with_capacity(0);
+        let mut text_start = 0;
+        let mut has_dropped = false;
+        let mut i = 0;
+
+        while i < chars.len() {
+            if chars[i] != '{' {
+                i += 1;
+                continue;
+            }
+
+            let Some(end) = Self::find_reference(&chars, i) else {
+                i += 1;
+                continue;
+            };
+
+            if i > 0 && chars[i - 1] == '\\' {
+                // An escaped reference is kept as is, without the backslash.
+                if text_start < i - 1 {
+                    segments.push(AttributeSegment::Text(text_start, i - 1));
+                }
+                text_start = i;
+                i = end + 1;
+                continue;
+            }
+
+            let reference: String = chars[i + 1..end].iter().collect();
+            let replacement = match self.resolve_reference(&reference) {
+                Reference::Value(value) => Some(value),
+                Reference::Drop => {
+                    has_dropped = true;
+                    Some("".to_owned())
+                }
+                Reference::DropLine => return None,
+                Reference::Skip => None,
+                Reference::Warn => {
+                    self.warnings.push(format!(
+                        "skipping reference to missing attribute: {reference}"
+                    ));
+                    None
+                }
+            };
+
+            if let Some(value) = replacement {
+                if text_start < i {
+                    segments.push(AttributeSegment::Text(text_start, i));
+                }
+                segments.push(AttributeSegment::Value(i, end + 1, value));
+                text_start = end + 1;
+            }
+            i = end + 1;
+        }
+
+        if text_start < chars.len() {
+            segments.push(AttributeSegment::Text(text_start, chars.len()));
+        }
+
+        let is_empty = segments.iter().all(|s| match s {
+            AttributeSegment::Text(start, end) => start == end,
+            AttributeSegment::Value(_, _, value) => value.is_empty(),
+        });
+        if has_dropped && is_empty {
+            return None;
+        }
+
+        Some(segments)
+    }
+
+    /// Returns the index of the closing brace of a reference opened at `start`.
+    pub(crate) fn find_reference(chars: &[char], start: usize) -> Option<usize> {
+        let end = start + chars[start..].iter().position(|&c| c == '}')?;
+        let reference: String = chars[start + 1..end].iter().collect();
+
+        let is_name = Self::is_valid_name(&reference);
+        let is_directive = reference
+            .strip_prefix("set:")
+            .is_some_and(|r| !r.is_empty());
+
+        (is_name || is_directive).then_some(end)
+    }
+
+    fn resolve_reference(&mut self, reference: &str) -> Reference {
+        if let Some(directive) = reference.strip_prefix("set:") {
+            return self.resolve_set_directive(directive);
+        }
+
+        if let Some(value) = self.get(reference) {
+            return Reference::Value(value.to_owned());
+        }
+
+        match self.attribute_missing() {
+            AttributeMissing::Drop => Reference::Drop,
+            AttributeMissing::DropLine => Reference::DropLine,
+            AttributeMissing::Skip => Reference::Skip,
+            AttributeMissing::Warn => Reference::Warn,
+        }
+    }
+
+    fn resolve_set_directive(&mut self, directive: &str) -> Reference {
+        let (name, value) = match directive.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (directive, None),
+        };
+
+        if let Some(unset) = name.strip_prefix('!').or(name.strip_suffix('!')) {
+            self.unset(unset);
+
+            return match self.attribute_undefined() {
+                AttributeUndefined::Drop => Reference::Drop,
+                AttributeUndefined::DropLine => Reference::DropLine,
+            };
+        }
+
+        self.set(name, value.unwrap_or(""));
+
+        Reference::Drop
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            && !name.contains(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
+    }
+}
+
+/// An attribute entry line, which sets or unsets a document attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AttributeEntryLine {
+    Set(String, String),
+    Unset(String),
+    /// The value continues on the next line.
+    Wrap,
+}
+
+/// Reads attribute entry lines like `:name: value` or `:name!:`, whose value
+/// may continue on the next lines after a trailing ` \`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AttributeEntryParser {
+    wrapped: Option<(String, String)>,
+}
+impl AttributeEntryParser {
+    /// Returns `Ok(None)` when `line` is not an attribute entry, and an error
+    /// when it is one with an invalid name.
+    pub(crate) fn parse_line(
+        &mut self,
+        line: &str,
+        line_number: usize,
+    ) -> Result<Option<AttributeEntryLine>, Error> {
+        // The name follows the colon which opens the line.
+        let invalid_name = |name: &str| Error::InvalidAttributeName {
+            name: name.to_owned(),
+            line: line_number,
+            column: 2,
+        };
+
+        if self.wrapped.is_some() {
+            return Ok(Some(self.parse_wrapped(line)));
+        }
+
+        if let Some((name, value)) = line.strip_prefix(':').and_then(|a| a.split_once(": ")) {
+            if !Attributes::is_valid_name(name) {
+                return Err(invalid_name(name));
+            }
+            self.wrapped = Some((name.to_owned(), "".to_owned()));
+
+            return Ok(Some(self.parse_wrapped(value)));
+        }
+
+        let Some(name) = line.strip_prefix(':').and_then(|a| a.strip_suffix(':')) else {
+            return Ok(None);
+        };
+        if let Some(unset) = name.strip_prefix('!').or(name.strip_suffix('!')) {
+            if !Attributes::is_valid_name(unset) {
+                return Err(invalid_name(name));
+            }
+
+            return Ok(Some(AttributeEntryLine::Unset(unset.to_owned())));
+        }
+        if !Attributes::is_valid_name(name) {
+            return Err(invalid_name(name));
+        }
+
+        Ok(Some(AttributeEntryLine::Set(
+            name.to_owned(),
+            "".to_owned(),
+        )))
+    }
+
+    /// Adds `line` to the value of the entry being read.
+    fn parse_wrapped(&mut self, line: &str) -> AttributeEntryLine {
+        let Some((name, value)) = self.wrapped.as_mut() else {
+            return AttributeEntryLine::Wrap;
+        };
+
+        if let Some(wrap_value) = line.strip_suffix(" + \\") {
+            value.push_str(wrap_value);
+            value.push('\n');
+
+            return AttributeEntryLine::Wrap;
+        }
+        if let Some(wrap_value) = line.strip_suffix(" \\") {
+            value.push_str(wrap_value);
+            value.push(' ');
+
+            return AttributeEntryLine::Wrap;
+        }
+
+        value.push_str(line);
+        let entry = AttributeEntryLine::Set(name.to_owned(), value.to_owned());
+        self.wrapped = None;
+
+        entry
+    }
+}
+
+enum Reference {
+    Value(String),
+    Drop,
+    DropLine,
+    Skip,
+    /// Skipped like [`Reference::Skip`], with a warning.
+    Warn,
+}
+
+/// Returns the date, time and year of `now` in UTC, formatted like Asciidoctor does.
+fn utc_date_time(now: SystemTime) -> (String, String, String) {
+    let seconds = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+
+    // Converts days since the Unix epoch to a proleptic Gregorian calendar date.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!(
+            "{:02}:{:02}:{:02} UTC",
+            seconds_of_day / 3600,
+            seconds_of_day % 3600 / 60,
+            seconds_of_day % 60
+        ),
+        format!("{:04}", year),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_and_time() {
+        let attributes =
+            Attributes::new_at(UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000));
+
+        assert_eq!(Some("2023-11-14"), attributes.get("localdate"));
+        assert_eq!(Some("22:13:20 UTC"), attributes.get("localtime"));
+        assert_eq!(Some("2023"), attributes.get("docyear"));
+    }
+
+    #[test]
+    fn attribute_reference() {
+        let mut attributes = Attributes::new();
+        attributes.set("url-repo", "https://my-git-repo.com");
+
+        assert_eq!(
+            "See https://my-git-repo.com[the repo]{sp}for details.",
+            attributes.substitute("See {url-repo}[the repo]\\{sp}for details.")
+        );
+    }
+
+    #[test]
+    fn missing_attribute_reference() {
+        let mut attributes = Attributes::new();
+        assert_eq!(
+            "first {missing}\nsecond",
+            attributes.substitute("first {missing}\nsecond")
+        );
+
+        attributes.set("attribute-missing", "drop");
+        assert_eq!(
+            "first \nsecond",
+            attributes.substitute("first {missing}\nsecond")
+        );
+
+        attributes.set("attribute-missing", "drop-line");
+        assert_eq!("second", attributes.substitute("first {missing}\nsecond"));
+        assert!(attributes.take_warnings().is_empty());
+
+        attributes.set("attribute-missing", "warn");
+        assert_eq!(
+            "first {missing}\nsecond",
+            attributes.substitute("first {missing}\nsecond")
+        );
+        assert_eq!(
+            vec!["skipping reference to missing attribute: missing"],
+            attributes.take_warnings()
+        );
+    }
+
+    #[test]
+    fn set_directive_reference() {
+        let mut attributes = Attributes::new();
+
+        assert_eq!(
+            "second",
+            attributes.substitute("{set:product:asciidoc2rs}\nsecond")
+        );
+        assert_eq!(Some("asciidoc2rs"), attributes.get("product"));
+
+        assert_eq!(
+            "second",
+            attributes.substitute("{set:product!} first\nsecond")
+        );
+        assert_eq!(None, attributes.get("product"));
+
+        attributes.set("attribute-undefined", "drop");
+        assert_eq!(" first", attributes.substitute("{set:!product} first"));
+    }
+
+    #[test]
+    fn attribute_entry_lines() {
+        let mut parser = AttributeEntryParser::default();
+        assert_eq!(
+            Some(AttributeEntryLine::Set(
+                "name".to_owned(),
+                "value".to_owned()
+            )),
+            parser.parse_line(":name: value", 1).unwrap()
+        );
+        assert_eq!(
+            Some(AttributeEntryLine::Set("flag".to_owned(), "".to_owned())),
+            parser.parse_line(":flag:", 1).unwrap()
+        );
+        assert_eq!(
+            Some(AttributeEntryLine::Unset("flag".to_owned())),
+            parser.parse_line(":flag!:", 1).unwrap()
+        );
+        assert_eq!(
+            Some(AttributeEntryLine::Wrap),
+            parser.parse_line(":long: first \\", 1).unwrap()
+        );
+        assert_eq!(
+            Some(AttributeEntryLine::Wrap),
+            parser.parse_line("second + \\", 1).unwrap()
+        );
+        assert_eq!(
+            Some(AttributeEntryLine::Set(
+                "long".to_owned(),
+                "first second\nthird".to_owned()
+            )),
+            parser.parse_line("third", 1).unwrap()
+        );
+        assert_eq!(None, parser.parse_line("text: value", 1).unwrap());
+        assert_eq!(
+            Err(Error::InvalidAttributeName {
+                name: "not valid".to_owned(),
+                line: 1,
+                column: 2
+            }),
+            parser.parse_line(":not valid: value", 1)
+        );
+    }
+}
@@ -2,27 +2,122 @@ mod block_break;
 mod block_leaf;
 mod block_macro;
 mod block_parent;
+mod callout;
 mod document;
 mod list;
+mod metadata;
+mod parser;
 mod section;
-
-pub(crate) use block_break::*;
-pub(crate) use block_leaf::*;
-pub(crate) use block_macro::*;
-pub(crate) use block_parent::*;
-pub(crate) use document::*;
-pub(crate) use list::*;
-pub(crate) use section::*;
+mod table;
+
+pub use block_break::*;
+pub use block_leaf::*;
+pub use block_macro::*;
+pub use block_parent::*;
+pub use callout::*;
+pub use document::*;
+pub use list::*;
+pub use metadata::*;
+pub(crate) use parser::*;
+pub use section::*;
+pub use table::*;
 
 use serde::{Deserialize, Serialize};
 
-use std::error::Error;
+use crate::asg::{AttributeEntryLine, Attributes, Inline, Location, LocationBoundary};
+
+use crate::Error;
+
+/// A problem in the source which the parser works around.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Warning {
+    /// Where the problem is, in the document or in an included file.
+    pub(crate) position: LocationBoundary,
+    pub(crate) message: String,
+}
+
+/// State shared by the block parsers while a document is being parsed.
+#[derive(Debug, Default)]
+pub(crate) struct Context {
+    pub(crate) attributes: Attributes,
+    /// Number of the line being parsed, starting at 1.
+    pub(crate) line_number: usize,
+    pub(crate) source: Option<String>,
+    pub(crate) warnings: Vec<Warning>,
+    pub(crate) callouts: Callouts,
+    /// The offset added to the level of the section headings of an included file.
+    pub(crate) level_offset: isize,
+}
+impl Context {
+    pub(crate) fn next_line(&mut self) {
+        self.line_number += 1;
+    }
+
+    /// Records a warning about the line being parsed.
+    pub(crate) fn warn(&mut self, message: String) {
+        self.warn_at(self.boundary(1), message);
+    }
+
+    pub(crate) fn warn_at(&mut self, position: LocationBoundary, message: String) {
+        self.warnings.push(Warning { position, message });
+    }
+
+    /// Records the warnings of the attribute references substituted so far.
+    pub(crate) fn warn_attributes(&mut self) {
+        for message in self.attributes.take_warnings() {
+            self.warn(message);
+        }
+    }
+
+    /// Sets or unsets a document attribute for the lines after an attribute entry.
+    pub(crate) fn apply_attribute_entry(&mut self, entry: AttributeEntryLine) {
+        match entry {
+            AttributeEntryLine::Set(name, value) => {
+                let value = self.attributes.substitute(&value);
+                self.attributes.set(&name, &value);
+            }
+            AttributeEntryLine::Unset(name) => self.attributes.unset(&name),
+            AttributeEntryLine::Wrap => {}
+        }
+    }
+
+    /// Parses a section heading, whose level is shifted by the level offset.
+    fn heading(&self, line: &str) -> Option<(usize, String)> {
+        let LineKind::HeadingMarker { level, title } = LineKind::parse(line.to_owned()) else {
+            return None;
+        };
+
+        Some((level.saturating_add_signed(self.level_offset), title))
+    }
+
+    /// Returns the boundary at `column` of the line being parsed.
+    pub(crate) fn boundary(&self, column: usize) -> LocationBoundary {
+        LocationBoundary::new(self.line_number, column, self.source.clone())
+    }
+
+    /// Returns the location of `line`, without its indentation and trailing spaces.
+    pub(crate) fn line_location(&self, line: &str) -> Location {
+        let indent = line.chars().count() - line.trim_indent().chars().count();
+        let end = line.trim_end().chars().count().max(indent + 1);
+
+        Location::new(self.boundary(indent + 1), self.boundary(end))
+    }
+
+    /// Returns the boundary at the first character of `text`, which is a suffix of `line`.
+    pub(crate) fn suffix_boundary(&self, line: &str, text: &str) -> LocationBoundary {
+        let column = line.trim_end().chars().count() - text.trim_end().chars().count() + 1;
+
+        self.boundary(column)
+    }
+}
 
 enum LineKind {
     Unknown,
     Empty,
     CommentMarker,
     CommentDelimiter(String),
+    ThematicBreak,
+    PageBreak,
     ExampleDelimiter(String),
     SidebarDelimiter(String),
     QuoteDelimiter(String),
@@ -30,6 +125,8 @@ enum LineKind {
     LiteralDelimiter(String),
     PassthroughDelimiter(String),
     OpenDelimiter(String),
+    /// A table delimiter, whose first character gives the format of the data.
+    TableDelimiter(String),
     HeadingMarker {
         level: usize,
         title: String,
@@ -42,7 +139,9 @@ enum LineKind {
         marker: String,
         principal: String,
     },
+    /// An ordered list item with an explicit number, like `4.`, `c.` or `iv)`.
     OffsetOrderedListMarker {
+        marker: String,
         offset: usize,
         principal: String,
     },
@@ -55,6 +154,15 @@ enum LineKind {
         term: String,
         principal: Option<String>,
     },
+    AdmonitionMarker {
+        variant: AdmonitionVariant,
+        text: String,
+    },
+    BlockMacro {
+        name: String,
+        target: String,
+        attrlist: String,
+    },
 }
 impl LineKind {
     fn parse(line: String) -> Self {
@@ -74,6 +182,19 @@ impl LineKind {
             }
         }
 
+        if line.starts_with("'''") && !line.contains(|c: char| c != '\'') {
+            return Self::ThematicBreak;
+        }
+
+        // Markdown-style thematic breaks.
+        if matches!(line, "---" | "- - -" | "***" | "* * *" | "___" | "_ _ _") {
+            return Self::ThematicBreak;
+        }
+
+        if line.starts_with("<<<") && !line.contains(|c: char| c != '<') {
+            return Self::PageBreak;
+        }
+
         if line.starts_with("====") && !line.contains(|c: char| c != '=') {
             return Self::ExampleDelimiter(line.to_owned());
         }
@@ -102,6 +223,29 @@ impl LineKind {
             return Self::OpenDelimiter(line.to_owned());
         }
 
+        if line.starts_with(['|', ',', ':', '!'])
+            && line.len() >= 4
+            && !line[1..].contains(|c: char| c != '=')
+        {
+            return Self::TableDelimiter(line.to_owned());
+        }
+
+        if let Some((name, rest)) = line.split_once("::") {
+            if let Some((target, attrlist)) = rest
+                .strip_suffix(']')
+                .and_then(|r| r.split_once('['))
+                .filter(|(t, _)| !t.starts_with(' ') && !t.ends_with(' '))
+            {
+                if BlockMacro::is_block_macro_name(name) {
+                    return Self::BlockMacro {
+                        name: name.to_owned(),
+                        target: target.to_owned(),
+                        attrlist: attrlist.to_owned(),
+                    };
+                }
+            }
+        }
+
         if let Some((marker, title)) = line.split_once("= ") {
             if marker.is_empty() || !marker.contains(|c: char| c != '=') {
                 return Self::HeadingMarker {
@@ -138,9 +282,24 @@ impl LineKind {
                 return Self::OrderedListMarker { marker, principal };
             }
 
-            let offset: usize = marker.parse().unwrap_or(0);
-            if 0 < offset && offset <= 9 {
-                return Self::OffsetOrderedListMarker { offset, principal };
+            marker.push('.');
+            if let Some((_, offset)) = NumberingStyle::parse_marker(&marker) {
+                return Self::OffsetOrderedListMarker {
+                    marker,
+                    offset,
+                    principal,
+                };
+            }
+        }
+
+        if let Some((list_marker, principal)) = line.split_once(") ") {
+            let marker = list_marker.trim_indent().to_owned() + ")";
+            if let Some((_, offset)) = NumberingStyle::parse_marker(&marker) {
+                return Self::OffsetOrderedListMarker {
+                    marker,
+                    offset,
+                    principal: principal.trim_start_matches(' ').to_owned(),
+                };
             }
         }
 
@@ -178,6 +337,26 @@ impl LineKind {
             }
         }
 
+        if let Some((dlist_term, primary_text)) = line.split_once(";; ") {
+            let term = dlist_term.trim_indent();
+            if !term.is_empty() && !term.ends_with([' ', ';']) {
+                return Self::DescriptionListMarker {
+                    marker: ";;".to_owned(),
+                    term: term.to_owned(),
+                    principal: Some(primary_text.trim_start_matches(' ').to_owned()),
+                };
+            }
+        }
+
+        if let Some((label, text)) = line.split_once(": ") {
+            if let Some(variant) = AdmonitionVariant::from_label(label) {
+                return Self::AdmonitionMarker {
+                    variant,
+                    text: text.trim_start_matches(' ').to_owned(),
+                };
+            }
+        }
+
         if line.ends_with("::") {
             let dlist_term = line.trim_indent();
             let term = dlist_term.trim_end_matches(':').to_owned();
@@ -190,9 +369,30 @@ impl LineKind {
             };
         }
 
+        if let Some(term) = line.trim_indent().strip_suffix(";;") {
+            if !term.is_empty() && !term.ends_with([' ', ';']) {
+                return Self::DescriptionListMarker {
+                    marker: ";;".to_owned(),
+                    term: term.to_owned(),
+                    principal: None,
+                };
+            }
+        }
+
         Self::Unknown
     }
 
+    fn is_list_item(&self) -> bool {
+        matches!(
+            self,
+            Self::UnorderedListMarker { .. }
+                | Self::OrderedListMarker { .. }
+                | Self::OffsetOrderedListMarker { .. }
+                | Self::CalloutListMarker { .. }
+                | Self::DescriptionListMarker { .. }
+        )
+    }
+
     fn block_delimiter(&self) -> Option<String> {
         match self {
             Self::ExampleDelimiter(x) => Some(x.to_owned()),
@@ -202,6 +402,7 @@ impl LineKind {
             Self::LiteralDelimiter(x) => Some(x.to_owned()),
             Self::PassthroughDelimiter(x) => Some(x.to_owned()),
             Self::OpenDelimiter(x) => Some(x.to_owned()),
+            Self::TableDelimiter(x) => Some(x.to_owned()),
             _ => None,
         }
     }
@@ -222,9 +423,25 @@ pub enum SectionBody {
     Block(Block),
     Section(Section),
 }
+impl SectionBody {
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::Block(block) => block.location(),
+            Self::Section(section) => section.location(),
+        }
+    }
+}
 
 type NonSectionBlockBody = Block;
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BlockForm {
+    Delimited,
+    Macro,
+    Paragraph,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum Block {
@@ -233,31 +450,199 @@ pub enum Block {
     BlockMacro(BlockMacro),
     BlockBreak(BlockBreak),
     AnyList(AnyList),
+    Table(Table),
 }
 impl Block {
     fn is_delimited_block(&self) -> bool {
         match self {
             Block::BlockParent(parent) => parent.delimiter().is_some(),
             Block::BlockLeaf(leaf) => leaf.delimiter().is_some(),
+            Block::Table(_) => true,
             _ => false,
         }
     }
 
+    /// Returns whether the next line belongs to an open delimited block,
+    /// which may be attached to the last item of a list.
+    fn is_in_delimited_block(&self) -> bool {
+        match self {
+            Block::AnyList(list) => list.is_in_delimited_block(),
+            _ => self.is_delimited_block(),
+        }
+    }
+
     fn delimiter(&self) -> Option<String> {
         match self {
             Block::BlockParent(parent) => parent.delimiter(),
             Block::BlockLeaf(leaf) => leaf.delimiter(),
+            Block::Table(table) => table.delimiter(),
+            _ => None,
+        }
+    }
+
+    /// Opens the delimited block started by `line`, if it is a block delimiter.
+    pub(crate) fn new_delimited(
+        line: &str,
+        style: Option<&str>,
+        context: &Context,
+    ) -> Option<Self> {
+        match LineKind::parse(line.to_owned()) {
+            LineKind::QuoteDelimiter(delimiter) if style == Some("verse") => {
+                Some(Self::new_verse(&delimiter, line, context))
+            }
+            LineKind::PassthroughDelimiter(delimiter)
+                if matches!(style, Some("stem" | "latexmath" | "asciimath")) =>
+            {
+                Some(Self::new_stem(&delimiter, line, context))
+            }
+            LineKind::ExampleDelimiter(delimiter)
+                if style.and_then(AdmonitionVariant::from_label).is_some() =>
+            {
+                let variant = style.and_then(AdmonitionVariant::from_label)?;
+
+                Some(Self::new_admonition(variant, &delimiter, line, context))
+            }
+            LineKind::ListingDelimiter(delimiter) => {
+                Some(Self::new_listing(&delimiter, line, context))
+            }
+            LineKind::LiteralDelimiter(delimiter) => {
+                Some(Self::new_literal(&delimiter, line, context))
+            }
+            LineKind::PassthroughDelimiter(delimiter) => {
+                Some(Self::new_pass(&delimiter, line, context))
+            }
+            LineKind::ExampleDelimiter(delimiter) => {
+                Some(Self::new_example(&delimiter, line, context))
+            }
+            LineKind::SidebarDelimiter(delimiter) => {
+                Some(Self::new_sidebar(&delimiter, line, context))
+            }
+            LineKind::QuoteDelimiter(delimiter) => Some(Self::new_quote(&delimiter, line, context)),
+            LineKind::OpenDelimiter(delimiter) => Some(Self::new_open(&delimiter, line, context)),
+            LineKind::TableDelimiter(delimiter) => Some(Self::new_table(&delimiter, line, context)),
             _ => None,
         }
     }
 
-    pub(crate) fn end(&mut self) {}
+    /// Applies the metadata lines read before the block.
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        match self {
+            Block::BlockParent(parent) => parent.set_metadata(pending, context),
+            Block::BlockLeaf(leaf) => leaf.set_metadata(pending, context),
+            Block::BlockMacro(block_macro) => block_macro.set_metadata(pending, context),
+            Block::BlockBreak(block_break) => block_break.set_metadata(pending, context),
+            Block::AnyList(list) => list.set_metadata(pending, context),
+            Block::Table(table) => table.set_metadata(pending, context),
+        }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Block::BlockParent(parent) => parent.body().id(),
+            Block::BlockLeaf(leaf) => leaf.body().id(),
+            Block::BlockMacro(block_macro) => block_macro.body().id(),
+            Block::BlockBreak(block_break) => block_break.id(),
+            Block::AnyList(list) => list.id(),
+            Block::Table(table) => table.id(),
+        }
+    }
 
-    pub(crate) fn push(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+    pub fn title(&self) -> Option<&[Inline]> {
         match self {
-            Self::BlockLeaf(leaf) => leaf.push(line),
-            Self::AnyList(list) => list.push(line),
-            _ => Err("not implemented".into()),
+            Block::BlockParent(parent) => parent.body().title(),
+            Block::BlockLeaf(leaf) => leaf.body().title(),
+            Block::BlockMacro(block_macro) => block_macro.body().title(),
+            Block::BlockBreak(block_break) => block_break.title(),
+            Block::AnyList(list) => list.title(),
+            Block::Table(table) => table.title(),
+        }
+    }
+
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        match self {
+            Block::BlockParent(parent) => parent.body_mut().title_mut(),
+            Block::BlockLeaf(leaf) => leaf.body_mut().title_mut(),
+            Block::BlockMacro(block_macro) => block_macro.body_mut().title_mut(),
+            Block::BlockBreak(block_break) => block_break.title_mut(),
+            Block::AnyList(list) => list.title_mut(),
+            Block::Table(table) => table.title_mut(),
+        }
+    }
+
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        match self {
+            Block::BlockParent(parent) => parent.body().reftext(),
+            Block::BlockLeaf(leaf) => leaf.body().reftext(),
+            Block::BlockMacro(block_macro) => block_macro.body().reftext(),
+            Block::BlockBreak(block_break) => block_break.reftext(),
+            Block::AnyList(list) => list.reftext(),
+            Block::Table(table) => table.reftext(),
+        }
+    }
+
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        match self {
+            Block::BlockParent(parent) => parent.body().metadata(),
+            Block::BlockLeaf(leaf) => leaf.body().metadata(),
+            Block::BlockMacro(block_macro) => block_macro.metadata(),
+            Block::BlockBreak(block_break) => block_break.metadata(),
+            Block::AnyList(list) => list.metadata(),
+            Block::Table(table) => table.metadata(),
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Block::BlockParent(parent) => parent.location(),
+            Block::BlockLeaf(leaf) => leaf.location(),
+            Block::BlockMacro(block_macro) => block_macro.location(),
+            Block::BlockBreak(block_break) => block_break.location(),
+            Block::AnyList(list) => list.location(),
+            Block::Table(table) => table.location(),
+        }
+    }
+
+    pub(crate) fn end(&mut self, context: &mut Context) {
+        match self {
+            Self::BlockParent(parent) => parent.end(context),
+            Self::BlockLeaf(leaf) => leaf.end(context),
+            Self::AnyList(list) => list.end(context),
+            Self::Table(table) => table.end(context),
+            _ => {}
+        }
+    }
+
+    /// Ends a delimited block at its closing delimiter `line`.
+    pub(crate) fn close(&mut self, line: &str, context: &mut Context) {
+        match self {
+            Self::BlockParent(parent) => parent.close(line, context),
+            Self::BlockLeaf(leaf) => leaf.close(line, context),
+            Self::Table(table) => table.close(line, context),
+            _ => {}
+        }
+        self.end(context);
+    }
+
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<(), Error> {
+        match self {
+            Self::BlockParent(parent) => parent.push(line, context),
+            Self::BlockLeaf(leaf) => leaf.push(line, context),
+            Self::Table(table) => {
+                table.push(line, context);
+
+                Ok(())
+            }
+            Self::AnyList(list) => match list.push(line, context)? {
+                true => Ok(()),
+                false => Err(Error::UnexpectedLine {
+                    line: context.line_number,
+                    column: 1,
+                }),
+            },
+            _ => Err(Error::UnexpectedLine {
+                line: context.line_number,
+                column: 1,
+            }),
         }
     }
 }
@@ -319,8 +704,21 @@ mod tests {
             matches!(LineKind::parse("\t\t... ordered list".to_owned()), LineKind::OrderedListMarker { marker, principal } if marker == "..." && principal == "ordered list")
         );
         assert!(
-            matches!(LineKind::parse("  5. ordered list".to_owned()), LineKind::OffsetOrderedListMarker { offset, principal } if offset == 5 && principal == "ordered list")
+            matches!(LineKind::parse("  5. ordered list".to_owned()), LineKind::OffsetOrderedListMarker { marker, offset, principal } if marker == "5." && offset == 5 && principal == "ordered list")
+        );
+        assert!(
+            matches!(LineKind::parse("42. ordered list".to_owned()), LineKind::OffsetOrderedListMarker { offset, .. } if offset == 42)
+        );
+        assert!(
+            matches!(LineKind::parse("B. ordered list".to_owned()), LineKind::OffsetOrderedListMarker { offset, .. } if offset == 2)
+        );
+        assert!(
+            matches!(LineKind::parse("iv) ordered list".to_owned()), LineKind::OffsetOrderedListMarker { marker, offset, .. } if marker == "iv)" && offset == 4)
         );
+        assert!(matches!(
+            LineKind::parse("Dr. Who".to_owned()),
+            LineKind::Unknown
+        ));
     }
 
     #[test]
@@ -331,6 +729,64 @@ mod tests {
         assert!(
             matches!(LineKind::parse("term:: description list".to_owned()), LineKind::DescriptionListMarker { marker, term, principal } if marker == "::" && term == "term" && principal == Some("description list".to_owned()))
         );
+        assert!(
+            matches!(LineKind::parse("term::: description list".to_owned()), LineKind::DescriptionListMarker { marker, term, .. } if marker == ":::" && term == "term")
+        );
+        assert!(
+            matches!(LineKind::parse("term;; description list".to_owned()), LineKind::DescriptionListMarker { marker, term, principal } if marker == ";;" && term == "term" && principal == Some("description list".to_owned()))
+        );
+        assert!(
+            matches!(LineKind::parse("  term;;".to_owned()), LineKind::DescriptionListMarker { marker, term, principal } if marker == ";;" && term == "term" && principal.is_none())
+        );
+    }
+
+    #[test]
+    fn admonition_line_kind() {
+        assert!(
+            matches!(LineKind::parse("NOTE: admonition paragraph".to_owned()), LineKind::AdmonitionMarker { variant, text } if variant == AdmonitionVariant::Note && text == "admonition paragraph")
+        );
+        assert!(matches!(
+            LineKind::parse("Note: not an admonition".to_owned()),
+            LineKind::Unknown
+        ));
+    }
+
+    #[test]
+    fn break_line_kind() {
+        assert!(matches!(
+            LineKind::parse("'''".to_owned()),
+            LineKind::ThematicBreak
+        ));
+        assert!(matches!(
+            LineKind::parse("* * *".to_owned()),
+            LineKind::ThematicBreak
+        ));
+        assert!(matches!(
+            LineKind::parse("---".to_owned()),
+            LineKind::ThematicBreak
+        ));
+        assert!(matches!(
+            LineKind::parse("<<<".to_owned()),
+            LineKind::PageBreak
+        ));
+        assert!(matches!(
+            LineKind::parse("----".to_owned()),
+            LineKind::ListingDelimiter(_)
+        ));
+    }
+
+    #[test]
+    fn block_macro_line_kind() {
+        assert!(
+            matches!(LineKind::parse("image::sunset.jpg[Sunset,300,200]".to_owned()), LineKind::BlockMacro { name, target, attrlist } if name == "image" && target == "sunset.jpg" && attrlist == "Sunset,300,200")
+        );
+        assert!(
+            matches!(LineKind::parse("toc::[]".to_owned()), LineKind::BlockMacro { name, target, attrlist } if name == "toc" && target.is_empty() && attrlist.is_empty())
+        );
+        assert!(matches!(
+            LineKind::parse("unknown::target[]".to_owned()),
+            LineKind::Unknown
+        ));
     }
 
     #[test]
@@ -375,5 +831,16 @@ mod tests {
         assert!(
             matches!(LineKind::parse("--".to_owned()), LineKind::OpenDelimiter(x) if x == "--")
         );
+
+        assert!(
+            matches!(LineKind::parse("|===".to_owned()), LineKind::TableDelimiter(x) if x == "|===")
+        );
+        assert!(
+            matches!(LineKind::parse(",====".to_owned()), LineKind::TableDelimiter(x) if x == ",====")
+        );
+        assert!(matches!(
+            LineKind::parse("|==".to_owned()),
+            LineKind::Unknown
+        ));
     }
 }
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::Block;
-use crate::asg::{Location, NodeType};
+use super::{Block, BlockMetadata, Context, PendingMetadata};
+use crate::asg::{Inline, Location, NodeType};
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -11,29 +11,94 @@ pub enum BlockBreak {
     Break {
         #[serde(rename = "type")]
         node_type: NodeType,
+        id: Option<String>,
+        title: Option<Vec<Inline>>,
+        reftext: Option<Vec<Inline>>,
+        metadata: Option<BlockMetadata>,
         variant: BlockBreakVariant,
         location: Option<Location>,
     },
 }
 impl BlockBreak {
-    fn new_page() -> Self {
+    fn new_page(line: &str, context: &Context) -> Self {
         Self::Break {
             node_type: NodeType::Block,
+            id: None,
+            title: None,
+            reftext: None,
+            metadata: None,
             variant: BlockBreakVariant::Page,
-            location: None,
+            location: Some(context.line_location(line)),
         }
     }
 
-    fn new_thematic() -> Self {
+    fn new_thematic(line: &str, context: &Context) -> Self {
         Self::Break {
             node_type: NodeType::Block,
+            id: None,
+            title: None,
+            reftext: None,
+            metadata: None,
             variant: BlockBreakVariant::Thematic,
-            location: None,
+            location: Some(context.line_location(line)),
+        }
+    }
+
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        let Self::Break {
+            id,
+            title,
+            reftext,
+            metadata,
+            ..
+        } = self;
+        (*id, *title, *reftext, *metadata) = pending.into_fields(context);
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        let Self::Break { id, .. } = self;
+
+        id.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&[Inline]> {
+        let Self::Break { title, .. } = self;
+
+        title.as_deref()
+    }
+
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        let Self::Break { title, .. } = self;
+
+        title.as_mut()
+    }
+
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        let Self::Break { reftext, .. } = self;
+
+        reftext.as_deref()
+    }
+
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        let Self::Break { metadata, .. } = self;
+
+        metadata.as_ref()
+    }
+
+    pub fn variant(&self) -> &BlockBreakVariant {
+        match self {
+            BlockBreak::Break { variant, .. } => variant,
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            BlockBreak::Break { location, .. } => location.as_ref(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum BlockBreakVariant {
     Page,
@@ -41,11 +106,11 @@ pub enum BlockBreakVariant {
 }
 
 impl Block {
-    fn new_page_break() -> Self {
-        Self::BlockBreak(BlockBreak::new_page())
+    pub(crate) fn new_page_break(line: &str, context: &Context) -> Self {
+        Self::BlockBreak(BlockBreak::new_page(line, context))
     }
 
-    fn new_thematic_break() -> Self {
-        Self::BlockBreak(BlockBreak::new_thematic())
+    pub(crate) fn new_thematic_break(line: &str, context: &Context) -> Self {
+        Self::BlockBreak(BlockBreak::new_thematic(line, context))
     }
 }
@@ -1,10 +1,10 @@
-use std::error::Error;
+use crate::Error;
 
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::Block;
-use crate::asg::{Inline, Location, NodeType};
+use super::{Block, BlockForm, BlockMetadata, Callout, Context, PendingMetadata, TrimIndent};
+use crate::asg::{Inline, Location, LocationBoundary, NodeType};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "name", rename_all = "camelCase")]
@@ -18,38 +18,126 @@ pub enum BlockLeaf {
 }
 
 impl BlockLeaf {
-    fn new_listing() -> Self {
-        Self::Listing(BlockLeafBody::new())
+    fn new_listing(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Listing(BlockLeafBody::new_delimited(delimiter, line, context))
     }
 
-    fn new_literal() -> Self {
-        Self::Literal(BlockLeafBody::new())
+    fn new_literal(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Literal(BlockLeafBody::new_delimited(delimiter, line, context))
     }
 
-    fn new_paragraph(line: &str) -> Self {
-        Self::Paragraph(BlockLeafBody::new_text(line))
+    fn new_paragraph(line: &str, context: &Context) -> Self {
+        Self::Paragraph(BlockLeafBody::new_text(line, context))
     }
 
-    fn new_pass() -> Self {
-        Self::Pass(BlockLeafBody::new())
+    fn new_paragraph_suffix(text: &str, line: &str, context: &Context) -> Self {
+        let mut body = BlockLeafBody::new_text(text, context);
+        body.location = Some(Location::new(
+            context.suffix_boundary(line, text),
+            context.line_location(line).end().clone(),
+        ));
+
+        Self::Paragraph(body)
+    }
+
+    fn new_pass(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Pass(BlockLeafBody::new_delimited(delimiter, line, context))
     }
 
-    fn new_stem() -> Self {
-        Self::Stem(BlockLeafBody::new())
+    fn new_stem(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Stem(BlockLeafBody::new_delimited(delimiter, line, context))
     }
 
-    fn new_verse() -> Self {
-        Self::Verse(BlockLeafBody::new())
+    fn new_verse(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Verse(BlockLeafBody::new_delimited(delimiter, line, context))
     }
 
-    pub(crate) fn push(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+    pub(crate) fn push(&mut self, line: &str, context: &Context) -> Result<(), Error> {
+        let body = self.body_mut();
+        body.lines.push(line.to_owned());
+        body.extend_location(line, context);
+
+        Ok(())
+    }
+
+    /// Extends the location of a delimited block up to its closing delimiter `line`.
+    pub(crate) fn close(&mut self, line: &str, context: &Context) {
+        self.body_mut().extend_location(line, context);
+    }
+
+    pub(crate) fn end(&mut self, context: &mut Context) {
         match self {
             Self::Paragraph(body) => {
-                body.inlines = Inline::append(body.inlines.clone(), line);
-
-                Ok(())
+                // The first line may be a suffix of the line read, after an admonition label.
+                let indent = body
+                    .lines
+                    .first()
+                    .map_or(0, |l| l.chars().count() - l.trim_indent().chars().count());
+                let start = body.location.as_ref().map(|l| l.start());
+                let start = LocationBoundary::new(
+                    start.map_or(context.line_number, |s| s.line()),
+                    start.map_or(1, |s| s.column() - indent),
+                    start.and_then(|s| s.source()).map(str::to_owned),
+                );
+                body.inlines = Inline::new(&body.lines.join("\n"), &start, &mut context.attributes);
+            }
+            Self::Verse(body) => {
+                let Some((line_number, lines)) = body.content() else {
+                    return;
+                };
+                let start = LocationBoundary::new(line_number, 1, context.source.clone());
+                body.inlines = Inline::new(&lines.join("\n"), &start, &mut context.attributes);
+            }
+            Self::Listing(body) | Self::Literal(body) => {
+                body.inlines = body.verbatim_inlines(context, Inline::new_verbatim_text);
+                if let Some((line_number, lines)) = body.content() {
+                    body.callouts = Callout::scan(&lines, line_number, context);
+                    context.callouts.push_block(&body.callouts);
+                }
+            }
+            Self::Pass(body) | Self::Stem(body) => {
+                body.inlines = body.verbatim_inlines(context, Inline::new_verbatim_raw);
             }
-            _ => Err("not implemented".into()),
+        }
+    }
+
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        self.body_mut().set_metadata(pending, context);
+        if let Self::Listing(body) = self {
+            body.set_default_language(context);
+        }
+    }
+
+    pub fn body(&self) -> &BlockLeafBody {
+        match self {
+            Self::Listing(body)
+            | Self::Literal(body)
+            | Self::Paragraph(body)
+            | Self::Pass(body)
+            | Self::Stem(body)
+            | Self::Verse(body) => body,
+        }
+    }
+
+    pub fn body_mut(&mut self) -> &mut BlockLeafBody {
+        match self {
+            Self::Listing(body)
+            | Self::Literal(body)
+            | Self::Paragraph(body)
+            | Self::Pass(body)
+            | Self::Stem(body)
+            | Self::Verse(body) => body,
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            BlockLeaf::Listing(BlockLeafBody { location, .. }) => location.as_ref(),
+            BlockLeaf::Literal(BlockLeafBody { location, .. }) => location.as_ref(),
+            BlockLeaf::Paragraph(BlockLeafBody { location, .. }) => location.as_ref(),
+            BlockLeaf::Pass(BlockLeafBody { location, .. }) => location.as_ref(),
+            BlockLeaf::Stem(BlockLeafBody { location, .. }) => location.as_ref(),
+            BlockLeaf::Verse(BlockLeafBody { location, .. }) => location.as_ref(),
         }
     }
 
@@ -70,60 +158,197 @@ impl BlockLeaf {
 pub struct BlockLeafBody {
     #[serde(rename = "type")]
     node_type: NodeType,
+    form: Option<BlockForm>,
     delimiter: Option<String>,
+    id: Option<String>,
     title: Option<Vec<Inline>>,
+    reftext: Option<Vec<Inline>>,
+    metadata: Option<BlockMetadata>,
     inlines: Vec<Inline>,
     location: Option<Location>,
+
+    /// Raw lines of the content, turned into `inlines` when the block ends.
+    #[serde(skip)]
+    lines: Vec<String>,
+    /// The callout markers of a verbatim block, which are kept in `inlines`.
+    #[serde(skip)]
+    callouts: Vec<Callout>,
 }
 
 impl BlockLeafBody {
-    fn new() -> Self {
+    fn new_delimited(delimiter: &str, line: &str, context: &Context) -> Self {
         Self {
             node_type: NodeType::Block,
-            delimiter: None,
+            form: Some(BlockForm::Delimited),
+            delimiter: Some(delimiter.to_owned()),
+            id: None,
             title: None,
+            reftext: None,
+            metadata: None,
             inlines: Vec::with_capacity(0),
-            location: None,
+            location: Some(context.line_location(line)),
+            lines: Vec::with_capacity(0),
+            callouts: Vec::with_capacity(0),
         }
     }
 
-    fn new_text(line: &str) -> Self {
+    fn new_text(line: &str, context: &Context) -> Self {
         Self {
             node_type: NodeType::Block,
+            form: None,
             delimiter: None,
+            id: None,
             title: None,
-            inlines: Inline::new(line),
-            location: None,
+            reftext: None,
+            metadata: None,
+            inlines: Vec::with_capacity(0),
+            location: Some(context.line_location(line)),
+            lines: vec![line.to_owned()],
+            callouts: Vec::with_capacity(0),
         }
     }
 
-    pub(crate) fn inlines(&self) -> Vec<Inline> {
-        self.inlines.clone()
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        (self.id, self.title, self.reftext, self.metadata) = pending.into_fields(context);
+    }
+
+    /// Makes a listing block without a language a source block in the
+    /// `source-language` of the document, if it is set.
+    fn set_default_language(&mut self, context: &Context) {
+        let metadata = self.metadata.as_ref();
+        let style = metadata.and_then(|m| m.attribute("1"));
+        let has_language = metadata.and_then(|m| m.attribute("2")).is_some();
+        if style.is_some_and(|s| s != "source") || has_language {
+            return;
+        }
+        let Some(language) = context.attributes.get("source-language") else {
+            return;
+        };
+
+        let metadata = self.metadata.get_or_insert_with(Default::default);
+        metadata.set_attribute("1", "source");
+        metadata.set_attribute("language", language);
+    }
+
+    fn extend_location(&mut self, line: &str, context: &Context) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let end = context.line_location(line).end().clone();
+        match self.location.as_mut() {
+            Some(location) => location.set_end(end),
+            None => self.location = Some(context.line_location(line)),
+        }
+    }
+
+    /// Returns the number of the first line of the content and its lines,
+    /// without the blank lines around them and the trailing spaces.
+    fn content(&self) -> Option<(usize, Vec<&str>)> {
+        let first = self.lines.iter().position(|l| !l.trim().is_empty())?;
+        let last = self.lines.iter().rposition(|l| !l.trim().is_empty())?;
+        let line_number = self.location.as_ref()?.start().line() + 1 + first;
+        let lines = self.lines[first..=last]
+            .iter()
+            .map(|l| l.trim_end())
+            .collect();
+
+        Some((line_number, lines))
+    }
+
+    /// Keeps the content as a single verbatim node, indentation included.
+    fn verbatim_inlines(
+        &self,
+        context: &Context,
+        new_inline: fn(&str, Location) -> Inline,
+    ) -> Vec<Inline> {
+        let Some((line_number, lines)) = self.content() else {
+            return Vec::with_capacity(0);
+        };
+        let source = context.source.clone();
+        let last_column = lines.last().map_or(1, |l| l.chars().count().max(1));
+        let location = Location::new(
+            LocationBoundary::new(line_number, 1, source.clone()),
+            LocationBoundary::new(line_number + lines.len() - 1, last_column, source),
+        );
+
+        vec![new_inline(&lines.join("\n"), location)]
+    }
+
+    pub fn form(&self) -> Option<BlockForm> {
+        self.form
+    }
+
+    pub fn delimiter(&self) -> Option<&str> {
+        self.delimiter.as_deref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&[Inline]> {
+        self.title.as_deref()
+    }
+
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        self.title.as_mut()
+    }
+
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        self.reftext.as_deref()
+    }
+
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns the callout markers of a listing or a literal block, which
+    /// are also kept in the text of its inlines.
+    pub fn callouts(&self) -> &[Callout] {
+        &self.callouts
+    }
+
+    pub fn inlines(&self) -> &[Inline] {
+        &self.inlines
+    }
+
+    pub fn inlines_mut(&mut self) -> &mut Vec<Inline> {
+        &mut self.inlines
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
     }
 }
 
 impl Block {
-    fn new_listing() -> Self {
-        Self::BlockLeaf(BlockLeaf::new_listing())
+    pub(crate) fn new_listing(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_listing(delimiter, line, context))
+    }
+
+    pub(crate) fn new_literal(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_literal(delimiter, line, context))
     }
 
-    fn new_literal() -> Self {
-        Self::BlockLeaf(BlockLeaf::new_literal())
+    pub(crate) fn new_paragraph(line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_paragraph(line, context))
     }
 
-    pub(crate) fn new_paragraph(line: &str) -> Self {
-        Self::BlockLeaf(BlockLeaf::new_paragraph(line))
+    /// Starts a paragraph whose first line of `text` is a suffix of `line`.
+    pub(crate) fn new_paragraph_suffix(text: &str, line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_paragraph_suffix(text, line, context))
     }
 
-    fn new_pass() -> Self {
-        Self::BlockLeaf(BlockLeaf::new_pass())
+    pub(crate) fn new_pass(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_pass(delimiter, line, context))
     }
 
-    fn new_stem() -> Self {
-        Self::BlockLeaf(BlockLeaf::new_stem())
+    pub(crate) fn new_stem(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_stem(delimiter, line, context))
     }
 
-    fn new_verse() -> Self {
-        Self::BlockLeaf(BlockLeaf::new_verse())
+    pub(crate) fn new_verse(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockLeaf(BlockLeaf::new_verse(delimiter, line, context))
     }
 }
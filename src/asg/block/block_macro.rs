@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::Block;
+use super::{Block, BlockForm, BlockMetadata, Context, PendingMetadata};
 use crate::asg::{Inline, Location, NodeType};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -13,20 +13,78 @@ pub enum BlockMacro {
     Toc(BlockMacroBody),
 }
 impl BlockMacro {
-    fn new_audio() -> Self {
-        Self::Audio(BlockMacroBody::new())
+    fn new_audio(target: &str, line: &str, context: &mut Context) -> Self {
+        Self::Audio(BlockMacroBody::new(target, line, context))
     }
 
-    fn new_video() -> Self {
-        Self::Video(BlockMacroBody::new())
+    fn new_video(target: &str, line: &str, context: &mut Context) -> Self {
+        Self::Video(BlockMacroBody::new(target, line, context))
     }
 
-    fn new_image() -> Self {
-        Self::Image(BlockMacroBody::new())
+    fn new_image(target: &str, line: &str, context: &mut Context) -> Self {
+        Self::Image(BlockMacroBody::new(target, line, context))
     }
 
-    fn new_toc() -> Self {
-        Self::Toc(BlockMacroBody::new())
+    fn new_toc(target: &str, line: &str, context: &mut Context) -> Self {
+        Self::Toc(BlockMacroBody::new(target, line, context))
+    }
+
+    /// Returns whether `name::target[]` lines are parsed as a block macro.
+    pub(crate) fn is_block_macro_name(name: &str) -> bool {
+        matches!(name, "audio" | "video" | "image" | "toc")
+    }
+
+    /// Returns the names given to the positional attributes of the macro `name`.
+    pub(crate) fn positional_names(name: &str) -> &'static [&'static str] {
+        match name {
+            "image" => &["alt", "width", "height"],
+            "video" => &["poster", "width", "height"],
+            _ => &[],
+        }
+    }
+
+    pub fn body(&self) -> &BlockMacroBody {
+        let (Self::Audio(body) | Self::Video(body) | Self::Image(body) | Self::Toc(body)) = self;
+
+        body
+    }
+
+    pub fn body_mut(&mut self) -> &mut BlockMacroBody {
+        let (Self::Audio(body) | Self::Video(body) | Self::Image(body) | Self::Toc(body)) = self;
+
+        body
+    }
+
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        match self {
+            BlockMacro::Audio(BlockMacroBody { metadata, .. })
+            | BlockMacro::Video(BlockMacroBody { metadata, .. })
+            | BlockMacro::Image(BlockMacroBody { metadata, .. })
+            | BlockMacro::Toc(BlockMacroBody { metadata, .. }) => metadata.as_ref(),
+        }
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            BlockMacro::Audio(BlockMacroBody { target, .. })
+            | BlockMacro::Video(BlockMacroBody { target, .. })
+            | BlockMacro::Image(BlockMacroBody { target, .. })
+            | BlockMacro::Toc(BlockMacroBody { target, .. }) => target.as_deref(),
+        }
+    }
+
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        let (Self::Audio(body) | Self::Video(body) | Self::Image(body) | Self::Toc(body)) = self;
+        (body.id, body.title, body.reftext, body.metadata) = pending.into_fields(context);
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            BlockMacro::Audio(BlockMacroBody { location, .. }) => location.as_ref(),
+            BlockMacro::Video(BlockMacroBody { location, .. }) => location.as_ref(),
+            BlockMacro::Image(BlockMacroBody { location, .. }) => location.as_ref(),
+            BlockMacro::Toc(BlockMacroBody { location, .. }) => location.as_ref(),
+        }
     }
 }
 
@@ -35,35 +93,84 @@ impl BlockMacro {
 pub struct BlockMacroBody {
     #[serde(rename = "type")]
     node_type: NodeType,
+    form: Option<BlockForm>,
     target: Option<String>,
+    id: Option<String>,
     title: Option<Vec<Inline>>,
+    reftext: Option<Vec<Inline>>,
+    metadata: Option<BlockMetadata>,
     location: Option<Location>,
 }
 impl BlockMacroBody {
-    fn new() -> Self {
+    fn new(target: &str, line: &str, context: &mut Context) -> Self {
+        let target = context.attributes.substitute(target);
+
         Self {
             node_type: NodeType::Block,
-            target: None,
+            form: Some(BlockForm::Macro),
+            target: (!target.is_empty()).then_some(target),
+            id: None,
             title: None,
-            location: None,
+            reftext: None,
+            metadata: None,
+            location: Some(context.line_location(line)),
         }
     }
-}
 
-impl Block {
-    fn new_audio() -> Self {
-        Self::BlockMacro(BlockMacro::new_audio())
+    pub fn form(&self) -> Option<BlockForm> {
+        self.form
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Replaces the target, like the path of an image.
+    pub fn set_target(&mut self, target: &str) {
+        self.target = (!target.is_empty()).then(|| target.to_owned());
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&[Inline]> {
+        self.title.as_deref()
+    }
+
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        self.title.as_mut()
     }
 
-    fn new_video() -> Self {
-        Self::BlockMacro(BlockMacro::new_video())
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        self.reftext.as_deref()
     }
 
-    fn new_image() -> Self {
-        Self::BlockMacro(BlockMacro::new_image())
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        self.metadata.as_ref()
     }
 
-    fn new_toc() -> Self {
-        Self::BlockMacro(BlockMacro::new_toc())
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+}
+
+impl Block {
+    /// Creates the block macro `name::target[]`, if it is a known one.
+    pub(crate) fn new_block_macro(
+        name: &str,
+        target: &str,
+        line: &str,
+        context: &mut Context,
+    ) -> Option<Self> {
+        let block_macro = match name {
+            "audio" => BlockMacro::new_audio(target, line, context),
+            "video" => BlockMacro::new_video(target, line, context),
+            "image" => BlockMacro::new_image(target, line, context),
+            "toc" => BlockMacro::new_toc(target, line, context),
+            _ => return None,
+        };
+
+        Some(Self::BlockMacro(block_macro))
     }
 }
@@ -1,28 +1,25 @@
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::{Block, NonSectionBlockBody};
+use crate::Error;
+
+use super::{
+    Block, BlockForm, BlockMetadata, BlockParser, Context, NonSectionBlockBody, PendingMetadata,
+};
 use crate::asg::{Inline, Location, NodeType};
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "name", rename_all = "camelCase")]
 pub enum BlockParent {
-    Admonition {
-        #[serde(rename = "type")]
-        node_type: NodeType,
-        delimiter: Option<String>,
-        title: Option<Vec<Inline>>,
-        blocks: Vec<NonSectionBlockBody>,
-        location: Option<Location>,
-        variant: AdmonitionVariant,
-    },
+    Admonition(BlockParentBody),
     Example(BlockParentBody),
     Sidebar(BlockParentBody),
     Open(BlockParentBody),
     Quote(BlockParentBody),
 }
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub enum AdmonitionVariant {
     Caution,
     Important,
@@ -30,43 +27,146 @@ pub enum AdmonitionVariant {
     Tip,
     Warning,
 }
+impl AdmonitionVariant {
+    /// Returns the variant named by a `NOTE:` label or a `[NOTE]` style.
+    pub(crate) fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "CAUTION" => Some(Self::Caution),
+            "IMPORTANT" => Some(Self::Important),
+            "NOTE" => Some(Self::Note),
+            "TIP" => Some(Self::Tip),
+            "WARNING" => Some(Self::Warning),
+            _ => None,
+        }
+    }
+
+    /// Returns the label of the variant, like `NOTE`.
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            Self::Caution => "CAUTION",
+            Self::Important => "IMPORTANT",
+            Self::Note => "NOTE",
+            Self::Tip => "TIP",
+            Self::Warning => "WARNING",
+        }
+    }
+}
+
 impl BlockParent {
-    fn new_admonition(variant: AdmonitionVariant) -> Self {
-        Self::Admonition {
+    fn new_admonition(
+        variant: AdmonitionVariant,
+        delimiter: &str,
+        line: &str,
+        context: &Context,
+    ) -> Self {
+        let mut body = BlockParentBody::new_delimited(delimiter, line, context);
+        body.variant = Some(variant);
+
+        Self::Admonition(body)
+    }
+
+    /// Starts an admonition paragraph, whose first line of `text` is a suffix of `line`.
+    fn new_admonition_paragraph(
+        variant: AdmonitionVariant,
+        text: &str,
+        line: &str,
+        context: &Context,
+    ) -> Self {
+        let paragraph = Block::new_paragraph_suffix(text, line, context);
+
+        Self::Admonition(BlockParentBody {
             node_type: NodeType::Block,
+            form: Some(BlockForm::Paragraph),
             delimiter: None,
+            id: None,
             title: None,
+            reftext: None,
+            metadata: None,
+            variant: Some(variant),
             blocks: Vec::with_capacity(0),
-            location: None,
-            variant,
+            location: Some(context.line_location(line)),
+            parser: Box::new(BlockParser::starting_with(paragraph, text)),
+        })
+    }
+
+    fn new_example(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Example(BlockParentBody::new_delimited(delimiter, line, context))
+    }
+
+    fn new_sidebar(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Sidebar(BlockParentBody::new_delimited(delimiter, line, context))
+    }
+
+    fn new_open(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Open(BlockParentBody::new_delimited(delimiter, line, context))
+    }
+
+    fn new_quote(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Quote(BlockParentBody::new_delimited(delimiter, line, context))
+    }
+
+    pub fn body(&self) -> &BlockParentBody {
+        match self {
+            Self::Admonition(body)
+            | Self::Example(body)
+            | Self::Sidebar(body)
+            | Self::Open(body)
+            | Self::Quote(body) => body,
+        }
+    }
+
+    pub fn body_mut(&mut self) -> &mut BlockParentBody {
+        match self {
+            Self::Admonition(body)
+            | Self::Example(body)
+            | Self::Sidebar(body)
+            | Self::Open(body)
+            | Self::Quote(body) => body,
         }
     }
 
-    fn new_example() -> Self {
-        Self::Example(BlockParentBody::new())
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        let body = self.body_mut();
+        (body.id, body.title, body.reftext, body.metadata) = pending.into_fields(context);
     }
 
-    fn new_sidebar() -> Self {
-        Self::Sidebar(BlockParentBody::new())
+    /// Feeds a line of the content to the parser of the nested blocks.
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<(), Error> {
+        let body = self.body_mut();
+        body.extend_location(line, context);
+
+        body.parser.push(line, context)
     }
 
-    fn new_open() -> Self {
-        Self::Open(BlockParentBody::new())
+    /// Extends the location of a delimited block up to its closing delimiter `line`.
+    pub(crate) fn close(&mut self, line: &str, context: &Context) {
+        self.body_mut().extend_location(line, context);
     }
 
-    fn new_quote() -> Self {
-        Self::Quote(BlockParentBody::new())
+    pub(crate) fn end(&mut self, context: &mut Context) {
+        let body = self.body_mut();
+        body.blocks = body.parser.end(context);
     }
 
     pub(crate) fn delimiter(&self) -> Option<String> {
         match self {
-            BlockParent::Admonition { delimiter, .. } => delimiter.to_owned(),
+            BlockParent::Admonition(BlockParentBody { delimiter, .. }) => delimiter.to_owned(),
             BlockParent::Example(BlockParentBody { delimiter, .. }) => delimiter.to_owned(),
             BlockParent::Sidebar(BlockParentBody { delimiter, .. }) => delimiter.to_owned(),
             BlockParent::Open(BlockParentBody { delimiter, .. }) => delimiter.to_owned(),
             BlockParent::Quote(BlockParentBody { delimiter, .. }) => delimiter.to_owned(),
         }
     }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            BlockParent::Admonition(BlockParentBody { location, .. }) => location.as_ref(),
+            BlockParent::Example(BlockParentBody { location, .. }) => location.as_ref(),
+            BlockParent::Sidebar(BlockParentBody { location, .. }) => location.as_ref(),
+            BlockParent::Open(BlockParentBody { location, .. }) => location.as_ref(),
+            BlockParent::Quote(BlockParentBody { location, .. }) => location.as_ref(),
+        }
+    }
 }
 
 #[skip_serializing_none]
@@ -74,41 +174,131 @@ impl BlockParent {
 pub struct BlockParentBody {
     #[serde(rename = "type")]
     node_type: NodeType,
+    form: Option<BlockForm>,
     delimiter: Option<String>,
+    id: Option<String>,
     title: Option<Vec<Inline>>,
+    reftext: Option<Vec<Inline>>,
+    metadata: Option<BlockMetadata>,
+    variant: Option<AdmonitionVariant>,
     blocks: Vec<NonSectionBlockBody>,
     location: Option<Location>,
+
+    /// Parses the nested blocks, which are moved to `blocks` when the block ends.
+    /// It is boxed since it is as large as the rest of the blocks put together.
+    #[serde(skip)]
+    parser: Box<BlockParser>,
 }
 impl BlockParentBody {
-    fn new() -> Self {
+    fn new_delimited(delimiter: &str, line: &str, context: &Context) -> Self {
         Self {
             node_type: NodeType::Block,
-            delimiter: None,
+            form: Some(BlockForm::Delimited),
+            delimiter: Some(delimiter.to_owned()),
+            id: None,
             title: None,
+            reftext: None,
+            metadata: None,
+            variant: None,
             blocks: Vec::with_capacity(0),
-            location: None,
+            location: Some(context.line_location(line)),
+            parser: Default::default(),
+        }
+    }
+
+    fn extend_location(&mut self, line: &str, context: &Context) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let end = context.line_location(line).end().clone();
+        if let Some(location) = self.location.as_mut() {
+            location.set_end(end);
         }
     }
+
+    pub fn form(&self) -> Option<BlockForm> {
+        self.form
+    }
+
+    pub fn delimiter(&self) -> Option<&str> {
+        self.delimiter.as_deref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&[Inline]> {
+        self.title.as_deref()
+    }
+
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        self.title.as_mut()
+    }
+
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        self.reftext.as_deref()
+    }
+
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns the variant of an admonition.
+    pub fn variant(&self) -> Option<AdmonitionVariant> {
+        self.variant
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut Vec<Block> {
+        &mut self.blocks
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
 }
 
 impl Block {
-    fn new_admonition(variant: AdmonitionVariant) -> Self {
-        Self::BlockParent(BlockParent::new_admonition(variant))
+    pub(crate) fn new_admonition(
+        variant: AdmonitionVariant,
+        delimiter: &str,
+        line: &str,
+        context: &Context,
+    ) -> Self {
+        Self::BlockParent(BlockParent::new_admonition(
+            variant, delimiter, line, context,
+        ))
+    }
+
+    pub(crate) fn new_admonition_paragraph(
+        variant: AdmonitionVariant,
+        text: &str,
+        line: &str,
+        context: &Context,
+    ) -> Self {
+        Self::BlockParent(BlockParent::new_admonition_paragraph(
+            variant, text, line, context,
+        ))
     }
 
-    fn new_example() -> Self {
-        Self::BlockParent(BlockParent::new_example())
+    pub(crate) fn new_example(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockParent(BlockParent::new_example(delimiter, line, context))
     }
 
-    fn new_sidebar() -> Self {
-        Self::BlockParent(BlockParent::new_sidebar())
+    pub(crate) fn new_sidebar(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockParent(BlockParent::new_sidebar(delimiter, line, context))
     }
 
-    fn new_open() -> Self {
-        Self::BlockParent(BlockParent::new_open())
+    pub(crate) fn new_open(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockParent(BlockParent::new_open(delimiter, line, context))
     }
 
-    fn new_quote() -> Self {
-        Self::BlockParent(BlockParent::new_quote())
+    pub(crate) fn new_quote(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::BlockParent(BlockParent::new_quote(delimiter, line, context))
     }
 }
@@ -0,0 +1,193 @@
+use super::{Context, Warning};
+use crate::asg::{Location, LocationBoundary};
+
+/// A callout marker at the end of a line of a verbatim block, like `<1>`,
+/// `// <2>` or `<.>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Callout {
+    /// The number of the marker, where the `<.>` ones are counted in the block.
+    pub(crate) number: usize,
+    /// The index of the line in the content of the block.
+    pub(crate) line_index: usize,
+    /// The location of the markers of the line, including a comment prefix.
+    pub(crate) location: Location,
+}
+impl Callout {
+    /// Scans the content `lines` of a verbatim block, whose first line is
+    /// `line_number`, for callout markers.
+    pub(crate) fn scan(lines: &[&str], line_number: usize, context: &Context) -> Vec<Self> {
+        let mut callouts = Vec::new();
+        let mut auto_number = 0;
+
+        for (line_index, text) in lines.iter().enumerate() {
+            let Some((column, numbers)) = scan_line(text) else {
+                continue;
+            };
+            let line = line_number + line_index;
+            let end = text.trim_end().chars().count();
+            let location = Location::new(
+                LocationBoundary::new(line, column, context.source.clone()),
+                LocationBoundary::new(line, end, context.source.clone()),
+            );
+
+            for number in numbers {
+                let number = number.unwrap_or_else(|| {
+                    auto_number += 1;
+                    auto_number
+                });
+                callouts.push(Self {
+                    number,
+                    line_index,
+                    location: location.clone(),
+                });
+            }
+        }
+
+        callouts
+    }
+
+    /// Returns the number of the marker, which the callout list item of the
+    /// same number explains.
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Returns the index of the line in the content of the block.
+    pub fn line_index(&self) -> usize {
+        self.line_index
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+/// Returns the column where the callout markers at the end of `line` start,
+/// and their numbers, which are `None` for `<.>`.
+fn scan_line(line: &str) -> Option<(usize, Vec<Option<usize>>)> {
+    let mut rest = line.trim_end();
+    let mut numbers = Vec::new();
+
+    loop {
+        let marker = match rest.strip_suffix("-->") {
+            Some(r) => r.rsplit_once("<!--"),
+            None => rest.strip_suffix('>').and_then(|r| r.rsplit_once('<')),
+        };
+        let Some((before, number)) = marker else {
+            break;
+        };
+        let number = match number {
+            "." => None,
+            n if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => Some(n.parse().ok()?),
+            _ => break,
+        };
+        // An escaped marker is kept as text, along with the ones before it.
+        if before.ends_with('\\') {
+            break;
+        }
+
+        numbers.push(number);
+        rest = before.strip_suffix(' ').unwrap_or(before);
+    }
+
+    if numbers.is_empty() {
+        return None;
+    }
+    numbers.reverse();
+
+    // A line comment of the source language may hide the markers.
+    let rest = ["//", "#", "--", ";;"]
+        .iter()
+        .find_map(|prefix| rest.strip_suffix(prefix))
+        .unwrap_or(rest);
+    let column =
+        rest.chars().count() + 1 + line[rest.len()..].chars().take_while(|c| *c == ' ').count();
+
+    Some((column, numbers))
+}
+
+/// The callout markers read since the last callout list, which has to
+/// explain them.
+#[derive(Debug, Default)]
+pub(crate) struct Callouts {
+    /// The number and the position of each marker.
+    pending: Vec<(usize, LocationBoundary)>,
+}
+impl Callouts {
+    pub(crate) fn push_block(&mut self, callouts: &[Callout]) {
+        self.pending.extend(
+            callouts
+                .iter()
+                .map(|c| (c.number, c.location.start().clone())),
+        );
+    }
+
+    /// Checks the numbers and the positions of the items of a callout list
+    /// against the markers read since the previous list.
+    pub(crate) fn check_list(&mut self, items: &[(usize, LocationBoundary)]) -> Vec<Warning> {
+        let mut warnings: Vec<_> = items
+            .iter()
+            .filter(|(number, _)| !self.pending.iter().any(|(n, _)| n == number))
+            .map(|(number, position)| Warning {
+                position: position.clone(),
+                message: format!("no callout found for <{}>", number),
+            })
+            .collect();
+        warnings.extend(
+            self.pending
+                .iter()
+                .filter(|(number, _)| !items.iter().any(|(n, _)| n == number))
+                .map(|(number, position)| Self::missing_item(*number, position.clone())),
+        );
+        self.pending.clear();
+
+        warnings
+    }
+
+    /// Reports the markers which no callout list followed.
+    pub(crate) fn end(&mut self) -> Vec<Warning> {
+        self.pending
+            .drain(..)
+            .map(|(number, position)| Self::missing_item(number, position))
+            .collect()
+    }
+
+    fn missing_item(number: usize, position: LocationBoundary) -> Warning {
+        Warning {
+            position,
+            message: format!("no callout list item found for <{}>", number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callout_markers() {
+        assert_eq!(Some((12, vec![Some(1)])), scan_line("let x = 1; <1>"));
+        assert_eq!(
+            Some((12, vec![Some(1), Some(2)])),
+            scan_line("let x = 1; // <1> <2>")
+        );
+        assert_eq!(Some((7, vec![None, None])), scan_line("x = 1 # <.><.>"));
+        assert_eq!(Some((6, vec![Some(3)])), scan_line("<a/> <!--3-->"));
+        assert_eq!(None, scan_line("<div>"));
+        assert_eq!(None, scan_line("escaped \\<1>"));
+    }
+
+    #[test]
+    fn callout_numbers() {
+        let context = Context::default();
+        let callouts = Callout::scan(&["a <.>", "b", "c <5> <.>"], 4, &context);
+
+        assert_eq!(
+            vec![(1, 0, 4), (5, 2, 6), (2, 2, 6)],
+            callouts
+                .iter()
+                .map(|c| (c.number, c.line_index, c.location.start().line()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,12 +1,15 @@
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::{Block, LineKind, Section, SectionBody};
-use crate::asg::{Inline, Location, NodeType};
+use super::{BlockParser, Context, Section, SectionBody, Warning};
+use crate::asg::{
+    AttributeEntryLine, AttributeEntryParser, Inline, Location, LocationBoundary, NodeType,
+};
+use crate::reader::SourceLine;
 use crate::Doctype;
 
+use crate::Error;
 use std::collections::HashMap;
-use std::error::Error;
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
@@ -26,17 +29,31 @@ pub struct Document {
     #[serde(skip)]
     is_started_body: bool,
     #[serde(skip)]
-    current_block: Option<Block>,
+    blocks_parser: BlockParser,
     #[serde(skip)]
-    comment_delimiter: Option<String>,
-    #[serde(skip)]
-    previous_line: String,
+    context: Context,
 }
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DocumentHeader {
     title: Vec<Inline>,
     location: Option<Location>,
+
+    #[serde(skip)]
+    title_source: String,
+}
+impl DocumentHeader {
+    pub fn title(&self) -> &[Inline] {
+        &self.title
+    }
+
+    pub fn title_mut(&mut self) -> &mut Vec<Inline> {
+        &mut self.title
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
 }
 impl Document {
     pub(crate) fn new(doctype: Doctype) -> Self {
@@ -50,37 +67,134 @@ impl Document {
             doctype,
             parser: Default::default(),
             is_started_body: false,
-            current_block: None,
-            comment_delimiter: None,
-            previous_line: "".to_owned(),
+            blocks_parser: Default::default(),
+            context: Default::default(),
         }
     }
 
     pub(crate) fn end(&mut self) {
-        if let Some(current) = self.current_block.as_mut() {
-            current.end();
-            self.blocks.push(SectionBody::Block(current.clone()));
-            self.current_block = None;
-        } else if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
-            last.end();
+        if !self.is_started_body {
+            self.end_header();
+        }
+
+        if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
+            last.end(&mut self.context);
+        } else {
+            self.end_preamble();
+        }
+        self.context.warn_attributes();
+        let warnings = self.context.callouts.end();
+        self.context.warnings.extend(warnings);
+
+        let header_location = self.header.as_ref().and_then(|h| h.location.as_ref());
+        let start = header_location
+            .or(self.blocks.first().and_then(|b| b.location()))
+            .map(|l| l.start());
+        let end = self
+            .blocks
+            .last()
+            .and_then(|b| b.location())
+            .or(header_location)
+            .map(|l| l.end());
+        if let (Some(start), Some(end)) = (start, end) {
+            self.location = Some(Location::new(start.clone(), end.clone()));
         }
     }
 
-    pub(crate) fn push(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+    pub fn header(&self) -> Option<&DocumentHeader> {
+        self.header.as_ref()
+    }
+
+    pub fn header_mut(&mut self) -> Option<&mut DocumentHeader> {
+        self.header.as_mut()
+    }
+
+    /// Returns the attributes set in the header, or `None` without a header.
+    pub fn attributes(&self) -> Option<&HashMap<String, String>> {
+        self.attributes.as_ref()
+    }
+
+    /// Returns the blocks of the preamble, followed by the sections.
+    pub fn blocks(&self) -> &[SectionBody] {
+        &self.blocks
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut Vec<SectionBody> {
+        &mut self.blocks
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    pub(crate) fn doctype(&self) -> &Doctype {
+        &self.doctype
+    }
+
+    pub(crate) fn set_source(&mut self, source: Option<String>) {
+        self.context.source = source;
+    }
+
+    /// Returns the warnings about the lines parsed so far.
+    pub(crate) fn warnings(&self) -> &[Warning] {
+        &self.context.warnings
+    }
+
+    /// Returns the parser state, which the preprocessor reads the attributes
+    /// from and reports its warnings to.
+    pub(crate) fn context_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    /// Parses a line read by the preprocessor, at the position it comes from.
+    pub(crate) fn push_source_line(&mut self, line: SourceLine) -> Result<(), Error> {
+        self.context.line_number = line.line_number - 1;
+        self.context.source = line.source;
+        self.context.level_offset = line.level_offset;
+
+        let result = self.push(&line.text);
+        self.context.warn_attributes();
+
+        result
+    }
+
+    pub(crate) fn push(&mut self, line: &str) -> Result<(), Error> {
+        self.context.next_line();
+
         if !self.is_started_body {
-            match self.parser.parse_line(line)? {
+            let kind = match self.parser.parse_line(line, self.context.line_number) {
+                // The header ends at a line which cannot be in it.
+                Err(error @ Error::InvalidHeader { .. }) => {
+                    self.is_started_body = true;
+                    self.end_header();
+                    self.push_body(line)?;
+
+                    return Err(error);
+                }
+                result => result?,
+            };
+            match kind {
                 HeaderLineKind::NotHeader => {
                     self.is_started_body = true;
+                    self.end_header();
+                    let result = self.push_body(line);
 
                     if matches!(self.doctype, Doctype::Manpage) {
-                        panic!("require document title for doctype-manpage");
+                        return Err(self.error_at_line(|line, column| {
+                            Error::MissingDocumentTitle { line, column }
+                        }));
                     }
+
+                    return result;
                 }
                 HeaderLineKind::End => {
                     self.is_started_body = true;
+                    self.end_header();
 
                     if matches!(self.doctype, Doctype::Manpage) && !self.parser.has_title {
-                        panic!("require document title for doctype-manpage");
+                        return Err(self.error_at_line(|line, column| {
+                            Error::MissingDocumentTitle { line, column }
+                        }));
                     }
 
                     return Ok(());
@@ -89,10 +203,10 @@ impl Document {
                     return Ok(());
                 }
                 HeaderLineKind::Title(document_title) => {
-                    let title = Inline::new(&document_title);
                     self.header = Some(DocumentHeader {
-                        title,
-                        location: None,
+                        title: Vec::with_capacity(0),
+                        location: Some(self.context.line_location(line)),
+                        title_source: document_title,
                     });
                     if self.attributes.is_none() {
                         self.attributes = Some(HashMap::new());
@@ -101,15 +215,20 @@ impl Document {
                     return Ok(());
                 }
                 HeaderLineKind::Authors(authors) => {
+                    self.extend_header_location(line);
                     self.set_authors(authors);
                     return Ok(());
                 }
                 HeaderLineKind::Revision(revnumber, revdate, revremark) => {
+                    self.extend_header_location(line);
+                    let revnumber = self.context.attributes.substitute(&revnumber);
                     self.set_value("revnumber".to_owned(), &revnumber);
                     if let Some(date) = revdate {
+                        let date = self.context.attributes.substitute(&date);
                         self.set_value("revdate".to_owned(), &date);
 
                         if let Some(remark) = revremark {
+                            let remark = self.context.attributes.substitute(&remark);
                             self.set_value("revremark".to_owned(), &remark);
                         }
                     }
@@ -117,289 +236,116 @@ impl Document {
                     return Ok(());
                 }
                 HeaderLineKind::UnsetAttribute(key) => {
+                    self.extend_header_location(line);
                     self.unset_value(key);
                     return Ok(());
                 }
                 HeaderLineKind::Attribute(key, value) => {
+                    self.extend_header_location(line);
+                    let value = self.context.attributes.substitute(&value);
                     self.set_value(key, &value);
                     return Ok(());
                 }
             }
         }
 
-        if self.comment_delimiter.is_some() {
-            if matches!(LineKind::parse(line.to_owned()), LineKind::CommentDelimiter(x) if matches!(&self.comment_delimiter, Some(y) if x == *y))
-            {
-                self.previous_line = "".to_owned();
-                self.comment_delimiter = None;
-            }
-
-            return Ok(());
-        }
-
-        if let Some(current) = self.current_block.as_mut() {
-            if current.is_delimited_block() {
-                self.previous_line = line.to_owned();
-
-                if LineKind::parse(line.to_owned()).block_delimiter() != current.delimiter() {
-                    current.push(line)?;
-
-                    return Ok(());
-                }
-
-                current.end();
-                self.blocks.push(SectionBody::Block(current.clone()));
-                self.current_block = None;
-
-                return Ok(());
-            }
-
-            if let Block::AnyList(_list) = current {
-                match LineKind::parse(line.to_owned()) {
-                    LineKind::Empty => {
-                        if self.previous_line == "//" {
-                            self.previous_line = "".to_owned();
-                            current.end();
-                            self.blocks.push(SectionBody::Block(current.clone()));
-                            self.current_block = None;
-
-                            return Ok(());
-                        }
-
-                        self.previous_line = "".to_owned();
-
-                        return Ok(());
-                    }
-                    LineKind::CommentMarker => {
-                        if self.previous_line.is_empty() {
-                            self.previous_line = "//".to_owned();
-                        }
-
-                        return Ok(());
-                    }
-                    _ => {
-                        if !self.previous_line.is_empty() {
-                            self.previous_line = line.to_owned();
-                            current.push(line)?;
-
-                            return Ok(());
-                        }
-
-                        current.end();
-                        self.blocks.push(SectionBody::Block(current.clone()));
-                        self.current_block = None;
-                    }
-                }
-            } else {
-                match LineKind::parse(line.to_owned()) {
-                    LineKind::Empty => {
-                        self.previous_line = "".to_owned();
-                        current.end();
-                        self.blocks.push(SectionBody::Block(current.clone()));
-                        self.current_block = None;
-
-                        return Ok(());
-                    }
-                    LineKind::CommentMarker => {
-                        self.previous_line = "".to_owned();
-
-                        return Ok(());
-                    }
-                    LineKind::CommentDelimiter(delimiter) => {
-                        self.comment_delimiter = Some(delimiter);
-
-                        current.end();
-                        self.blocks.push(SectionBody::Block(current.clone()));
-                        self.current_block = None;
-
-                        return Ok(());
-                    }
-                    _ => {
-                        self.previous_line = line.to_owned();
-
-                        return current.push(line);
-                    }
-                }
-            }
-        }
+        self.push_body(line)
+    }
 
+    fn push_body(&mut self, line: &str) -> Result<(), Error> {
         if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
-            match LineKind::parse(line.to_owned()) {
-                LineKind::HeadingMarker { level, title } => {
-                    if !self.previous_line.is_empty() {
-                        self.previous_line = line.to_owned();
-
-                        return last.push(line);
-                    }
+            if let Some((level, title)) = self.context.heading(line) {
+                if level <= last.level && last.is_at_block_boundary() {
+                    let metadata = last.take_metadata();
+                    last.end(&mut self.context);
+                    let mut section = Section::new(level, &title, &mut self.context);
+                    section.set_metadata(metadata, &mut self.context);
+                    self.blocks.push(SectionBody::Section(section));
 
                     if level == 0 && !matches!(self.doctype, Doctype::Book) {
-                        self.previous_line = line.to_owned();
-                        let section = Section::new(0, &title);
-                        self.blocks.push(SectionBody::Section(section));
-
-                        return Err("level 0 sections can only be used when doctype is book".into());
+                        return Err(self.error_at_line(|line, column| Error::Level0InArticle {
+                            line,
+                            column,
+                        }));
                     }
 
-                    if level <= last.level {
-                        self.previous_line = line.to_owned();
-                        let section = Section::new(level, &title);
-                        self.blocks.push(SectionBody::Section(section));
-
-                        return Ok(());
-                    }
+                    return Ok(());
                 }
-                _ => {}
             }
 
-            self.previous_line = line.to_owned();
-
-            return last.push(line);
+            return last.push(line, &mut self.context);
         }
 
-        self.parse_preamble(line)
-    }
-
-    fn parse_preamble(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
-        match LineKind::parse(line.to_owned()) {
-            LineKind::Empty => {
-                self.previous_line = "".to_owned();
-
-                Ok(())
-            }
-            LineKind::CommentMarker => {
-                self.previous_line = "".to_owned();
-
-                Ok(())
-            }
-            LineKind::CommentDelimiter(delimiter) => {
-                self.comment_delimiter = Some(delimiter);
-
-                Ok(())
-            }
-            LineKind::HeadingMarker { level, title } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
+        if let Some((level, title)) = self.context.heading(line) {
+            if self.blocks_parser.is_at_block_boundary() {
                 if level > 1 {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Err("cannot skip section level".into());
+                    self.blocks_parser.push(line, &mut self.context)?;
+
+                    return Err(
+                        self.error_at_line(|line, column| Error::SkippedSectionLevel {
+                            expected: 1,
+                            found: level,
+                            line,
+                            column,
+                        }),
+                    );
                 }
 
                 if level == 0 && !matches!(self.doctype, Doctype::Book) {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
+                    self.blocks_parser.push(line, &mut self.context)?;
 
-                    return Err("level 0 sections can only be used when doctype is book".into());
+                    return Err(
+                        self.error_at_line(|line, column| Error::Level0InArticle { line, column })
+                    );
                 }
 
-                self.previous_line = line.to_owned();
-                let section = Section::new(level, &title);
+                let metadata = self.blocks_parser.take_metadata();
+                self.end_preamble();
+                let mut section = Section::new(level, &title, &mut self.context);
+                section.set_metadata(metadata, &mut self.context);
                 self.blocks.push(SectionBody::Section(section));
 
-                Ok(())
-            }
-            LineKind::UnorderedListMarker { marker, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let unordered_list = Block::new_unordered_list(marker, principal);
-                self.current_block = Some(unordered_list);
-
-                Ok(())
-            }
-            LineKind::OrderedListMarker { marker, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let ordered_list = Block::new_ordered_list(marker, principal);
-                self.current_block = Some(ordered_list);
-
-                Ok(())
-            }
-            LineKind::OffsetOrderedListMarker { offset, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let ordered_list = Block::new_ordered_list(format!("{}.", offset), principal);
-                self.current_block = Some(ordered_list);
-
-                Ok(())
+                return Ok(());
             }
-            LineKind::CalloutListMarker { marker, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
+        }
 
-                    return Ok(());
-                }
+        self.blocks_parser.push(line, &mut self.context)
+    }
 
-                self.previous_line = line.to_owned();
-                let callout_list = Block::new_callout_list(marker, principal);
-                self.current_block = Some(callout_list);
+    /// Returns an error found at the start of the line being parsed.
+    fn error_at_line(&self, error: impl FnOnce(usize, usize) -> Error) -> Error {
+        error(self.context.line_number, 1)
+    }
 
-                Ok(())
-            }
-            LineKind::DescriptionListMarker {
-                marker,
-                term,
-                principal,
-            } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
+    /// Moves the blocks parsed so far before the sections.
+    fn end_preamble(&mut self) {
+        let blocks = self.blocks_parser.end(&mut self.context);
+        self.blocks
+            .extend(blocks.into_iter().map(SectionBody::Block));
+    }
 
-                    return Ok(());
+    fn end_header(&mut self) {
+        if let Some(header) = self.header.as_mut() {
+            // The title follows the `= ` marker at the start of the first header line.
+            let title_start = match header.location.as_ref().map(|l| l.start()) {
+                Some(start) => {
+                    LocationBoundary::new(start.line(), 3, start.source().map(str::to_owned))
                 }
+                None => self.context.boundary(3),
+            };
+            // The title is parsed last so it can refer to attributes defined below it.
+            header.title = Inline::new(
+                &header.title_source,
+                &title_start,
+                &mut self.context.attributes,
+            );
+        }
+    }
 
-                self.previous_line = line.to_owned();
-                let description_list = Block::new_description_list(marker, term, principal);
-                self.current_block = Some(description_list);
-
-                Ok(())
-            }
-            LineKind::Unknown => {
-                self.previous_line = line.to_owned();
-                let paragraph = Block::new_paragraph(line);
-                self.current_block = Some(paragraph);
-
-                Ok(())
-            }
-            _ => {
-                self.previous_line = line.to_owned();
-                let paragraph = Block::new_paragraph(line);
-                self.current_block = Some(paragraph);
-
-                Ok(())
-            }
+    fn extend_header_location(&mut self, line: &str) {
+        let end = self.context.line_location(line).end().clone();
+        if let Some(location) = self.header.as_mut().and_then(|h| h.location.as_mut()) {
+            location.set_end(end);
         }
     }
 
@@ -420,12 +366,14 @@ impl Document {
     }
 
     fn unset_value(&mut self, name: String) {
+        self.context.attributes.unset(&name);
         if let Some(attrs) = self.attributes.as_mut() {
             attrs.remove(&name.to_lowercase());
         }
     }
 
     fn set_value(&mut self, name: String, value: &str) {
+        self.context.attributes.set(&name, value);
         if let Some(attrs) = self.attributes.as_mut() {
             attrs.insert(name.to_lowercase(), value.to_owned());
         } else {
@@ -449,33 +397,20 @@ enum HeaderLineKind {
     Wrap,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct HeaderParser {
-    doctype: Doctype,
     has_title: bool,
     has_attr: bool,
     is_authors_line: bool,
     is_revision_line: bool,
-    wrapped_attr: Option<(String, String)>,
-}
-impl Default for HeaderParser {
-    fn default() -> Self {
-        Self::new(Doctype::Article)
-    }
+    attribute_entries: AttributeEntryParser,
+    /// Number of the line being parsed, for the errors.
+    line_number: usize,
 }
 impl HeaderParser {
-    fn new(doctype: Doctype) -> Self {
-        Self {
-            doctype,
-            has_title: false,
-            has_attr: false,
-            is_authors_line: false,
-            is_revision_line: false,
-            wrapped_attr: None,
-        }
-    }
+    fn parse_line(&mut self, line: &str, line_number: usize) -> Result<HeaderLineKind, Error> {
+        self.line_number = line_number;
 
-    fn parse_line(&mut self, line: &str) -> Result<HeaderLineKind, Box<dyn Error>> {
         if line.is_empty() {
             if self.has_title || self.has_attr {
                 return Ok(HeaderLineKind::End);
@@ -484,35 +419,26 @@ impl HeaderParser {
             return Ok(HeaderLineKind::Skip);
         }
 
-        match self.parse_attribute_line(line)? {
-            HeaderLineKind::Attribute(key, value) => {
+        if let Some(kind) = self.parse_attribute_line(line)? {
+            if !matches!(kind, HeaderLineKind::Wrap) {
                 self.has_attr = true;
-                return Ok(HeaderLineKind::Attribute(key, value));
-            }
-            HeaderLineKind::Wrap => {
-                return Ok(HeaderLineKind::Wrap);
             }
-            HeaderLineKind::Skip => {}
-            _ => panic!("not expected value"),
+            return Ok(kind);
         }
 
         if line.starts_with("//") && !line.starts_with("///") {
             return Ok(HeaderLineKind::Comment);
         }
 
-        match self.parse_implicit_line(line)? {
-            HeaderLineKind::Authors(a) => {
-                return Ok(HeaderLineKind::Authors(a));
-            }
-            HeaderLineKind::Revision(n, d, r) => {
-                return Ok(HeaderLineKind::Revision(n, d, r));
-            }
-            HeaderLineKind::End => {}
-            _ => panic!("not expected value"),
+        if let Some(kind) = self.parse_implicit_line(line)? {
+            return Ok(kind);
         }
 
         if self.has_title {
-            return Err("invalid document header".into());
+            return Err(Error::InvalidHeader {
+                line: self.line_number,
+                column: 1,
+            });
         }
 
         if let Some(document_title) = line.strip_prefix("= ") {
@@ -523,96 +449,37 @@ impl HeaderParser {
         Ok(HeaderLineKind::NotHeader)
     }
 
-    fn parse_attribute_line(&mut self, line: &str) -> Result<HeaderLineKind, Box<dyn Error>> {
-        let result = if self.wrapped_attr.is_some() {
-            self.parse_wrapped_attr(line)?
-        } else if let Some((attr_name, attr_value)) =
-            line.strip_prefix(':').and_then(|a| a.split_once(": "))
-        {
-            if Self::is_valid_attribute_name(attr_name.to_owned()) {
-                self.wrapped_attr = Some((attr_name.to_owned(), "".to_owned()));
-                self.parse_wrapped_attr(attr_value)?
-            } else {
-                return Err(format!("invalid document attribute: {}", attr_name).into());
-            }
-        } else if let Some(attr_name) = line.strip_prefix(':').and_then(|a| a.strip_suffix(':')) {
-            if let Some(unset_attr) = attr_name.strip_prefix('!').or(attr_name.strip_suffix('!')) {
-                if !Self::is_valid_attribute_name(unset_attr.to_owned()) {
-                    return Err(format!("invalid document attribute: {}", attr_name).into());
-                }
-                HeaderLineKind::UnsetAttribute(unset_attr.to_owned())
-            } else {
-                if !Self::is_valid_attribute_name(attr_name.to_owned()) {
-                    return Err(format!("invalid document attribute: {}", attr_name).into());
-                }
-                HeaderLineKind::Attribute(attr_name.to_owned(), "".to_owned())
-            }
-        } else {
-            HeaderLineKind::Skip
-        };
-
-        Ok(result)
-    }
-
-    fn is_valid_attribute_name(attr_name: String) -> bool {
-        attr_name.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
-            && !attr_name.contains(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
-    }
-
-    fn parse_wrapped_attr(&mut self, line: &str) -> Result<HeaderLineKind, Box<dyn Error>> {
-        let result = if let Some(wrap_value) = line.strip_suffix(" + \\") {
-            let mut value = wrap_value.to_owned();
-            value.push('\n');
-
-            let Some((_, current_value)) = self.wrapped_attr.as_mut() else {
-                panic!("cannot call");
-            };
-            current_value.push_str(&value);
-
-            HeaderLineKind::Wrap
-        } else if let Some(wrap_value) = line.strip_suffix(" \\") {
-            let mut value = wrap_value.to_owned();
-            value.push(' ');
-
-            let Some((_, current_value)) = self.wrapped_attr.as_mut() else {
-                panic!("cannot call");
-            };
-            current_value.push_str(&value);
-
-            HeaderLineKind::Wrap
-        } else {
-            let Some((key, value)) = self.wrapped_attr.as_mut() else {
-                panic!("cannot call");
-            };
-            value.push_str(line);
-            let attr = HeaderLineKind::Attribute(key.to_owned(), value.to_owned());
-            self.wrapped_attr = None;
-
-            attr
+    fn parse_attribute_line(&mut self, line: &str) -> Result<Option<HeaderLineKind>, Error> {
+        let kind = match self.attribute_entries.parse_line(line, self.line_number)? {
+            Some(AttributeEntryLine::Set(name, value)) => HeaderLineKind::Attribute(name, value),
+            Some(AttributeEntryLine::Unset(name)) => HeaderLineKind::UnsetAttribute(name),
+            Some(AttributeEntryLine::Wrap) => HeaderLineKind::Wrap,
+            None => return Ok(None),
         };
 
-        Ok(result)
+        Ok(Some(kind))
     }
 
-    fn parse_implicit_line(&mut self, line: &str) -> Result<HeaderLineKind, Box<dyn Error>> {
+    /// Parses the author line or the revision line, which follow the title.
+    fn parse_implicit_line(&mut self, line: &str) -> Result<Option<HeaderLineKind>, Error> {
         let result = if self.is_authors_line {
             self.is_authors_line = false;
             self.is_revision_line = true;
 
             let authors = self.parse_authors_line(line)?;
-            HeaderLineKind::Authors(authors)
+            Some(HeaderLineKind::Authors(authors))
         } else if self.is_revision_line {
             self.is_revision_line = false;
 
-            self.parse_revision_line(line)?
+            Some(self.parse_revision_line(line)?)
         } else {
-            HeaderLineKind::End
+            None
         };
 
         Ok(result)
     }
 
-    fn parse_authors_line(&mut self, line: &str) -> Result<Vec<Author>, Box<dyn Error>> {
+    fn parse_authors_line(&mut self, line: &str) -> Result<Vec<Author>, Error> {
         let split_authors: Vec<&str> = line.split_terminator(';').collect();
         let mut authors: Vec<Author> = Vec::with_capacity(split_authors.len());
         for author in split_authors {
@@ -622,7 +489,10 @@ impl HeaderParser {
                     if let Some(email) = e.strip_suffix('>') {
                         Author::new(a.to_owned(), Some(email.to_owned()))
                     } else {
-                        return Err("Invalid author format".into());
+                        return Err(Error::InvalidAuthor {
+                            line: self.line_number,
+                            column: 1,
+                        });
                     }
                 }
             };
@@ -633,12 +503,15 @@ impl HeaderParser {
         Ok(authors)
     }
 
-    fn parse_revision_line(&mut self, line: &str) -> Result<HeaderLineKind, Box<dyn Error>> {
+    fn parse_revision_line(&mut self, line: &str) -> Result<HeaderLineKind, Error> {
         let (revnumber, revdate, revremark) = match line.split_once(", ") {
             None => {
                 if let Some(revnumber) = line.strip_prefix('v') {
                     if !revnumber.contains(|c: char| c.is_ascii_digit()) {
-                        return Err("Invalid revision format".into());
+                        return Err(Error::InvalidRevision {
+                            line: self.line_number,
+                            column: 1,
+                        });
                     }
 
                     let (number, remark) = match revnumber.split_once(": ") {
@@ -648,12 +521,18 @@ impl HeaderParser {
 
                     (number, None, remark)
                 } else {
-                    return Err("Invalid revision format".into());
+                    return Err(Error::InvalidRevision {
+                        line: self.line_number,
+                        column: 1,
+                    });
                 }
             }
             Some((revnumber, revdate)) => {
                 if !revnumber.contains(|c: char| c.is_ascii_digit()) {
-                    return Err("Invalid revision format".into());
+                    return Err(Error::InvalidRevision {
+                        line: self.line_number,
+                        column: 1,
+                    });
                 }
 
                 let (date, remark) = match revdate.split_once(": ") {
@@ -686,27 +565,19 @@ impl Author {
     }
 }
 
-#[derive(Debug)]
-struct Revision {
-    number: String,
-    date: Option<String>,
-    remark: Option<String>,
-}
-impl Revision {
-    fn new(number: String, date: Option<String>, remark: Option<String>) -> Self {
-        Self {
-            number,
-            date,
-            remark,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use crate::asg::{
+        block::{Block, BlockLeaf},
+        inlines::tests::{inlines, without_locations},
+        Attributes,
+    };
+
     use super::*;
 
-    fn parse(text: &'static str) -> Result<Document, Box<dyn Error>> {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn parse(text: &'static str) -> Result<Document, Error> {
         let mut document = Document::new(Doctype::Article);
 
         for line in text.lines() {
@@ -722,8 +593,8 @@ mod tests {
         let document = parse("// this comment line is ignored\n= Document Title\nKismet R. Lee <kismet@asciidoctor.org>\n:description: The document's description.\n:sectanchors:\n:url-repo: https://my-git-repo.com\n\nThe document body starts here.").unwrap();
 
         assert_eq!(
-            Some(Inline::new("Document Title")),
-            document.header.map(|h| h.title)
+            Some(inlines("Document Title")),
+            document.header.map(|h| without_locations(&h.title))
         );
         let mut attrs = HashMap::new();
         attrs.insert("author".to_owned(), "Kismet R. Lee".to_owned());
@@ -739,12 +610,17 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn illegal_level0_section_block() {
-        parse(
+        let error = parse(
             "= Document Title\n\n= Illegal Level 0 Section (violates rule #1)\n\n== First Section",
         )
-        .unwrap();
+        .unwrap_err();
+
+        assert_eq!(Error::Level0InArticle { line: 3, column: 1 }, error);
+        assert_eq!(
+            "line 3, column 1: level 0 sections can only be used when doctype is book",
+            error.to_string()
+        );
     }
 
     #[test]
@@ -754,8 +630,8 @@ mod tests {
                 .unwrap();
 
         assert_eq!(
-            Some(Inline::new("The Intrepid Chronicles")),
-            document.header.map(|h| h.title)
+            Some(inlines("The Intrepid Chronicles")),
+            document.header.map(|h| without_locations(&h.title))
         );
         let mut attrs = HashMap::new();
         attrs.insert("author".to_owned(), "Kismet Lee".to_owned());
@@ -770,8 +646,8 @@ mod tests {
         let document = parse("= The Intrepid Chronicles\nKismet Lee\nv7.5").unwrap();
 
         assert_eq!(
-            Some(Inline::new("The Intrepid Chronicles")),
-            document.header.map(|h| h.title)
+            Some(inlines("The Intrepid Chronicles")),
+            document.header.map(|h| without_locations(&h.title))
         );
         let mut attrs = HashMap::new();
         attrs.insert("author".to_owned(), "Kismet Lee".to_owned());
@@ -781,18 +657,25 @@ mod tests {
 
     #[test]
     fn trim_prefix_char_revision_line() {
-        let document =
-            parse("= The Intrepid Chronicles\nKismet Lee\nLPR55, {docdate}: A Special ⚄ Edition")
-                .unwrap();
+        let mut document = Document::new(Doctype::Article);
+        // The clock is pinned so that the date does not change during the test.
+        document.context.attributes =
+            Attributes::new_at(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        for line in
+            "= The Intrepid Chronicles\nKismet Lee\nLPR55, {docdate}: A Special ⚄ Edition".lines()
+        {
+            document.push(line).unwrap();
+        }
+        document.end();
 
         assert_eq!(
-            Some(Inline::new("The Intrepid Chronicles")),
-            document.header.map(|h| h.title)
+            Some(inlines("The Intrepid Chronicles")),
+            document.header.map(|h| without_locations(&h.title))
         );
         let mut attrs = HashMap::new();
         attrs.insert("author".to_owned(), "Kismet Lee".to_owned());
         attrs.insert("revnumber".to_owned(), "55".to_owned());
-        attrs.insert("revdate".to_owned(), "{docdate}".to_owned());
+        attrs.insert("revdate".to_owned(), "2023-11-14".to_owned());
         attrs.insert("revremark".to_owned(), "A Special ⚄ Edition".to_owned());
         assert_eq!(Some(attrs), document.attributes);
     }
@@ -803,4 +686,169 @@ mod tests {
 
         assert_eq!(2, document.blocks.len());
     }
+
+    #[test]
+    fn attribute_references() {
+        let document = parse("= {product} Guide\n:product: asciidoc2rs\n:url-repo: https://github.com/kphrx/{product}\n\nSee {url-repo}.\n{undefined} is kept.").unwrap();
+
+        assert_eq!(
+            Some(inlines("asciidoc2rs Guide")),
+            document.header.map(|h| without_locations(&h.title))
+        );
+        assert_eq!(
+            Some(&"https://github.com/kphrx/asciidoc2rs".to_owned()),
+            document.attributes.as_ref().unwrap().get("url-repo")
+        );
+        let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(paragraph)))) =
+            document.blocks.first()
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(
+            inlines("See https://github.com/kphrx/asciidoc2rs.\n{undefined} is kept."),
+            without_locations(paragraph.inlines())
+        );
+    }
+
+    #[test]
+    fn node_locations() {
+        let document = parse(
+            "= Document Title\n:attr: value\n\n== Section\n\nFirst *line*\nsecond line\n\n* item",
+        )
+        .unwrap();
+        let location = |start: (usize, usize), end: (usize, usize)| {
+            Some(Location::new(
+                LocationBoundary::new(start.0, start.1, None),
+                LocationBoundary::new(end.0, end.1, None),
+            ))
+        };
+
+        assert_eq!(location((1, 1), (9, 6)), document.location);
+        assert_eq!(
+            location((1, 1), (2, 12)),
+            document.header.and_then(|h| h.location)
+        );
+
+        let Some(SectionBody::Section(section)) = document.blocks.first() else {
+            panic!("cannot call");
+        };
+        assert_eq!(location((4, 1), (9, 6)).as_ref(), section.location());
+        let Some(SectionBody::Block(paragraph)) = section.blocks.first() else {
+            panic!("cannot call");
+        };
+        assert_eq!(location((6, 1), (7, 11)).as_ref(), paragraph.location());
+        let Block::BlockLeaf(BlockLeaf::Paragraph(paragraph)) = paragraph else {
+            panic!("cannot call");
+        };
+        let inlines = paragraph.inlines();
+        assert_eq!(
+            vec![
+                location((6, 1), (6, 6)),
+                location((6, 7), (6, 12)),
+                location((6, 12), (7, 11))
+            ],
+            inlines
+                .iter()
+                .map(|i| i.location().cloned())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn callouts() {
+        let document = parse(
+            "----\nlet a = 1; // <1>\nlet b = 2; <.>\nlet c = 3; <3>\n----\n<1> a\n<2> b\n<4> d\n\n----\nlast <1>\n----",
+        )
+        .unwrap();
+
+        let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Listing(listing)))) =
+            document.blocks.first()
+        else {
+            panic!("not expected")
+        };
+        assert_eq!(
+            vec![(1, 0), (1, 1), (3, 2)],
+            listing
+                .callouts()
+                .iter()
+                .map(|c| (c.number, c.line_index))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![
+                (8, "callout list item index: expected 3, got 4".to_owned()),
+                (7, "no callout found for <2>".to_owned()),
+                (8, "no callout found for <4>".to_owned()),
+                (4, "no callout list item found for <3>".to_owned()),
+                (11, "no callout list item found for <1>".to_owned()),
+            ],
+            document
+                .warnings()
+                .iter()
+                .map(|w| (w.position.line(), w.message.clone()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn included_sections() {
+        let mut resolver = crate::MemoryResolver::new();
+        resolver.insert("chapters/one.adoc", "= One\n\ninclude::two.adoc[]");
+        resolver.insert("chapters/two.adoc", "== Two");
+
+        let document = crate::Parser::new("= Book\n\ninclude::chapters/one.adoc[leveloffset=+1]")
+            .with_include_resolver(resolver)
+            .parse_to_asg()
+            .unwrap();
+
+        let [SectionBody::Section(one)] = document.blocks.as_slice() else {
+            panic!("not expected")
+        };
+        assert_eq!(1, one.level);
+        assert_eq!(
+            serde_json::json!({"line": 1, "col": 3, "file": ["chapters/one.adoc"]}),
+            serde_json::to_value(one).unwrap()["title"][0]["location"][0]
+        );
+
+        let [SectionBody::Section(two)] = one.blocks.as_slice() else {
+            panic!("not expected")
+        };
+        assert_eq!(2, two.level);
+        assert_eq!(
+            Some(&LocationBoundary::new(
+                1,
+                1,
+                Some("chapters/two.adoc".to_owned())
+            )),
+            two.location().map(|l| l.start())
+        );
+    }
+
+    #[test]
+    fn body_attribute_entries() {
+        let document = crate::Parser::new(
+            "= Document\n:product: Alpha\n\n{product}\n\n:product: Beta\n:source-language: rust\n\n{product}\n\nifdef::source-language[]\n----\nfn main() {}\n----\nendif::[]\n\n:product!:\n:source-language!:\n{product}",
+        )
+        .parse_to_asg()
+        .unwrap();
+
+        assert_eq!(
+            Some(HashMap::from([("product".to_owned(), "Alpha".to_owned())])),
+            document.attributes
+        );
+
+        let [SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(alpha))), SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(beta))), SectionBody::Block(Block::BlockLeaf(BlockLeaf::Listing(listing))), SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(missing)))] =
+            document.blocks.as_slice()
+        else {
+            panic!("not expected")
+        };
+        assert_eq!(inlines("Alpha"), without_locations(alpha.inlines()));
+        assert_eq!(inlines("Beta"), without_locations(beta.inlines()));
+        assert_eq!(inlines("{product}"), without_locations(missing.inlines()));
+        assert_eq!(
+            Some("rust"),
+            listing.metadata().and_then(|m| m.attribute("language"))
+        );
+    }
 }
@@ -1,10 +1,13 @@
-use std::error::Error;
+use crate::Error;
 
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::{Block, NonSectionBlockBody, TrimIndent};
-use crate::asg::{Inline, Location, NodeType};
+use super::{
+    Block, BlockMetadata, BlockParser, Context, LineKind, NonSectionBlockBody, PendingMetadata,
+    TrimIndent,
+};
+use crate::asg::{Inline, Location, LocationBoundary, NodeType};
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -15,7 +18,12 @@ pub enum AnyList {
         node_type: NodeType,
         variant: ListVariant,
         marker: String,
+        id: Option<String>,
         title: Option<Vec<Inline>>,
+        reftext: Option<Vec<Inline>>,
+        metadata: Option<BlockMetadata>,
+        /// The number of the first item of an ordered list.
+        start: Option<i64>,
         location: Option<Location>,
         items: Vec<ListItem>,
     },
@@ -23,166 +31,760 @@ pub enum AnyList {
         #[serde(rename = "type")]
         node_type: NodeType,
         marker: String,
+        id: Option<String>,
         title: Option<Vec<Inline>>,
+        reftext: Option<Vec<Inline>>,
+        metadata: Option<BlockMetadata>,
         location: Option<Location>,
         items: Vec<DlistItem>,
-
-        #[serde(skip)]
-        current_terms: Vec<String>,
     },
 }
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ListVariant {
     Callout,
     Ordered,
     Unordered,
 }
+/// The numbering style of an ordered list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberingStyle {
+    Arabic,
+    Decimal,
+    Loweralpha,
+    Upperalpha,
+    Lowerroman,
+    Upperroman,
+    Lowergreek,
+}
+impl NumberingStyle {
+    /// Returns the style named by a block style like `[loweralpha]`.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "arabic" => Some(Self::Arabic),
+            "decimal" => Some(Self::Decimal),
+            "loweralpha" => Some(Self::Loweralpha),
+            "upperalpha" => Some(Self::Upperalpha),
+            "lowerroman" => Some(Self::Lowerroman),
+            "upperroman" => Some(Self::Upperroman),
+            "lowergreek" => Some(Self::Lowergreek),
+            _ => None,
+        }
+    }
+
+    /// Returns the default style of a list whose marker is `depth` dots.
+    fn from_depth(depth: usize) -> Self {
+        match depth % 5 {
+            1 => Self::Arabic,
+            2 => Self::Loweralpha,
+            3 => Self::Lowerroman,
+            4 => Self::Upperalpha,
+            _ => Self::Upperroman,
+        }
+    }
+
+    /// Returns the style and the number of an explicit marker, like `4.`,
+    /// `c.` or `iv)`.
+    pub(crate) fn parse_marker(marker: &str) -> Option<(Self, usize)> {
+        if let Some(number) = marker.strip_suffix('.') {
+            if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                return number.parse().ok().map(|n| (Self::Arabic, n));
+            }
+
+            let mut chars = number.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c @ 'a'..='z'), None) => {
+                    Some((Self::Loweralpha, c as usize - 'a' as usize + 1))
+                }
+                (Some(c @ 'A'..='Z'), None) => {
+                    Some((Self::Upperalpha, c as usize - 'A' as usize + 1))
+                }
+                _ => None,
+            };
+        }
+
+        let number = marker.strip_suffix(')')?;
+        let value = roman_value(&number.to_ascii_lowercase())?;
+        if number.chars().all(|c| c.is_ascii_lowercase()) {
+            Some((Self::Lowerroman, value))
+        } else if number.chars().all(|c| c.is_ascii_uppercase()) {
+            Some((Self::Upperroman, value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the name of the style, as in a block style.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Arabic => "arabic",
+            Self::Decimal => "decimal",
+            Self::Loweralpha => "loweralpha",
+            Self::Upperalpha => "upperalpha",
+            Self::Lowerroman => "lowerroman",
+            Self::Upperroman => "upperroman",
+            Self::Lowergreek => "lowergreek",
+        }
+    }
+
+    /// Formats `number` in this style, without the trailing `.` or `)`.
+    fn format(&self, number: usize) -> String {
+        match self {
+            Self::Arabic => number.to_string(),
+            Self::Decimal => format!("{:02}", number),
+            Self::Loweralpha => alpha(number).to_ascii_lowercase(),
+            Self::Upperalpha => alpha(number),
+            Self::Lowerroman => roman(number).to_ascii_lowercase(),
+            Self::Upperroman => roman(number),
+            Self::Lowergreek => char::from_u32('α' as u32 + (number as u32).saturating_sub(1))
+                .map_or_else(|| number.to_string(), |c| c.to_string()),
+        }
+    }
+}
+
+const ROMAN_NUMERALS: [(usize, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// Returns the value of the lowercase roman numeral `numeral`.
+fn roman_value(numeral: &str) -> Option<usize> {
+    let mut rest = numeral;
+    let mut value = 0;
+    for (number, digits) in ROMAN_NUMERALS {
+        while let Some(r) = rest.strip_prefix(&digits.to_ascii_lowercase()) {
+            value += number;
+            rest = r;
+        }
+    }
+
+    (rest.is_empty() && value > 0).then_some(value)
+}
+
+fn roman(mut number: usize) -> String {
+    let mut numeral = String::new();
+    for (value, digits) in ROMAN_NUMERALS {
+        while number >= value {
+            numeral.push_str(digits);
+            number -= value;
+        }
+    }
+
+    numeral
+}
+
+fn alpha(number: usize) -> String {
+    match number {
+        1..=26 => ((b'A' + (number - 1) as u8) as char).to_string(),
+        _ => number.to_string(),
+    }
+}
+
+/// The style of a description list, given by its block style.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DlistStyle {
+    /// `[horizontal]`, which lays out the terms and descriptions side by side.
+    Horizontal,
+    /// `[qanda]`, whose terms are questions and descriptions answers.
+    Qanda,
+}
+
 impl AnyList {
-    fn new_callout_list(marker: String, principal: String) -> Self {
-        let mut items = Vec::with_capacity(1);
-        items.push(ListItem::new(marker.clone(), Inline::new(&principal)));
+    fn new_list(
+        variant: ListVariant,
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        let item = ListItem::new(marker.clone(), &principal, line, context);
 
         Self::List {
             node_type: NodeType::Block,
-            variant: ListVariant::Callout,
+            variant,
             marker,
+            id: None,
             title: None,
-            location: None,
-            items,
+            reftext: None,
+            metadata: None,
+            start: None,
+            location: item.location.clone(),
+            items: vec![item],
         }
     }
 
-    fn new_ordered_list(marker: String, principal: String) -> Self {
-        let mut items = Vec::with_capacity(1);
-        items.push(ListItem::new(marker.clone(), Inline::new(&principal)));
+    fn new_callout_list(
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        Self::new_list(ListVariant::Callout, marker, principal, line, context)
+    }
 
-        Self::List {
+    fn new_ordered_list(
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        Self::new_list(ListVariant::Ordered, marker, principal, line, context)
+    }
+
+    fn new_unordered_list(
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        Self::new_list(ListVariant::Unordered, marker, principal, line, context)
+    }
+
+    fn new_description_list(
+        marker: String,
+        term: String,
+        principal: Option<String>,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        let item = DlistItem::new(marker.clone(), &term, principal.as_deref(), line, context);
+
+        Self::Dlist {
             node_type: NodeType::Block,
-            variant: ListVariant::Ordered,
             marker,
+            id: None,
             title: None,
-            location: None,
-            items,
+            reftext: None,
+            metadata: None,
+            location: item.location.clone(),
+            items: vec![item],
         }
     }
 
-    fn new_unordered_list(marker: String, principal: String) -> Self {
-        let mut items = Vec::with_capacity(1);
-        items.push(ListItem::new(marker.clone(), Inline::new(&principal)));
+    /// Returns the marker of the first item, like `*`, `.`, `<1>` or `::`.
+    pub fn marker(&self) -> &str {
+        match self {
+            Self::List { marker, .. } | Self::Dlist { marker, .. } => marker,
+        }
+    }
 
-        Self::List {
-            node_type: NodeType::Block,
-            variant: ListVariant::Unordered,
-            marker,
-            title: None,
-            location: None,
-            items,
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::List { id, .. } | Self::Dlist { id, .. } => id.as_deref(),
         }
     }
 
-    fn new_description_list(marker: String, term: String, principal: Option<String>) -> Self {
-        let mut items = Vec::with_capacity(1);
-        let mut current_terms = Vec::with_capacity(1);
-        current_terms.push(term.clone());
-        if let Some(principal) = principal {
-            items.push(DlistItem::new(
-                marker.clone(),
-                current_terms.to_owned(),
-                Inline::new(&principal),
-            ));
+    pub fn title(&self) -> Option<&[Inline]> {
+        match self {
+            Self::List { title, .. } | Self::Dlist { title, .. } => title.as_deref(),
         }
+    }
 
-        Self::Dlist {
-            node_type: NodeType::Block,
-            marker,
-            title: None,
-            location: None,
-            items,
-            current_terms,
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        match self {
+            Self::List { title, .. } | Self::Dlist { title, .. } => title.as_mut(),
         }
     }
 
-    pub(crate) fn push(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+    pub fn reftext(&self) -> Option<&[Inline]> {
         match self {
-            Self::List { marker, items, .. } => {
-                let prefix = marker.to_owned() + " ";
-                if let Some(principal) = line.trim_indent().strip_prefix(&prefix) {
-                    items.push(ListItem::new(marker.to_owned(), Inline::new(principal)));
+            Self::List { reftext, .. } | Self::Dlist { reftext, .. } => reftext.as_deref(),
+        }
+    }
 
-                    return Ok(());
-                }
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        match self {
+            Self::List { metadata, .. } | Self::Dlist { metadata, .. } => metadata.as_ref(),
+        }
+    }
+
+    /// Returns the variant of a list, or `None` for a description list.
+    pub fn variant(&self) -> Option<ListVariant> {
+        match self {
+            Self::List { variant, .. } => Some(*variant),
+            Self::Dlist { .. } => None,
+        }
+    }
+
+    /// Returns the number of the first item of an ordered list.
+    pub fn start(&self) -> Option<i64> {
+        match self {
+            Self::List { start, .. } => *start,
+            Self::Dlist { .. } => None,
+        }
+    }
+
+    /// Returns the items of a list, which a description list has none of.
+    pub fn items(&self) -> &[ListItem] {
+        match self {
+            Self::List { items, .. } => items,
+            Self::Dlist { .. } => &[],
+        }
+    }
+
+    pub fn items_mut(&mut self) -> &mut [ListItem] {
+        match self {
+            Self::List { items, .. } => items,
+            Self::Dlist { .. } => &mut [],
+        }
+    }
+
+    /// Returns the items of a description list, which a list has none of.
+    pub fn dlist_items(&self) -> &[DlistItem] {
+        match self {
+            Self::List { .. } => &[],
+            Self::Dlist { items, .. } => items,
+        }
+    }
+
+    pub fn dlist_items_mut(&mut self) -> &mut [DlistItem] {
+        match self {
+            Self::List { .. } => &mut [],
+            Self::Dlist { items, .. } => items,
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::List { location, .. } => location.as_ref(),
+            Self::Dlist { location, .. } => location.as_ref(),
+        }
+    }
+
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        let (Self::List {
+            id,
+            title,
+            reftext,
+            metadata,
+            ..
+        }
+        | Self::Dlist {
+            id,
+            title,
+            reftext,
+            metadata,
+            ..
+        }) = self;
+        (*id, *title, *reftext, *metadata) = pending.into_fields(context);
+    }
+
+    /// Returns the style of a description list, if it has a known one.
+    pub fn dlist_style(&self) -> Option<DlistStyle> {
+        let Self::Dlist { metadata, .. } = self else {
+            return None;
+        };
+
+        match metadata.as_ref().and_then(|m| m.attribute("1")) {
+            Some("horizontal") => Some(DlistStyle::Horizontal),
+            Some("qanda") => Some(DlistStyle::Qanda),
+            _ => None,
+        }
+    }
 
+    /// Returns the number of the first item of an ordered list, given by the
+    /// `start` attribute or the first explicit marker; a reversed list
+    /// counts down to 1 by default.
+    fn resolve_start(items: &[ListItem], metadata: Option<&BlockMetadata>) -> i64 {
+        if let Some(start) = metadata
+            .and_then(|m| m.attribute("start"))
+            .and_then(|s| s.trim().parse().ok())
+        {
+            return start;
+        }
+
+        if let Some((_, first)) = items
+            .first()
+            .and_then(|i| NumberingStyle::parse_marker(&i.marker))
+        {
+            return first as i64;
+        }
+
+        match metadata.is_some_and(|m| m.has_option("reversed")) {
+            true => items.len() as i64,
+            false => 1,
+        }
+    }
+
+    /// Checks the items of a callout list against the callout markers of the
+    /// verbatim blocks before it; a `<.>` item takes the number of its index.
+    fn check_callouts(items: &[ListItem], context: &mut Context) {
+        let mut numbers = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let position = item
+                .location
+                .as_ref()
+                .map_or_else(|| context.boundary(1), |l| l.start().clone());
+            let number = item
+                .marker
+                .strip_prefix('<')
+                .and_then(|m| m.strip_suffix('>'))
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(index + 1);
+            if number != index + 1 {
+                context.warn_at(
+                    position.clone(),
+                    format!(
+                        "callout list item index: expected {}, got {}",
+                        index + 1,
+                        number
+                    ),
+                );
+            }
+            numbers.push((number, position));
+        }
+
+        let warnings = context.callouts.check_list(&numbers);
+        context.warnings.extend(warnings);
+    }
+
+    /// Returns the numbering style of an ordered list, given by its block style
+    /// or else by its marker.
+    pub fn numbering_style(&self) -> Option<NumberingStyle> {
+        let Self::List {
+            variant: ListVariant::Ordered,
+            marker,
+            metadata,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let style = metadata
+            .as_ref()
+            .and_then(|m| m.attribute("1"))
+            .and_then(NumberingStyle::from_name);
+        let marker_style = NumberingStyle::parse_marker(marker).map(|(style, _)| style);
+
+        Some(
+            style
+                .or(marker_style)
+                .unwrap_or_else(|| NumberingStyle::from_depth(marker.len())),
+        )
+    }
+
+    /// Returns whether the next line belongs to a delimited block attached
+    /// to the last item.
+    pub(crate) fn is_in_delimited_block(&self) -> bool {
+        match self {
+            Self::List { items, .. } => items
+                .last()
+                .is_some_and(|i| i.content.is_in_delimited_block()),
+            Self::Dlist { items, .. } => items
+                .last()
+                .is_some_and(|i| i.content.is_in_delimited_block()),
+        }
+    }
+
+    /// Returns whether `kind` is an item of this list rather than of another one.
+    fn is_own_item(&self, kind: &LineKind) -> bool {
+        match (self, kind) {
+            (
+                Self::List {
+                    variant: ListVariant::Callout,
+                    ..
+                },
+                LineKind::CalloutListMarker { .. },
+            ) => true,
+            (
+                Self::List { marker, .. },
+                LineKind::UnorderedListMarker { marker: other, .. }
+                | LineKind::OrderedListMarker { marker: other, .. },
+            ) => marker == other,
+            (
+                Self::List { marker, .. },
+                LineKind::OffsetOrderedListMarker { marker: other, .. },
+            ) => {
+                let style = |m: &str| NumberingStyle::parse_marker(m).map(|(style, _)| style);
+
+                style(marker).is_some() && style(marker) == style(other)
+            }
+            (Self::Dlist { marker, .. }, LineKind::DescriptionListMarker { marker: other, .. }) => {
+                marker == other
+            }
+            _ => false,
+        }
+    }
+
+    /// Reads `line` into the list, and returns whether it belongs to the
+    /// list; a line which does not ends the list.
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<bool, Error> {
+        let kind = LineKind::parse(line.to_owned());
+        let accepted = if !self.is_in_delimited_block() && self.is_own_item(&kind) {
+            self.push_item(kind, line, context);
+
+            true
+        } else {
+            match self {
+                Self::List { items, .. } => match items.last_mut() {
+                    Some(last) => last.push(line, context)?,
+                    None => false,
+                },
+                Self::Dlist { items, .. } => match items.last_mut() {
+                    Some(last) => last.push(line, context)?,
+                    None => false,
+                },
+            }
+        };
+
+        if accepted && !line.trim().is_empty() {
+            let end = context.line_location(line).end().clone();
+            let (Self::List { location, .. } | Self::Dlist { location, .. }) = self;
+            if let Some(location) = location {
+                location.set_end(end);
+            }
+        }
+
+        Ok(accepted)
+    }
+
+    /// Ends the last item and starts the one of `kind`.
+    fn push_item(&mut self, kind: LineKind, line: &str, context: &mut Context) {
+        match (self, kind) {
+            (
+                Self::List { items, .. },
+                LineKind::UnorderedListMarker { marker, principal }
+                | LineKind::OrderedListMarker { marker, principal }
+                | LineKind::CalloutListMarker { marker, principal },
+            ) => {
                 if let Some(last) = items.last_mut() {
-                    return last.push(line);
+                    last.end(context);
+                }
+                items.push(ListItem::new(marker, &principal, line, context));
+            }
+            (
+                Self::List { items, .. },
+                LineKind::OffsetOrderedListMarker {
+                    marker,
+                    offset,
+                    principal,
+                },
+            ) => {
+                if let Some((style, first)) = items
+                    .first()
+                    .and_then(|i| NumberingStyle::parse_marker(&i.marker))
+                {
+                    let expected = first + items.len();
+                    if offset != expected {
+                        let position = context.line_location(line).start().clone();
+                        context.warn_at(
+                            position,
+                            format!(
+                                "list item index: expected {}, got {}",
+                                style.format(expected),
+                                style.format(offset)
+                            ),
+                        );
+                    }
+                }
+                if let Some(last) = items.last_mut() {
+                    last.end(context);
+                }
+                items.push(ListItem::new(marker, &principal, line, context));
+            }
+            (
+                Self::Dlist { items, .. },
+                LineKind::DescriptionListMarker {
+                    marker,
+                    term,
+                    principal,
+                },
+            ) => {
+                // Consecutive terms share the description which follows them.
+                if let Some(last) = items.last_mut().filter(|i| i.content.is_empty()) {
+                    last.push_term(&term, principal.as_deref(), line, context);
+
+                    return;
                 }
 
-                Err("not expected empty items of list".into())
+                if let Some(last) = items.last_mut() {
+                    last.end(context);
+                }
+                items.push(DlistItem::new(
+                    marker,
+                    &term,
+                    principal.as_deref(),
+                    line,
+                    context,
+                ));
             }
-            Self::Dlist {
-                marker,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn end(&mut self, context: &mut Context) {
+        match self {
+            Self::List {
+                variant,
                 items,
-                current_terms,
+                metadata,
+                start,
                 ..
             } => {
-                let delimiter = marker.to_owned() + " ";
-
-                if !current_terms.is_empty() {
-                    if let Some((term, principal)) = line.split_once(&delimiter) {
-                        current_terms.push(term.to_owned());
-                        let principal = principal.trim_start_matches(' ');
-                        items.push(DlistItem::new(
-                            marker.clone(),
-                            current_terms.to_owned(),
-                            Inline::new(principal),
-                        ));
-                        current_terms.clear();
-
-                        return Ok(());
-                    }
+                if let Some(last) = items.last_mut() {
+                    last.end(context);
+                }
+                if *variant == ListVariant::Ordered {
+                    *start = Some(Self::resolve_start(items, metadata.as_ref()));
+                }
+                if *variant == ListVariant::Callout {
+                    Self::check_callouts(items, context);
+                }
+                if items.iter().any(|i| i.checked.is_some()) {
+                    metadata
+                        .get_or_insert_with(Default::default)
+                        .set_option("checklist");
+                }
+            }
+            Self::Dlist { items, .. } => {
+                if let Some(last) = items.last_mut() {
+                    last.end(context);
+                }
+            }
+        }
+    }
+}
 
-                    if let Some((term, "")) = line.split_once(&marker.to_owned()) {
-                        current_terms.push(term.to_owned());
+/// The content of a list item after its marker: the principal text, which
+/// may wrap over the following lines, and the blocks attached to the item.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ListItemContent {
+    principal: Vec<String>,
+    principal_start: Option<LocationBoundary>,
+    /// Parses the attached blocks, which are moved to the item when it ends.
+    parser: BlockParser,
+    /// Whether a line of text is still appended to the principal text.
+    is_wrapping: bool,
+    /// Whether a `+` list continuation attaches the next block to the item.
+    has_continuation: bool,
+}
+impl ListItemContent {
+    /// Starts with the `principal` text, which is a suffix of `line`.
+    fn new(principal: Option<&str>, line: &str, context: &Context) -> Self {
+        let mut content = Self {
+            is_wrapping: true,
+            ..Default::default()
+        };
+        if let Some(principal) = principal.filter(|p| !p.is_empty()) {
+            content.push_principal(principal, line, context);
+        }
 
-                        return Ok(());
-                    }
+        content
+    }
 
-                    let principal = line.trim_indent();
-                    items.push(DlistItem::new(
-                        marker.clone(),
-                        current_terms.to_owned(),
-                        Inline::new(principal),
-                    ));
-                    current_terms.clear();
+    /// Returns whether neither a principal text nor a block has been read yet.
+    fn is_empty(&self) -> bool {
+        self.principal.is_empty() && !self.has_continuation && self.parser.is_empty()
+    }
 
-                    return Ok(());
-                }
+    fn is_in_delimited_block(&self) -> bool {
+        self.parser.is_in_delimited_block()
+    }
 
-                if let Some((term, principal)) = line.split_once(&delimiter) {
-                    let principal = principal.trim_start_matches(' ');
-                    items.push(DlistItem::new(
-                        marker.clone(),
-                        vec![term.to_owned()],
-                        Inline::new(principal),
-                    ));
+    fn push_principal(&mut self, text: &str, line: &str, context: &Context) {
+        if self.principal_start.is_none() {
+            self.principal_start = Some(context.suffix_boundary(line, text));
+        }
+        self.principal.push(text.to_owned());
+    }
 
-                    return Ok(());
-                }
+    /// Reads `line` into the item, and returns whether it belongs to the item.
+    fn push(&mut self, line: &str, context: &mut Context) -> Result<bool, Error> {
+        if self.parser.is_in_delimited_block() {
+            self.parser.push(line, context)?;
+
+            return Ok(true);
+        }
 
-                if let Some((term, "")) = line.split_once(&marker.to_owned()) {
-                    current_terms.push(term.to_owned());
+        // A nested list takes the following lines, up to one which belongs to
+        // none of its items.
+        if let Some(list) = self.parser.current_list_mut() {
+            return list.push(line, context);
+        }
+
+        let kind = LineKind::parse(line.to_owned());
+
+        if line.trim() == "+" {
+            // The continuation ends an attached paragraph like a blank line does.
+            self.parser.push("", context)?;
+            self.is_wrapping = false;
+            self.has_continuation = true;
+
+            return Ok(true);
+        }
 
-                    return Ok(());
+        if self.has_continuation {
+            if !matches!(kind, LineKind::Empty) {
+                self.parser.push(line, context)?;
+                // Metadata lines and comments keep attaching the next block.
+                self.has_continuation =
+                    self.parser.has_pending_metadata() || matches!(kind, LineKind::CommentMarker);
+            }
+
+            return Ok(true);
+        }
+
+        match kind {
+            LineKind::Empty => {
+                self.parser.push(line, context)?;
+                if !self.principal.is_empty() {
+                    self.is_wrapping = false;
                 }
 
-                if let Some(last) = items.last_mut() {
-                    return last.push(line);
+                Ok(true)
+            }
+            LineKind::CommentMarker => Ok(true),
+            kind if kind.is_list_item() => {
+                // An item with another marker than the ones of the enclosing
+                // lists starts a nested list, which ends an attached paragraph.
+                if self.parser.has_current_block() {
+                    self.parser.push("", context)?;
                 }
+                self.parser.push(line, context)?;
+                self.is_wrapping = false;
+
+                Ok(true)
+            }
+            kind if kind.block_delimiter().is_some()
+                || PendingMetadata::is_attribute_line(line) =>
+            {
+                Ok(false)
+            }
+            _ if self.parser.has_current_block() => {
+                self.parser.push(line, context)?;
+
+                Ok(true)
+            }
+            _ if self.is_wrapping => {
+                self.push_principal(line.trim_indent(), line, context);
 
-                Err("not expected empty items of list".into())
+                Ok(true)
             }
+            _ => Ok(false),
         }
     }
+
+    /// Parses the principal text and takes the attached blocks.
+    fn end(&mut self, context: &mut Context) -> (Vec<Inline>, Vec<Block>) {
+        let principal = match &self.principal_start {
+            Some(start) => Inline::new(&self.principal.join("\n"), start, &mut context.attributes),
+            None => Vec::with_capacity(0),
+        };
+
+        (principal, self.parser.end(context))
+    }
 }
 
 #[skip_serializing_none]
@@ -193,23 +795,96 @@ pub struct ListItem {
     node_type: NodeType,
     marker: String,
     principal: Vec<Inline>,
+    /// Whether the checkbox of a checklist item is checked, or `None` when
+    /// the item has no checkbox.
+    checked: Option<bool>,
     blocks: Option<Vec<NonSectionBlockBody>>,
     location: Option<Location>,
+
+    #[serde(skip)]
+    content: ListItemContent,
 }
 impl ListItem {
-    fn new(marker: String, principal: Vec<Inline>) -> Self {
+    fn new(marker: String, principal: &str, line: &str, context: &mut Context) -> Self {
+        // Only the items of an unordered list can start with a checkbox.
+        let (checked, principal) = match marker.starts_with(['*', '-']) {
+            true => Self::strip_checkbox(principal),
+            false => (None, principal),
+        };
+
         Self {
             name: "listItem".to_owned(),
             node_type: NodeType::Block,
             marker,
-            principal,
+            principal: Vec::with_capacity(0),
+            checked,
             blocks: Some(Vec::with_capacity(0)),
-            location: None,
+            location: Some(context.line_location(line)),
+            content: ListItemContent::new(Some(principal), line, context),
+        }
+    }
+
+    /// Splits a `[ ]`, `[x]` or `[*]` checkbox off the front of `principal`.
+    fn strip_checkbox(principal: &str) -> (Option<bool>, &str) {
+        let checked = match principal.get(..4) {
+            Some("[ ] ") => false,
+            Some("[x] " | "[X] " | "[*] ") => true,
+            _ => return (None, principal),
+        };
+        let text = principal[4..].trim_start_matches(' ');
+
+        match text.is_empty() {
+            true => (None, principal),
+            false => (Some(checked), text),
         }
     }
 
-    pub(crate) fn push(&mut self, _line: &str) -> Result<(), Box<dyn Error>> {
-        Err("not implemented".into())
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    /// Returns whether the checkbox of a checklist item is checked, or `None`
+    /// when the item has no checkbox.
+    pub fn checked(&self) -> Option<bool> {
+        self.checked
+    }
+
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<bool, Error> {
+        let accepted = self.content.push(line, context)?;
+        if accepted && !line.trim().is_empty() {
+            let end = context.line_location(line).end().clone();
+            if let Some(location) = self.location.as_mut() {
+                location.set_end(end);
+            }
+        }
+
+        Ok(accepted)
+    }
+
+    fn end(&mut self, context: &mut Context) {
+        let (principal, blocks) = self.content.end(context);
+        self.principal = principal;
+        self.blocks = Some(blocks);
+    }
+
+    pub fn principal(&self) -> &[Inline] {
+        &self.principal
+    }
+
+    pub fn principal_mut(&mut self) -> &mut Vec<Inline> {
+        &mut self.principal
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        self.blocks.as_deref().unwrap_or_default()
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut Vec<Block> {
+        self.blocks.get_or_insert_with(Vec::new)
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
     }
 }
 
@@ -224,57 +899,173 @@ pub struct DlistItem {
     blocks: Option<Vec<NonSectionBlockBody>>,
     location: Option<Location>,
     terms: Vec<Vec<Inline>>,
+
+    #[serde(skip)]
+    content: ListItemContent,
 }
 impl DlistItem {
-    fn new(marker: String, _terms: Vec<String>, principal: Vec<Inline>) -> Self {
+    fn new(
+        marker: String,
+        term: &str,
+        principal: Option<&str>,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
         Self {
             name: "dlistItem".to_owned(),
             node_type: NodeType::Block,
             marker,
-            principal,
+            principal: Vec::with_capacity(0),
             blocks: Some(Vec::with_capacity(0)),
-            location: None,
-            terms: Vec::with_capacity(0),
+            location: Some(context.line_location(line)),
+            terms: vec![Self::parse_term(term, line, context)],
+            content: ListItemContent::new(principal, line, context),
+        }
+    }
+
+    /// Parses `term`, which starts `line` after its indentation.
+    fn parse_term(term: &str, line: &str, context: &mut Context) -> Vec<Inline> {
+        let start = context.line_location(line).start().clone();
+
+        Inline::new(term, &start, &mut context.attributes)
+    }
+
+    /// Adds another term on `line`, before any description has been read.
+    fn push_term(
+        &mut self,
+        term: &str,
+        principal: Option<&str>,
+        line: &str,
+        context: &mut Context,
+    ) {
+        self.terms.push(Self::parse_term(term, line, context));
+        if let Some(principal) = principal.filter(|p| !p.is_empty()) {
+            self.content.push_principal(principal, line, context);
+        }
+        let end = context.line_location(line).end().clone();
+        if let Some(location) = self.location.as_mut() {
+            location.set_end(end);
+        }
+    }
+
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<bool, Error> {
+        let accepted = self.content.push(line, context)?;
+        if accepted && !line.trim().is_empty() {
+            let end = context.line_location(line).end().clone();
+            if let Some(location) = self.location.as_mut() {
+                location.set_end(end);
+            }
         }
+
+        Ok(accepted)
+    }
+
+    fn end(&mut self, context: &mut Context) {
+        let (principal, blocks) = self.content.end(context);
+        self.principal = principal;
+        self.blocks = Some(blocks);
     }
 
-    pub(crate) fn push(&mut self, _line: &str) -> Result<(), Box<dyn Error>> {
-        Err("not implemented".into())
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    pub fn terms(&self) -> &[Vec<Inline>] {
+        &self.terms
+    }
+
+    pub fn terms_mut(&mut self) -> &mut Vec<Vec<Inline>> {
+        &mut self.terms
+    }
+
+    pub fn principal(&self) -> &[Inline] {
+        &self.principal
+    }
+
+    pub fn principal_mut(&mut self) -> &mut Vec<Inline> {
+        &mut self.principal
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        self.blocks.as_deref().unwrap_or_default()
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut Vec<Block> {
+        self.blocks.get_or_insert_with(Vec::new)
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
     }
 }
 
 impl Block {
-    pub(crate) fn new_callout_list(marker: String, principal: String) -> Self {
-        Self::AnyList(AnyList::new_callout_list(marker, principal))
+    pub(crate) fn new_callout_list(
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        Self::AnyList(AnyList::new_callout_list(marker, principal, line, context))
     }
 
-    pub(crate) fn new_ordered_list(marker: String, principal: String) -> Self {
-        Self::AnyList(AnyList::new_ordered_list(marker, principal))
+    pub(crate) fn new_ordered_list(
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        Self::AnyList(AnyList::new_ordered_list(marker, principal, line, context))
     }
 
-    pub(crate) fn new_unordered_list(marker: String, principal: String) -> Self {
-        Self::AnyList(AnyList::new_unordered_list(marker, principal))
+    pub(crate) fn new_unordered_list(
+        marker: String,
+        principal: String,
+        line: &str,
+        context: &mut Context,
+    ) -> Self {
+        Self::AnyList(AnyList::new_unordered_list(
+            marker, principal, line, context,
+        ))
     }
 
     pub(crate) fn new_description_list(
         marker: String,
         term: String,
         principal: Option<String>,
+        line: &str,
+        context: &mut Context,
     ) -> Self {
-        Self::AnyList(AnyList::new_description_list(marker, term, principal))
+        Self::AnyList(AnyList::new_description_list(
+            marker, term, principal, line, context,
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::asg::{
+        block::Warning,
+        inlines::tests::{inlines, without_locations},
+        LocationBoundary,
+    };
+
     use super::*;
 
     #[test]
     fn unordered_list() {
-        let mut list = Block::new_unordered_list("*".to_owned(), "item 1".to_owned());
+        let mut context = Context::default();
+        context.next_line();
+        let mut list = Block::new_unordered_list(
+            "*".to_owned(),
+            "item 1".to_owned(),
+            "* item 1",
+            &mut context,
+        );
 
-        list.push("* item 2");
-        list.push("  * item 3");
+        list.push("* item 2", &mut context).unwrap();
+        list.push("  * item 3", &mut context).unwrap();
+        list.end(&mut context);
 
         let Block::AnyList(AnyList::List {
             variant,
@@ -295,23 +1086,29 @@ mod tests {
         let item_2 = items.pop().unwrap();
         let item_1 = items.pop().unwrap();
 
-        assert_eq!(Inline::new("item 1"), item_1.principal);
-        assert_eq!(Inline::new("item 2"), item_2.principal);
-        assert_eq!(Inline::new("item 3"), item_3.principal);
+        assert_eq!(inlines("item 1"), without_locations(&item_1.principal));
+        assert_eq!(inlines("item 2"), without_locations(&item_2.principal));
+        assert_eq!(inlines("item 3"), without_locations(&item_3.principal));
     }
 
     #[test]
     fn description_list() {
+        let mut context = Context::default();
+        context.next_line();
         let mut list = Block::new_description_list(
             "::".to_owned(),
             "term 1".to_owned(),
             Some("description 1".to_owned()),
+            "term 1:: description 1",
+            &mut context,
         );
 
-        list.push("term 2::");
-        list.push("  description 2");
-        list.push("term 3::");
-        list.push("    term 4:: description 3-4");
+        list.push("term 2::", &mut context).unwrap();
+        list.push("  description 2", &mut context).unwrap();
+        list.push("term 3::", &mut context).unwrap();
+        list.push("    term 4:: description 3-4", &mut context)
+            .unwrap();
+        list.end(&mut context);
 
         let Block::AnyList(AnyList::Dlist { marker, items, .. }) = list else {
             panic!("not expected")
@@ -325,8 +1122,156 @@ mod tests {
         let item_2 = items.pop().unwrap();
         let item_1 = items.pop().unwrap();
 
-        assert_eq!(Inline::new("description 1"), item_1.principal);
-        assert_eq!(Inline::new("description 2"), item_2.principal);
-        assert_eq!(Inline::new("description 3-4"), item_3.principal);
+        assert_eq!(
+            inlines("description 1"),
+            without_locations(&item_1.principal)
+        );
+        assert_eq!(
+            inlines("description 2"),
+            without_locations(&item_2.principal)
+        );
+        assert_eq!(
+            inlines("description 3-4"),
+            without_locations(&item_3.principal)
+        );
+        assert_eq!(
+            vec![inlines("term 1")],
+            item_1
+                .terms
+                .iter()
+                .map(|t| without_locations(t))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![inlines("term 2")],
+            item_2
+                .terms
+                .iter()
+                .map(|t| without_locations(t))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![inlines("term 3"), inlines("term 4")],
+            item_3
+                .terms
+                .iter()
+                .map(|t| without_locations(t))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn checklist() {
+        let mut context = Context::default();
+        context.next_line();
+        let mut list = Block::new_unordered_list(
+            "*".to_owned(),
+            "[x] checked".to_owned(),
+            "* [x] checked",
+            &mut context,
+        );
+
+        list.push("* [ ] unchecked", &mut context).unwrap();
+        list.push("* [*] checked", &mut context).unwrap();
+        list.push("* plain", &mut context).unwrap();
+        list.push("* [ ]", &mut context).unwrap();
+        list.end(&mut context);
+
+        let Block::AnyList(AnyList::List {
+            metadata, items, ..
+        }) = list
+        else {
+            panic!("not expected")
+        };
+
+        assert!(metadata.is_some_and(|m| m.has_option("checklist")));
+        assert_eq!(
+            vec![Some(true), Some(false), Some(true), None, None],
+            items.iter().map(|i| i.checked()).collect::<Vec<_>>()
+        );
+        assert_eq!(inlines("unchecked"), without_locations(&items[1].principal));
+        assert_eq!(inlines("[ ]"), without_locations(&items[4].principal));
+    }
+
+    #[test]
+    fn ordered_list_numbering() {
+        let mut context = Context::default();
+        context.next_line();
+        let mut list =
+            Block::new_ordered_list("c.".to_owned(), "c".to_owned(), "c. c", &mut context);
+
+        context.next_line();
+        list.push("d. d", &mut context).unwrap();
+        context.next_line();
+        list.push("f. f", &mut context).unwrap();
+        list.end(&mut context);
+
+        let Block::AnyList(ordered) = &list else {
+            panic!("not expected")
+        };
+        let AnyList::List { start, items, .. } = ordered else {
+            panic!("not expected")
+        };
+        assert_eq!(Some(3), *start);
+        assert_eq!(3, items.len());
+        assert_eq!(Some(NumberingStyle::Loweralpha), ordered.numbering_style());
+        assert_eq!(
+            vec![Warning {
+                position: LocationBoundary::new(3, 1, None),
+                message: "list item index: expected e, got f".to_owned()
+            }],
+            context.warnings
+        );
+    }
+
+    #[test]
+    fn ordered_list_attributes() {
+        let mut context = Context::default();
+        context.next_line();
+        let mut pending = PendingMetadata::default();
+        pending.push("[upperroman%reversed]", &mut context);
+        let mut list =
+            Block::new_ordered_list(".".to_owned(), "one".to_owned(), ". one", &mut context);
+        list.set_metadata(pending, &mut context);
+        list.push(". two", &mut context).unwrap();
+        list.end(&mut context);
+
+        let Block::AnyList(ordered @ AnyList::List { start, .. }) = &list else {
+            panic!("not expected")
+        };
+        assert_eq!(Some(2), *start);
+        assert_eq!(Some(NumberingStyle::Upperroman), ordered.numbering_style());
+
+        let mut pending = PendingMetadata::default();
+        pending.push("[start=4]", &mut context);
+        let mut list =
+            Block::new_ordered_list("..".to_owned(), "one".to_owned(), ".. one", &mut context);
+        list.set_metadata(pending, &mut context);
+        list.end(&mut context);
+
+        let Block::AnyList(ordered @ AnyList::List { start, .. }) = &list else {
+            panic!("not expected")
+        };
+        assert_eq!(Some(4), *start);
+        assert_eq!(Some(NumberingStyle::Loweralpha), ordered.numbering_style());
+    }
+
+    #[test]
+    fn numbering_markers() {
+        assert_eq!(
+            Some((NumberingStyle::Arabic, 42)),
+            NumberingStyle::parse_marker("42.")
+        );
+        assert_eq!(
+            Some((NumberingStyle::Upperalpha, 2)),
+            NumberingStyle::parse_marker("B.")
+        );
+        assert_eq!(
+            Some((NumberingStyle::Upperroman, 1994)),
+            NumberingStyle::parse_marker("MCMXCIV)")
+        );
+        assert_eq!(None, NumberingStyle::parse_marker("ab."));
+        assert_eq!(None, NumberingStyle::parse_marker("iIi)"));
+        assert_eq!("xiv", NumberingStyle::Lowerroman.format(14));
     }
 }
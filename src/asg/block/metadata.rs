@@ -0,0 +1,513 @@
+use serde::{Deserialize, Serialize};
+use serde_with_macros::skip_serializing_none;
+
+use super::Context;
+use crate::asg::{Inline, Location, LocationBoundary};
+
+use std::collections::HashMap;
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BlockMetadata {
+    attributes: HashMap<String, String>,
+    options: Vec<String>,
+    roles: Vec<String>,
+    location: Option<Location>,
+}
+impl BlockMetadata {
+    /// Returns the attributes of the block, where the positional ones are
+    /// named by their position, like `1` for the style.
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|v| v.as_str())
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|o| o == name)
+    }
+
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Sets the attribute `name`, as the parser does for a block default.
+    pub(crate) fn set_attribute(&mut self, name: &str, value: &str) {
+        self.attributes.insert(name.to_owned(), value.to_owned());
+    }
+
+    /// Sets the option `name`, as the parser does for a checklist.
+    pub(crate) fn set_option(&mut self, name: &str) {
+        if !self.has_option(name) {
+            self.options.push(name.to_owned());
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.options.is_empty() && self.roles.is_empty()
+    }
+
+    /// Adds the attributes of an attribute list, such as `source#id.role%option,rust`.
+    ///
+    /// The `id`, `role`, `options` and `reftext` attributes are returned apart in
+    /// `anchor` since they are not kept in `attributes`.
+    pub(crate) fn push_attribute_list(
+        &mut self,
+        entries: Vec<AttributeEntry>,
+        anchor: &mut Anchor,
+    ) {
+        self.push_entries(entries, &[], anchor);
+    }
+
+    /// Adds the attributes of the attribute list of a block macro, whose
+    /// positional attributes are named by `names` in order.
+    pub(crate) fn push_macro_attribute_list(
+        &mut self,
+        entries: Vec<AttributeEntry>,
+        names: &[&str],
+        anchor: &mut Anchor,
+    ) {
+        self.push_entries(entries, names, anchor);
+    }
+
+    fn push_entries(&mut self, entries: Vec<AttributeEntry>, names: &[&str], anchor: &mut Anchor) {
+        let mut position = 0;
+        for entry in entries {
+            match entry {
+                AttributeEntry::Named(name, value) => match name.as_str() {
+                    "id" => anchor.id = Some(value),
+                    "reftext" => anchor.reftext = Some(value),
+                    "role" | "roles" => self.push_roles(&value),
+                    "options" | "opts" => self.push_options(&value),
+                    _ => {
+                        self.attributes.insert(name, value);
+                    }
+                },
+                AttributeEntry::Positional(value) => {
+                    position += 1;
+                    // Only the style of a block attribute line has shorthands.
+                    let value = if position == 1 && names.is_empty() {
+                        self.parse_shorthands(&value, anchor)
+                    } else {
+                        value
+                    };
+                    let name = names
+                        .get(position - 1)
+                        .map_or_else(|| position.to_string(), |n| n.to_string());
+                    if !value.is_empty() {
+                        self.attributes.insert(name, value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Strips the `#id`, `.role` and `%option` shorthands off the first positional
+    /// attribute, returning the style in front of them.
+    fn parse_shorthands(&mut self, value: &str, anchor: &mut Anchor) -> String {
+        let Some(start) = value.find(['#', '.', '%']) else {
+            return value.to_owned();
+        };
+        let (style, mut rest) = value.split_at(start);
+
+        while let Some(kind) = rest.chars().next() {
+            let rest_value = &rest[1..];
+            let end = rest_value.find(['#', '.', '%']).unwrap_or(rest_value.len());
+            let shorthand = &rest_value[..end];
+            match kind {
+                '#' if !shorthand.is_empty() => anchor.id = Some(shorthand.to_owned()),
+                '.' if !shorthand.is_empty() => self.roles.push(shorthand.to_owned()),
+                '%' if !shorthand.is_empty() => self.options.push(shorthand.to_owned()),
+                _ => {}
+            }
+            rest = &rest_value[end..];
+        }
+
+        style.to_owned()
+    }
+
+    fn push_roles(&mut self, value: &str) {
+        self.roles
+            .extend(value.split_whitespace().map(|r| r.to_owned()));
+    }
+
+    fn push_options(&mut self, value: &str) {
+        self.options.extend(
+            value
+                .split(',')
+                .map(|o| o.trim().to_owned())
+                .filter(|o| !o.is_empty()),
+        );
+    }
+}
+
+/// The id and reference text given to a block by its anchor or attribute list.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Anchor {
+    pub(crate) id: Option<String>,
+    pub(crate) reftext: Option<String>,
+}
+
+/// An entry of an attribute list.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AttributeEntry {
+    Positional(String),
+    Named(String, String),
+}
+impl AttributeEntry {
+    /// Returns the value of the `title` attribute in `entries`, if any.
+    fn title(entries: &[Self]) -> Option<String> {
+        entries.iter().find_map(|entry| match entry {
+            Self::Named(name, value) if name == "title" => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Parses the content of an attribute list, the text between the brackets.
+    pub(crate) fn parse_list(text: &str) -> Vec<Self> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut entries = Vec::with_capacity(0);
+        let mut i = 0;
+
+        while i <= chars.len() {
+            while i < chars.len() && chars[i] == ' ' {
+                i += 1;
+            }
+
+            let name_end = chars[i..]
+                .iter()
+                .position(|c| !(c.is_alphanumeric() || *c == '_' || *c == '-'))
+                .map_or(chars.len(), |p| i + p);
+            let name = (name_end > i && chars.get(name_end) == Some(&'='))
+                .then(|| chars[i..name_end].iter().collect::<String>());
+            if name.is_some() {
+                i = name_end + 1;
+                while i < chars.len() && chars[i] == ' ' {
+                    i += 1;
+                }
+            }
+
+            let (value, next) = Self::parse_value(&chars, i);
+            entries.push(match name {
+                Some(name) => Self::Named(name, value),
+                None => Self::Positional(value),
+            });
+            i = next + 1;
+        }
+
+        if entries == [Self::Positional("".to_owned())] {
+            entries.clear();
+        }
+
+        entries
+    }
+
+    /// Reads the value starting at `start`, up to the index of the next comma.
+    fn parse_value(chars: &[char], start: usize) -> (String, usize) {
+        let Some(&quote) = chars.get(start).filter(|c| **c == '"' || **c == '\'') else {
+            let end = chars[start..]
+                .iter()
+                .position(|c| *c == ',')
+                .map_or(chars.len(), |p| start + p);
+            let value: String = chars[start..end].iter().collect();
+
+            return (value.trim().to_owned(), end);
+        };
+
+        let mut value = String::new();
+        let mut i = start + 1;
+        while i < chars.len() && chars[i] != quote {
+            if chars[i] == '\\' && chars.get(i + 1) == Some(&quote) {
+                i += 1;
+            }
+            value.push(chars[i]);
+            i += 1;
+        }
+        if i == chars.len() {
+            // An unclosed quote is taken as part of an unquoted value.
+            let end = chars[start..]
+                .iter()
+                .position(|c| *c == ',')
+                .map_or(chars.len(), |p| start + p);
+            let value: String = chars[start..end].iter().collect();
+
+            return (value.trim().to_owned(), end);
+        }
+
+        let end = chars[i..]
+            .iter()
+            .position(|c| *c == ',')
+            .map_or(chars.len(), |p| i + p);
+
+        (value, end)
+    }
+}
+
+/// The `id`, `title`, `reftext` and `metadata` of a block.
+pub(crate) type BlockFields = (
+    Option<String>,
+    Option<Vec<Inline>>,
+    Option<Vec<Inline>>,
+    Option<BlockMetadata>,
+);
+
+/// Metadata lines read before a block, waiting for the block they apply to.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingMetadata {
+    anchor: Anchor,
+    /// Where the text of the reftext starts, in its anchor or attribute line.
+    reftext_start: Option<LocationBoundary>,
+    title: Option<Vec<Inline>>,
+    metadata: BlockMetadata,
+}
+impl PendingMetadata {
+    /// Returns the block style, given by the first positional attribute.
+    pub(crate) fn style(&self) -> Option<&str> {
+        self.metadata.attribute("1")
+    }
+
+    /// Returns whether no metadata line has been read yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.metadata.location.is_none()
+    }
+
+    /// Returns whether `line` is a block attribute line, which interrupts a paragraph.
+    pub(crate) fn is_attribute_line(line: &str) -> bool {
+        line.trim_end()
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .is_some_and(is_attribute_list)
+    }
+
+    /// Reads `line` if it is a block anchor, a block attribute line or a block title.
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> bool {
+        let trimmed = line.trim_end();
+
+        if let Some(anchor) = trimmed
+            .strip_prefix("[[")
+            .and_then(|l| l.strip_suffix("]]"))
+            .filter(|a| is_anchor(a))
+        {
+            self.anchor.id = Some(anchor.split(',').next().unwrap_or(anchor).to_owned());
+            if let Some((_, reftext)) = anchor.split_once(',') {
+                let reftext = reftext.trim_start();
+                self.anchor.reftext = Some(reftext.to_owned());
+                // The reftext is the end of the anchor, before its closing brackets.
+                self.reftext_start = Some(context.suffix_boundary(line, &format!("{reftext}]]")));
+            }
+            self.extend_location(line, context);
+
+            return true;
+        }
+
+        if let Some(list) = trimmed
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .filter(|l| is_attribute_list(l))
+        {
+            let list = context.attributes.substitute(list);
+            let reftext = self.anchor.reftext.clone();
+            let entries = AttributeEntry::parse_list(&list);
+            let title = AttributeEntry::title(&entries);
+            self.metadata.push_attribute_list(entries, &mut self.anchor);
+            let start = context.line_location(line).start().clone();
+            if self.anchor.reftext != reftext {
+                self.reftext_start = Some(start.clone());
+            }
+            self.push_title_attribute(title, &start, context);
+            self.extend_location(line, context);
+
+            return true;
+        }
+
+        if let Some(title) = trimmed.strip_prefix('.').filter(|t| is_title(t)) {
+            self.title = Some(Inline::new(
+                title,
+                &context.boundary(2),
+                &mut context.attributes,
+            ));
+            self.extend_location(line, context);
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Adds the attribute list of a block macro, the text between its brackets.
+    pub(crate) fn push_macro_attributes(
+        &mut self,
+        attrlist: &str,
+        names: &[&str],
+        context: &mut Context,
+    ) {
+        let attrlist = context.attributes.substitute(attrlist);
+        let entries = AttributeEntry::parse_list(&attrlist);
+        let title = AttributeEntry::title(&entries);
+        self.metadata
+            .push_macro_attribute_list(entries, names, &mut self.anchor);
+        self.push_title_attribute(title, &context.boundary(1), context);
+    }
+
+    /// Takes the `title` attribute of an attribute list, at `start`, as the title
+    /// of the block, like Asciidoctor does.
+    fn push_title_attribute(
+        &mut self,
+        title: Option<String>,
+        start: &LocationBoundary,
+        context: &mut Context,
+    ) {
+        if let Some(title) = title {
+            self.title = Some(Inline::new(&title, start, &mut context.attributes));
+        }
+    }
+
+    fn extend_location(&mut self, line: &str, context: &Context) {
+        let location = context.line_location(line);
+        match self.metadata.location.as_mut() {
+            Some(l) => l.set_end(location.end().clone()),
+            None => self.metadata.location = Some(location),
+        }
+    }
+
+    /// Splits into the `id`, `title`, `reftext` and `metadata` of a block.
+    pub(crate) fn into_fields(self, context: &mut Context) -> BlockFields {
+        let reftext = self.anchor.reftext.map(|reftext| {
+            let start = self.reftext_start.unwrap_or_else(|| context.boundary(1));
+            Inline::new(&reftext, &start, &mut context.attributes)
+        });
+        let metadata = (!self.metadata.is_empty()).then_some(self.metadata);
+
+        (self.anchor.id, self.title, reftext, metadata)
+    }
+}
+
+/// Returns whether `text` is the content of a block anchor, like `id` or `id, reftext`.
+fn is_anchor(text: &str) -> bool {
+    let id = text.split_once(',').map_or(text, |(id, _)| id);
+
+    id.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == ':')
+        && id
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '-'))
+}
+
+/// Returns whether `text` is the content of a block attribute line.
+fn is_attribute_list(text: &str) -> bool {
+    text.is_empty()
+        || text.starts_with(|c: char| {
+            c.is_alphanumeric() || matches!(c, '_' | '.' | '#' | '%' | '{' | ',' | '"' | '\'')
+        })
+}
+
+/// Returns whether `text` is a block title without its leading `.`.
+fn is_title(text: &str) -> bool {
+    let text = text.strip_prefix('.').unwrap_or(text);
+
+    text.starts_with(|c: char| !c.is_whitespace() && c != '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_list() {
+        assert_eq!(
+            vec![
+                AttributeEntry::Positional("source".to_owned()),
+                AttributeEntry::Positional("rust".to_owned()),
+                AttributeEntry::Named("title".to_owned(), "a, \"b\"".to_owned()),
+                AttributeEntry::Named("width".to_owned(), "50".to_owned()),
+            ],
+            AttributeEntry::parse_list(r#"source, rust,title="a, \"b\"" , width=50"#)
+        );
+        assert!(AttributeEntry::parse_list("").is_empty());
+    }
+
+    #[test]
+    fn shorthand_attributes() {
+        let mut context = Context::default();
+        context.next_line();
+        let mut pending = PendingMetadata::default();
+
+        assert!(pending.push("[source#main.rust.example%linenums,rust]", &mut context));
+        assert!(pending.push("[role=extra,opts=\"a,b\"]", &mut context));
+        assert!(!pending.push("[.not a list", &mut context));
+
+        assert_eq!(Some("source"), pending.style());
+        assert_eq!(Some("rust"), pending.metadata.attribute("2"));
+        assert_eq!(Some("main".to_owned()), pending.anchor.id);
+        assert_eq!(vec!["rust", "example", "extra"], pending.metadata.roles);
+        assert_eq!(vec!["linenums", "a", "b"], pending.metadata.options);
+    }
+
+    #[test]
+    fn anchor_and_title() {
+        let mut context = Context::default();
+        context.next_line();
+        let mut pending = PendingMetadata::default();
+
+        assert!(pending.push("[[install, Installation]]", &mut context));
+        assert!(pending.push(".Block title", &mut context));
+        assert!(!pending.push(". list item", &mut context));
+        assert!(!pending.push("[[[bibliography]]]", &mut context));
+
+        let (id, title, reftext, metadata) = pending.into_fields(&mut context);
+        assert_eq!(Some("install".to_owned()), id);
+        assert!(title.is_some());
+        assert!(reftext.is_some());
+        assert!(metadata.is_none());
+    }
+
+    #[test]
+    fn title_attribute() {
+        let title_value = |title: Option<Vec<Inline>>| match title.as_deref() {
+            Some([Inline::Text(text)]) => Some(text.value().to_owned()),
+            _ => None,
+        };
+        let mut context = Context::default();
+        context.next_line();
+        let mut pending = PendingMetadata::default();
+
+        assert!(pending.push("[title=\"A, b\"]", &mut context));
+        let (_, title, _, metadata) = pending.into_fields(&mut context);
+        assert_eq!(Some("A, b".to_owned()), title_value(title));
+        assert_eq!(Some("A, b"), metadata.unwrap().attribute("title"));
+
+        let mut pending = PendingMetadata::default();
+        pending.push_macro_attributes("Sunset,title=Over the sea", &["alt"], &mut context);
+        let (_, title, _, _) = pending.into_fields(&mut context);
+        assert_eq!(Some("Over the sea".to_owned()), title_value(title));
+
+        let mut pending = PendingMetadata::default();
+        assert!(pending.push("[title=First]", &mut context));
+        assert!(pending.push(".Second", &mut context));
+        let (_, title, _, _) = pending.into_fields(&mut context);
+        assert_eq!(Some("Second".to_owned()), title_value(title));
+    }
+
+    #[test]
+    fn reftext_location() {
+        let mut context = Context::default();
+        let mut pending = PendingMetadata::default();
+        for line in [".Title", "", "[[a1,Ref Text]]"] {
+            context.next_line();
+            pending.push(line, &mut context);
+        }
+
+        let (_, _, reftext, _) = pending.into_fields(&mut context);
+        let start = reftext.unwrap()[0].location().unwrap().start().clone();
+        assert_eq!((3, 6), (start.line(), start.column()));
+    }
+}
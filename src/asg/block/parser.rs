@@ -0,0 +1,281 @@
+use super::{
+    AdmonitionVariant, AnyList, Block, BlockMacro, Context, LineKind, PendingMetadata, TrimIndent,
+};
+
+use crate::asg::AttributeEntryParser;
+
+use crate::Error;
+
+/// Parses the lines of a sequence of non-section blocks, such as the content
+/// of a section or of a compound delimited block.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BlockParser {
+    blocks: Vec<Block>,
+    current_block: Option<Box<Block>>,
+    comment_delimiter: Option<String>,
+    previous_line: String,
+    metadata: PendingMetadata,
+    attribute_entries: AttributeEntryParser,
+}
+impl BlockParser {
+    /// Starts with `block` open, as if `line` had just been read into it.
+    pub(crate) fn starting_with(block: Block, line: &str) -> Self {
+        Self {
+            current_block: Some(Box::new(block)),
+            previous_line: line.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns whether the next line would start a new block, as a section
+    /// heading has to.
+    pub(crate) fn is_at_block_boundary(&self) -> bool {
+        self.previous_line.is_empty() && !self.is_in_delimited_block()
+    }
+
+    /// Returns whether the next line belongs to an open comment or delimited
+    /// block, including one attached to a list item.
+    pub(crate) fn is_in_delimited_block(&self) -> bool {
+        self.comment_delimiter.is_some()
+            || self
+                .current_block
+                .as_ref()
+                .is_some_and(|b| b.is_in_delimited_block())
+    }
+
+    /// Returns whether a block is open and would take the next line of text.
+    pub(crate) fn has_current_block(&self) -> bool {
+        self.current_block.is_some()
+    }
+
+    /// Returns the list being parsed, which takes the next lines first.
+    pub(crate) fn current_list_mut(&mut self) -> Option<&mut AnyList> {
+        match self.current_block.as_deref_mut() {
+            Some(Block::AnyList(list)) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns whether metadata lines are waiting for the next block.
+    pub(crate) fn has_pending_metadata(&self) -> bool {
+        !self.metadata.is_empty()
+    }
+
+    /// Returns whether no block has been read yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.blocks.is_empty() && self.current_block.is_none()
+    }
+
+    /// Ends the current block and takes all the parsed blocks.
+    pub(crate) fn end(&mut self, context: &mut Context) -> Vec<Block> {
+        if let Some(mut current) = self.current_block.take() {
+            current.end(context);
+            self.blocks.push(*current);
+        }
+        self.previous_line = "".to_owned();
+        self.metadata = Default::default();
+
+        std::mem::take(&mut self.blocks)
+    }
+
+    /// Takes the metadata lines read since the last block, for a section heading.
+    pub(crate) fn take_metadata(&mut self) -> PendingMetadata {
+        std::mem::take(&mut self.metadata)
+    }
+
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<(), Error> {
+        if self.comment_delimiter.is_some() {
+            if matches!(LineKind::parse(line.to_owned()), LineKind::CommentDelimiter(x) if matches!(&self.comment_delimiter, Some(y) if x == *y))
+            {
+                self.previous_line = "".to_owned();
+                self.comment_delimiter = None;
+            }
+
+            return Ok(());
+        }
+
+        if let Some(current) = self.current_block.as_mut() {
+            if current.is_delimited_block() {
+                if LineKind::parse(line.to_owned()).block_delimiter() != current.delimiter() {
+                    self.previous_line = line.to_owned();
+                    current.push(line, context)?;
+
+                    return Ok(());
+                }
+
+                // The closing delimiter ends the block like a blank line does.
+                self.previous_line = "".to_owned();
+                current.close(line, context);
+                self.push_current_block();
+
+                return Ok(());
+            }
+
+            if let Block::AnyList(list) = current.as_mut() {
+                if !list.is_in_delimited_block() {
+                    match LineKind::parse(line.to_owned()) {
+                        LineKind::Empty if self.previous_line == "//" => {
+                            // A line comment between blank lines separates two lists.
+                            self.previous_line = "".to_owned();
+                            current.end(context);
+                            self.push_current_block();
+
+                            return Ok(());
+                        }
+                        LineKind::CommentMarker if self.previous_line.is_empty() => {
+                            self.previous_line = "//".to_owned();
+
+                            return Ok(());
+                        }
+                        _ => {}
+                    }
+                }
+
+                if list.push(line, context)? {
+                    self.previous_line = line.trim().to_owned();
+
+                    return Ok(());
+                }
+
+                current.end(context);
+                self.push_current_block();
+            } else {
+                match LineKind::parse(line.to_owned()) {
+                    LineKind::Empty => {
+                        self.previous_line = "".to_owned();
+                        current.end(context);
+                        self.push_current_block();
+
+                        return Ok(());
+                    }
+                    // A line comment is skipped without ending the paragraph.
+                    LineKind::CommentMarker => return Ok(()),
+                    LineKind::CommentDelimiter(delimiter) => {
+                        self.comment_delimiter = Some(delimiter);
+
+                        current.end(context);
+                        self.push_current_block();
+
+                        return Ok(());
+                    }
+                    kind if kind.block_delimiter().is_some()
+                        || PendingMetadata::is_attribute_line(line) =>
+                    {
+                        // A block delimiter or attribute line interrupts the paragraph.
+                        current.end(context);
+                        self.push_current_block();
+                    }
+                    _ => {
+                        self.previous_line = line.to_owned();
+
+                        return current.push(line, context);
+                    }
+                }
+            }
+        }
+
+        self.parse_block(line, context)
+    }
+
+    /// Moves the current block, once ended or closed, to the parsed blocks.
+    fn push_current_block(&mut self) {
+        if let Some(current) = self.current_block.take() {
+            self.blocks.push(*current);
+        }
+    }
+
+    fn parse_block(&mut self, line: &str, context: &mut Context) -> Result<(), Error> {
+        // An attribute entry with an invalid name is read as text.
+        if let Ok(Some(entry)) = self.attribute_entries.parse_line(line, context.line_number) {
+            context.apply_attribute_entry(entry);
+            self.previous_line = "".to_owned();
+
+            return Ok(());
+        }
+
+        if self.metadata.push(line, context) {
+            self.previous_line = "".to_owned();
+
+            return Ok(());
+        }
+
+        if let Some(mut block) = Block::new_delimited(line, self.metadata.style(), context) {
+            block.set_metadata(self.take_metadata(), context);
+            self.previous_line = line.to_owned();
+            self.current_block = Some(Box::new(block));
+
+            return Ok(());
+        }
+
+        let mut block = match LineKind::parse(line.to_owned()) {
+            LineKind::Empty | LineKind::CommentMarker => {
+                self.previous_line = "".to_owned();
+
+                return Ok(());
+            }
+            LineKind::CommentDelimiter(delimiter) => {
+                self.comment_delimiter = Some(delimiter);
+
+                return Ok(());
+            }
+            LineKind::UnorderedListMarker { marker, principal } => {
+                Block::new_unordered_list(marker, principal, line, context)
+            }
+            LineKind::OrderedListMarker { marker, principal } => {
+                Block::new_ordered_list(marker, principal, line, context)
+            }
+            LineKind::OffsetOrderedListMarker {
+                marker, principal, ..
+            } => Block::new_ordered_list(marker, principal, line, context),
+            LineKind::CalloutListMarker { marker, principal } => {
+                Block::new_callout_list(marker, principal, line, context)
+            }
+            LineKind::DescriptionListMarker {
+                marker,
+                term,
+                principal,
+            } => Block::new_description_list(marker, term, principal, line, context),
+            LineKind::BlockMacro {
+                name,
+                target,
+                attrlist,
+            } => {
+                let names = BlockMacro::positional_names(&name);
+                self.metadata
+                    .push_macro_attributes(&attrlist, names, context);
+                Block::new_block_macro(&name, &target, line, context)
+                    .unwrap_or_else(|| Block::new_paragraph(line, context))
+            }
+            LineKind::ThematicBreak => Block::new_thematic_break(line, context),
+            LineKind::PageBreak => Block::new_page_break(line, context),
+            LineKind::AdmonitionMarker { variant, text } => {
+                Block::new_admonition_paragraph(variant, &text, line, context)
+            }
+            _ => match self
+                .metadata
+                .style()
+                .and_then(AdmonitionVariant::from_label)
+            {
+                Some(variant) => {
+                    Block::new_admonition_paragraph(variant, line.trim_indent(), line, context)
+                }
+                None => Block::new_paragraph(line, context),
+            },
+        };
+
+        block.set_metadata(self.take_metadata(), context);
+
+        if let Block::BlockMacro(_) | Block::BlockBreak(_) = block {
+            // A block macro or a break is made of a single line.
+            self.previous_line = "".to_owned();
+            self.blocks.push(block);
+
+            return Ok(());
+        }
+
+        self.previous_line = line.to_owned();
+        self.current_block = Some(Box::new(block));
+
+        Ok(())
+    }
+}
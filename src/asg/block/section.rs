@@ -1,10 +1,10 @@
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use super::{Block, LineKind, SectionBody};
+use super::{BlockMetadata, BlockParser, Context, PendingMetadata, SectionBody};
 use crate::asg::{Inline, Location, NodeType};
 
-use std::error::Error;
+use crate::Error;
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
@@ -12,318 +12,197 @@ pub struct Section {
     name: String,
     #[serde(rename = "type")]
     node_type: NodeType,
+    id: Option<String>,
     title: Vec<Inline>,
+    reftext: Option<Vec<Inline>>,
+    metadata: Option<BlockMetadata>,
     pub(crate) level: usize,
-    blocks: Vec<SectionBody>,
+    pub(crate) blocks: Vec<SectionBody>,
     location: Option<Location>,
 
+    // Boxed so that a section is not much larger than the blocks beside it.
     #[serde(skip)]
-    current_block: Option<Block>,
-    #[serde(skip)]
-    comment_delimiter: Option<String>,
-    #[serde(skip)]
-    previous_line: String,
+    parser: Box<BlockParser>,
 }
 impl Section {
-    pub(crate) fn new(level: usize, heading: &str) -> Self {
+    pub(crate) fn new(level: usize, heading: &str, context: &mut Context) -> Self {
+        // The heading line is made of `level + 1` markers and a space before the
+        // title, less the level offset of an included file.
+        let markers = level.saturating_add_signed(-context.level_offset) + 1;
+        let title_start = context.boundary(markers + 2);
+        let heading_end = context.boundary(markers + 1 + heading.chars().count());
+
         Self {
             name: "section".to_owned(),
             node_type: NodeType::Block,
-            title: Inline::new(heading),
+            id: None,
+            title: Inline::new(heading, &title_start, &mut context.attributes),
+            reftext: None,
+            metadata: None,
             level,
             blocks: Vec::with_capacity(0),
-            location: None,
-            current_block: None,
-            comment_delimiter: None,
-            previous_line: "".to_owned(),
+            location: Some(Location::new(context.boundary(1), heading_end)),
+            parser: Default::default(),
         }
     }
 
-    pub(crate) fn end(&mut self) {
-        if let Some(current) = self.current_block.as_mut() {
-            current.end();
-            self.blocks.push(SectionBody::Block(current.clone()));
-            self.current_block = None;
-        } else if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
-            last.end();
+    pub(crate) fn end(&mut self, context: &mut Context) {
+        if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
+            last.end(context);
+        } else {
+            self.end_blocks(context);
         }
-    }
 
-    pub(crate) fn push(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
-        if self.comment_delimiter.is_some() {
-            if matches!(LineKind::parse(line.to_owned()), LineKind::CommentDelimiter(x) if matches!(&self.comment_delimiter, Some(y) if x == *y))
-            {
-                self.previous_line = "".to_owned();
-                self.comment_delimiter = None;
-            }
-
-            return Ok(());
+        let end = self
+            .blocks
+            .last()
+            .and_then(|b| b.location())
+            .map(|l| l.end());
+        if let (Some(location), Some(end)) = (self.location.as_mut(), end) {
+            location.set_end(end.clone());
         }
+    }
 
-        if let Some(current) = self.current_block.as_mut() {
-            if current.is_delimited_block() {
-                self.previous_line = line.to_owned();
-
-                if LineKind::parse(line.to_owned()).block_delimiter() != current.delimiter() {
-                    current.push(line)?;
-
-                    return Ok(());
-                }
-
-                current.end();
-                self.blocks.push(SectionBody::Block(current.clone()));
-                self.current_block = None;
+    /// Moves the blocks parsed so far before the subsections.
+    fn end_blocks(&mut self, context: &mut Context) {
+        let blocks = self.parser.end(context);
+        self.blocks
+            .extend(blocks.into_iter().map(SectionBody::Block));
+    }
 
-                return Ok(());
-            }
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        // A block title does not apply to a section, which has its own.
+        let (id, _, reftext, metadata) = pending.into_fields(context);
+        (self.id, self.reftext, self.metadata) = (id, reftext, metadata);
+    }
 
-            if let Block::AnyList(_list) = current {
-                match LineKind::parse(line.to_owned()) {
-                    LineKind::Empty => {
-                        if self.previous_line == "//" {
-                            self.previous_line = "".to_owned();
-                            current.end();
-                            self.blocks.push(SectionBody::Block(current.clone()));
-                            self.current_block = None;
-
-                            return Ok(());
-                        }
-
-                        self.previous_line = "".to_owned();
-
-                        return Ok(());
-                    }
-                    LineKind::CommentMarker => {
-                        if self.previous_line.is_empty() {
-                            self.previous_line = "//".to_owned();
-                        }
-
-                        return Ok(());
-                    }
-                    _ => {
-                        if !self.previous_line.is_empty() {
-                            self.previous_line = line.to_owned();
-                            current.push(line)?;
-
-                            return Ok(());
-                        }
-
-                        current.end();
-                        self.blocks.push(SectionBody::Block(current.clone()));
-                        self.current_block = None;
-                    }
-                }
-            } else {
-                match LineKind::parse(line.to_owned()) {
-                    LineKind::Empty => {
-                        self.previous_line = "".to_owned();
-                        current.end();
-                        self.blocks.push(SectionBody::Block(current.clone()));
-                        self.current_block = None;
-
-                        return Ok(());
-                    }
-                    LineKind::CommentMarker => {
-                        self.previous_line = "".to_owned();
-
-                        return Ok(());
-                    }
-                    LineKind::CommentDelimiter(delimiter) => {
-                        self.comment_delimiter = Some(delimiter);
-
-                        current.end();
-                        self.blocks.push(SectionBody::Block(current.clone()));
-                        self.current_block = None;
-
-                        return Ok(());
-                    }
-                    _ => {
-                        self.previous_line = line.to_owned();
-
-                        return current.push(line);
-                    }
-                }
-            }
+    /// Takes the metadata lines read before a heading in the innermost section.
+    pub(crate) fn take_metadata(&mut self) -> PendingMetadata {
+        match self.blocks.last_mut() {
+            Some(SectionBody::Section(last)) => last.take_metadata(),
+            _ => self.parser.take_metadata(),
         }
+    }
 
-        if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
-            match LineKind::parse(line.to_owned()) {
-                LineKind::HeadingMarker { level, title } => {
-                    if !self.previous_line.is_empty() {
-                        self.previous_line = line.to_owned();
-
-                        return last.push(line);
-                    }
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
 
-                    if level == last.level {
-                        self.previous_line = line.to_owned();
-                        let section = Section::new(level, &title);
-                        self.blocks.push(SectionBody::Section(section));
+    pub fn title(&self) -> &[Inline] {
+        &self.title
+    }
 
-                        return Ok(());
-                    }
-                }
-                _ => {}
-            }
+    pub fn title_mut(&mut self) -> &mut Vec<Inline> {
+        &mut self.title
+    }
 
-            self.previous_line = line.to_owned();
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        self.reftext.as_deref()
+    }
 
-            return last.push(line);
-        }
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        self.metadata.as_ref()
+    }
 
-        self.parse_content(line)
+    /// Returns the level of the section, from 0 for a part of a book.
+    pub fn level(&self) -> usize {
+        self.level
     }
 
-    fn parse_content(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
-        match LineKind::parse(line.to_owned()) {
-            LineKind::Empty => {
-                self.previous_line = "".to_owned();
+    pub fn set_level(&mut self, level: usize) {
+        self.level = level;
+    }
 
-                Ok(())
-            }
-            LineKind::CommentMarker => {
-                self.previous_line = "".to_owned();
+    /// Returns the blocks of the section, followed by its subsections.
+    pub fn blocks(&self) -> &[SectionBody] {
+        &self.blocks
+    }
 
-                Ok(())
-            }
-            LineKind::CommentDelimiter(delimiter) => {
-                self.comment_delimiter = Some(delimiter);
+    pub fn blocks_mut(&mut self) -> &mut Vec<SectionBody> {
+        &mut self.blocks
+    }
 
-                Ok(())
-            }
-            LineKind::HeadingMarker { level, title } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
 
-                    return Ok(());
-                }
+    /// Returns whether the next line would start a new block in the innermost section.
+    pub(crate) fn is_at_block_boundary(&self) -> bool {
+        match self.blocks.last() {
+            Some(SectionBody::Section(last)) => last.is_at_block_boundary(),
+            _ => self.parser.is_at_block_boundary(),
+        }
+    }
 
-                if level == self.level + 1 {
-                    self.previous_line = line.to_owned();
-                    let section = Section::new(level, &title);
+    pub(crate) fn push(&mut self, line: &str, context: &mut Context) -> Result<(), Error> {
+        if let Some(SectionBody::Section(last)) = self.blocks.last_mut() {
+            if let Some((level, title)) = context.heading(line) {
+                if level == last.level && last.is_at_block_boundary() {
+                    let metadata = last.take_metadata();
+                    last.end(context);
+                    let mut section = Section::new(level, &title, context);
+                    section.set_metadata(metadata, context);
                     self.blocks.push(SectionBody::Section(section));
 
                     return Ok(());
                 }
-
-                self.previous_line = line.to_owned();
-                let paragraph = Block::new_paragraph(line);
-                self.current_block = Some(paragraph);
-
-                Err("cannot skip section level".into())
-            }
-            LineKind::UnorderedListMarker { marker, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let unordered_list = Block::new_unordered_list(marker, principal);
-                self.current_block = Some(unordered_list);
-
-                Ok(())
-            }
-            LineKind::OrderedListMarker { marker, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let ordered_list = Block::new_ordered_list(marker, principal);
-                self.current_block = Some(ordered_list);
-
-                Ok(())
             }
-            LineKind::OffsetOrderedListMarker { offset, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
 
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let ordered_list = Block::new_ordered_list(format!("{}.", offset), principal);
-                self.current_block = Some(ordered_list);
-
-                Ok(())
-            }
-            LineKind::CalloutListMarker { marker, principal } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
-
-                    return Ok(());
-                }
-
-                self.previous_line = line.to_owned();
-                let callout_list = Block::new_callout_list(marker, principal);
-                self.current_block = Some(callout_list);
-
-                Ok(())
-            }
-            LineKind::DescriptionListMarker {
-                marker,
-                term,
-                principal,
-            } => {
-                if !self.previous_line.is_empty() {
-                    self.previous_line = line.to_owned();
-                    let paragraph = Block::new_paragraph(line);
-                    self.current_block = Some(paragraph);
+            return last.push(line, context);
+        }
 
-                    return Ok(());
+        if let Some((level, title)) = context.heading(line) {
+            if self.parser.is_at_block_boundary() {
+                if level != self.level + 1 {
+                    self.parser.push(line, context)?;
+
+                    return Err(Error::SkippedSectionLevel {
+                        expected: self.level + 1,
+                        found: level,
+                        line: context.line_number,
+                        column: 1,
+                    });
                 }
 
-                self.previous_line = line.to_owned();
-                let description_list = Block::new_description_list(marker, term, principal);
-                self.current_block = Some(description_list);
-
-                Ok(())
-            }
-            LineKind::Unknown => {
-                self.previous_line = line.to_owned();
-                let paragraph = Block::new_paragraph(line);
-                self.current_block = Some(paragraph);
-
-                Ok(())
-            }
-            _ => {
-                self.previous_line = line.to_owned();
-                let paragraph = Block::new_paragraph(line);
-                self.current_block = Some(paragraph);
+                let metadata = self.parser.take_metadata();
+                self.end_blocks(context);
+                let mut section = Section::new(level, &title, context);
+                section.set_metadata(metadata, context);
+                self.blocks.push(SectionBody::Section(section));
 
-                Ok(())
+                return Ok(());
             }
         }
+
+        self.parser.push(line, context)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::asg::{block::BlockLeaf, inlines::Inline};
+    use crate::asg::{
+        block::{
+            AdmonitionVariant, AnyList, Block, BlockBreakVariant, BlockLeaf, BlockMacro,
+            BlockParent, CellStyle, DlistStyle, HorizontalAlignment,
+        },
+        inlines::{
+            tests::{inlines, without_locations},
+            Inline,
+        },
+        LocationBoundary,
+    };
 
     use super::*;
 
-    fn parse(text: &'static str) -> Result<Section, Box<dyn Error>> {
-        let mut section = Section::new(0, "");
+    fn parse(text: &'static str) -> Result<Section, Error> {
+        let mut context = Context::default();
+        let mut section = Section::new(0, "", &mut context);
 
         for line in text.lines() {
-            section.push(line)?;
+            context.next_line();
+            section.push(line, &mut context)?;
         }
-        section.end();
+        section.end(&mut context);
 
         Ok(section)
     }
@@ -337,35 +216,50 @@ mod tests {
         let Some(SectionBody::Section(level1_section)) = section.blocks.first() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Level 1 Section Title"), level1_section.title);
+        assert_eq!(
+            inlines("Level 1 Section Title"),
+            without_locations(&level1_section.title)
+        );
         assert_eq!(1, level1_section.blocks.len());
         let Some(SectionBody::Section(level2_section)) = level1_section.blocks.first() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Level 2 Section Title"), level2_section.title);
+        assert_eq!(
+            inlines("Level 2 Section Title"),
+            without_locations(&level2_section.title)
+        );
         assert_eq!(1, level2_section.blocks.len());
         let Some(SectionBody::Section(level3_section)) = level2_section.blocks.first() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Level 3 Section Title"), level3_section.title);
+        assert_eq!(
+            inlines("Level 3 Section Title"),
+            without_locations(&level3_section.title)
+        );
         assert_eq!(1, level3_section.blocks.len());
         let Some(SectionBody::Section(level4_section)) = level3_section.blocks.first() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Level 4 Section Title"), level4_section.title);
+        assert_eq!(
+            inlines("Level 4 Section Title"),
+            without_locations(&level4_section.title)
+        );
         assert_eq!(1, level4_section.blocks.len());
         let Some(SectionBody::Section(level5_section)) = level4_section.blocks.first() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Level 5 Section Title"), level5_section.title);
+        assert_eq!(
+            inlines("Level 5 Section Title"),
+            without_locations(&level5_section.title)
+        );
         assert_eq!(0, level5_section.blocks.len());
 
         let Some(SectionBody::Section(another_level1_section)) = section.blocks.last() else {
             panic!("cannot call");
         };
         assert_eq!(
-            Inline::new("Another Level 1 Section Title"),
-            another_level1_section.title
+            inlines("Another Level 1 Section Title"),
+            without_locations(&another_level1_section.title)
         );
         assert_eq!(0, another_level1_section.blocks.len());
     }
@@ -379,7 +273,10 @@ mod tests {
         let Some(SectionBody::Section(first_section)) = section.blocks.first() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("First Section"), first_section.title);
+        assert_eq!(
+            inlines("First Section"),
+            without_locations(&first_section.title)
+        );
         assert_eq!(2, first_section.blocks.len());
         let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(
             content_of_first_section,
@@ -388,14 +285,17 @@ mod tests {
             panic!("cannot call");
         };
         assert_eq!(
-            Inline::new("Content of first section"),
-            content_of_first_section.inlines()
+            inlines("Content of first section"),
+            without_locations(content_of_first_section.inlines())
         );
 
         let Some(SectionBody::Section(nested_section)) = first_section.blocks.last() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Nested Section"), nested_section.title);
+        assert_eq!(
+            inlines("Nested Section"),
+            without_locations(&nested_section.title)
+        );
         assert_eq!(1, nested_section.blocks.len());
         let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(
             content_of_nested_section,
@@ -404,14 +304,17 @@ mod tests {
             panic!("cannot call");
         };
         assert_eq!(
-            Inline::new("Content of nested section"),
-            content_of_nested_section.inlines()
+            inlines("Content of nested section"),
+            without_locations(content_of_nested_section.inlines())
         );
 
         let Some(SectionBody::Section(second_section)) = section.blocks.last() else {
             panic!("cannot call");
         };
-        assert_eq!(Inline::new("Second Section"), second_section.title);
+        assert_eq!(
+            inlines("Second Section"),
+            without_locations(&second_section.title)
+        );
         assert_eq!(1, second_section.blocks.len());
         let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(
             content_of_second_section,
@@ -420,14 +323,446 @@ mod tests {
             panic!("cannot call");
         };
         assert_eq!(
-            Inline::new("Content of second section"),
-            content_of_second_section.inlines()
+            inlines("Content of second section"),
+            without_locations(content_of_second_section.inlines())
         );
     }
 
     #[test]
-    #[should_panic]
     fn illegal_level_skipped_section_block() {
-        parse("== First Section\n\n==== Illegal Nested Section (violates rule #2)").unwrap();
+        let error = parse("== First Section\n\n==== Illegal Nested Section (violates rule #2)")
+            .unwrap_err();
+
+        assert_eq!(
+            Error::SkippedSectionLevel {
+                expected: 2,
+                found: 3,
+                line: 3,
+                column: 1
+            },
+            error
+        );
+    }
+
+    #[test]
+    fn delimited_leaf_blocks() {
+        let section = parse("Paragraph\n----\n  indented\n\n*not bold*\n------\n----\n\n....\n....\n++++\n<b>raw</b>\n++++").unwrap();
+        let verbatim = |text: &str| {
+            let boundary = LocationBoundary::new(1, 1, None);
+            without_locations(&[Inline::new_verbatim_text(
+                text,
+                Location::new(boundary.clone(), boundary),
+            )])
+        };
+
+        assert_eq!(4, section.blocks.len());
+
+        let Some(SectionBody::Block(Block::BlockLeaf(listing @ BlockLeaf::Listing(body)))) =
+            section.blocks.get(1)
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("----".to_owned()), listing.delimiter());
+        assert_eq!(
+            verbatim("  indented\n\n*not bold*\n------"),
+            without_locations(body.inlines())
+        );
+        assert_eq!(
+            Some(&Location::new(
+                LocationBoundary::new(2, 1, None),
+                LocationBoundary::new(7, 4, None)
+            )),
+            listing.location()
+        );
+
+        let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Literal(body)))) =
+            section.blocks.get(2)
+        else {
+            panic!("cannot call");
+        };
+        assert!(body.inlines().is_empty());
+
+        let Some(SectionBody::Block(Block::BlockLeaf(pass @ BlockLeaf::Pass(_)))) =
+            section.blocks.get(3)
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("++++".to_owned()), pass.delimiter());
+    }
+
+    #[test]
+    fn compound_delimited_blocks() {
+        let section = parse("****\nSidebar\n\n====\n* item\n======\n----\n------\n----\n======\n====\n****\n\n--\nOpen\n--").unwrap();
+
+        assert_eq!(2, section.blocks.len());
+
+        let Some(SectionBody::Block(Block::BlockParent(BlockParent::Sidebar(sidebar)))) =
+            section.blocks.first()
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(2, sidebar.blocks().len());
+        let Some(Block::BlockParent(example @ BlockParent::Example(body))) =
+            sidebar.blocks().last()
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("====".to_owned()), example.delimiter());
+        assert_eq!(
+            Some(&Location::new(
+                LocationBoundary::new(4, 1, None),
+                LocationBoundary::new(11, 4, None)
+            )),
+            example.location()
+        );
+        assert_eq!(2, body.blocks().len());
+        let Some(Block::BlockParent(BlockParent::Example(nested))) = body.blocks().last() else {
+            panic!("cannot call");
+        };
+        let Some(Block::BlockLeaf(BlockLeaf::Listing(listing))) = nested.blocks().first() else {
+            panic!("cannot call");
+        };
+        assert_eq!(inlines("------"), without_locations(listing.inlines()));
+
+        let Some(SectionBody::Block(Block::BlockParent(open @ BlockParent::Open(_)))) =
+            section.blocks.last()
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("--".to_owned()), open.delimiter());
+    }
+
+    #[test]
+    fn block_metadata() {
+        let section = parse("[[first]]\n.Title\nParagraph\n[verse, Carl Sandburg]\n____\nThe fog comes\n____\n\n[#next.role]\n== Next").unwrap();
+
+        assert_eq!(3, section.blocks.len());
+        let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(paragraph)))) =
+            section.blocks.first()
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("first"), paragraph.id());
+        assert_eq!(
+            Some(inlines("Title")),
+            paragraph.title().map(without_locations)
+        );
+
+        let Some(SectionBody::Block(Block::BlockLeaf(BlockLeaf::Verse(verse)))) =
+            section.blocks.get(1)
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(
+            Some("Carl Sandburg"),
+            verse.metadata().and_then(|m| m.attribute("2"))
+        );
+
+        let Some(SectionBody::Section(next)) = section.blocks.last() else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("next".to_owned()), next.id);
+        assert!(next.metadata.is_some());
+    }
+
+    #[test]
+    fn admonition_blocks() {
+        let section =
+            parse("NOTE: Remember this\nand that.\n\n[TIP]\nTip\n\n[WARNING]\n====\nCareful\n====")
+                .unwrap();
+
+        let variants: Vec<_> = section
+            .blocks
+            .iter()
+            .map(|b| match b {
+                SectionBody::Block(Block::BlockParent(BlockParent::Admonition(body))) => {
+                    (body.variant(), body.blocks().len())
+                }
+                _ => panic!("not expected"),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(AdmonitionVariant::Note), 1),
+                (Some(AdmonitionVariant::Tip), 1),
+                (Some(AdmonitionVariant::Warning), 1)
+            ],
+            variants
+        );
+
+        let Some(SectionBody::Block(Block::BlockParent(BlockParent::Admonition(note)))) =
+            section.blocks.first()
+        else {
+            panic!("cannot call");
+        };
+        let Some(Block::BlockLeaf(BlockLeaf::Paragraph(paragraph))) = note.blocks().first() else {
+            panic!("cannot call");
+        };
+        assert_eq!(
+            inlines("Remember this\nand that."),
+            without_locations(paragraph.inlines())
+        );
+        assert_eq!(
+            Some(&Location::new(
+                LocationBoundary::new(1, 7, None),
+                LocationBoundary::new(2, 9, None)
+            )),
+            Block::BlockLeaf(BlockLeaf::Paragraph(paragraph.clone())).location()
+        );
+    }
+
+    #[test]
+    fn block_macros() {
+        let section =
+            parse("[#sunset]\nimage::sunset.jpg[Sunset, 300, title=\"Over, the sea\"]\ntoc::[]\naudio::ocean.wav[]\n").unwrap();
+
+        assert_eq!(3, section.blocks.len());
+        let Some(SectionBody::Block(image_block @ Block::BlockMacro(image @ BlockMacro::Image(_)))) =
+            section.blocks.first()
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(Some("sunset.jpg"), image.target());
+        let metadata = image.metadata().unwrap();
+        assert_eq!(Some("Sunset"), metadata.attribute("alt"));
+        assert_eq!(Some("300"), metadata.attribute("width"));
+        assert_eq!(Some("Over, the sea"), metadata.attribute("title"));
+        assert_eq!(
+            Some(inlines("Over, the sea").as_slice()),
+            image_block.title().map(without_locations).as_deref()
+        );
+
+        let Some(SectionBody::Block(Block::BlockMacro(toc @ BlockMacro::Toc(_)))) =
+            section.blocks.get(1)
+        else {
+            panic!("cannot call");
+        };
+        assert_eq!(None, toc.target());
+    }
+
+    #[test]
+    fn block_breaks() {
+        let section = parse("First\n\n'''\n* * *\n<<<\nLast").unwrap();
+
+        let kinds: Vec<_> = section
+            .blocks
+            .iter()
+            .map(|b| match b {
+                SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(_))) => "paragraph",
+                SectionBody::Block(Block::BlockBreak(b)) => match b.variant() {
+                    BlockBreakVariant::Page => "page",
+                    BlockBreakVariant::Thematic => "thematic",
+                },
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            vec!["paragraph", "thematic", "thematic", "page", "paragraph"],
+            kinds
+        );
+    }
+
+    #[test]
+    fn line_comment_in_paragraph() {
+        let section = parse("para\n// c\n== Heading\nmore").unwrap();
+
+        let [SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(paragraph)))] =
+            section.blocks.as_slice()
+        else {
+            panic!("expected a single paragraph");
+        };
+        assert_eq!(
+            inlines("para\n== Heading\nmore"),
+            without_locations(paragraph.inlines())
+        );
+    }
+
+    #[test]
+    fn list_continuation() {
+        let section = parse(
+            "* first\nwrapped\n+\n----\ncode\n----\n+\nattached\n\n* second\n\n//\n\nterm::\n+\n--\nopen\n--\nafter",
+        )
+        .unwrap();
+
+        assert_eq!(3, section.blocks.len());
+
+        let SectionBody::Block(Block::AnyList(AnyList::List { items, .. })) = &section.blocks[0]
+        else {
+            panic!("not expected")
+        };
+        assert_eq!(2, items.len());
+        assert_eq!(
+            inlines("first\nwrapped"),
+            without_locations(items[0].principal())
+        );
+        assert!(matches!(
+            items[0].blocks(),
+            [
+                Block::BlockLeaf(BlockLeaf::Listing(_)),
+                Block::BlockLeaf(BlockLeaf::Paragraph(_))
+            ]
+        ));
+        assert!(items[1].blocks().is_empty());
+
+        let SectionBody::Block(Block::AnyList(AnyList::Dlist { items, .. })) = &section.blocks[1]
+        else {
+            panic!("not expected")
+        };
+        assert!(items[0].principal().is_empty());
+        assert!(matches!(
+            items[0].blocks(),
+            [Block::BlockParent(BlockParent::Open(_))]
+        ));
+
+        assert!(matches!(
+            section.blocks[2],
+            SectionBody::Block(Block::BlockLeaf(BlockLeaf::Paragraph(_)))
+        ));
+    }
+
+    #[test]
+    fn nested_lists() {
+        let section = parse("* a\n** b\n*** c\n** d\n. e\n* f\n\n.. g").unwrap();
+
+        let markers = |list: &AnyList| match list {
+            AnyList::List { items, .. } => items
+                .iter()
+                .map(|i| (without_locations(i.principal()), i.blocks().len()))
+                .collect::<Vec<_>>(),
+            AnyList::Dlist { .. } => panic!("not expected"),
+        };
+        let nested = |blocks: &[Block]| match blocks {
+            [Block::AnyList(list)] => list.clone(),
+            _ => panic!("not expected"),
+        };
+
+        let [SectionBody::Block(Block::AnyList(list))] = section.blocks.as_slice() else {
+            panic!("not expected")
+        };
+        assert_eq!(vec![(inlines("a"), 1), (inlines("f"), 1)], markers(list));
+
+        let AnyList::List { items, .. } = list else {
+            panic!("not expected")
+        };
+        let level_2 = nested(items[0].blocks());
+        assert_eq!(
+            vec![(inlines("b"), 1), (inlines("d"), 1)],
+            markers(&level_2)
+        );
+
+        let AnyList::List {
+            items: level_2_items,
+            ..
+        } = &level_2
+        else {
+            panic!("not expected")
+        };
+        assert!(matches!(
+            nested(level_2_items[1].blocks()),
+            AnyList::List { marker, .. } if marker == "."
+        ));
+        assert!(matches!(
+            nested(items[1].blocks()),
+            AnyList::List { marker, .. } if marker == ".."
+        ));
+    }
+
+    #[test]
+    fn description_lists() {
+        let section = parse(
+            "[horizontal]\n*CPU*::\nProcessor:: The brain\nPins;; Many\nRAM::: Memory\n\n//\n\n[qanda]\nWhy?:: Because",
+        )
+        .unwrap();
+
+        let [SectionBody::Block(Block::AnyList(horizontal)), SectionBody::Block(Block::AnyList(qanda))] =
+            section.blocks.as_slice()
+        else {
+            panic!("not expected")
+        };
+        assert_eq!(Some(DlistStyle::Horizontal), horizontal.dlist_style());
+        assert_eq!(Some(DlistStyle::Qanda), qanda.dlist_style());
+
+        let AnyList::Dlist { items, .. } = horizontal else {
+            panic!("not expected")
+        };
+        assert_eq!(1, items.len());
+        assert_eq!(
+            vec![inlines("*CPU*"), inlines("Processor")],
+            items[0]
+                .terms()
+                .iter()
+                .map(|t| without_locations(t))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            inlines("The brain"),
+            without_locations(items[0].principal())
+        );
+
+        let [Block::AnyList(semicolons @ AnyList::Dlist { items: nested, .. })] = items[0].blocks()
+        else {
+            panic!("not expected")
+        };
+        assert!(semicolons.dlist_style().is_none());
+        assert_eq!(
+            vec![inlines("Pins")],
+            nested[0]
+                .terms()
+                .iter()
+                .map(|t| without_locations(t))
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            nested[0].blocks(),
+            [Block::AnyList(AnyList::Dlist { marker, .. })] if marker == ":::"
+        ));
+    }
+
+    #[test]
+    fn tables() {
+        let section = parse(
+            "[cols=\"1,>2a\"]\n|===\n|Name |Description\n\n2+|spanned\n.2+|tall |* item\n|second\n|===\n\n[%footer]\n,===\nname,\"quoted, \"\"value\"\"\"\ntotal,2\n,===",
+        )
+        .unwrap();
+
+        let [SectionBody::Block(Block::Table(psv)), SectionBody::Block(Block::Table(csv))] =
+            section.blocks.as_slice()
+        else {
+            panic!("not expected")
+        };
+        assert_eq!(
+            vec![Some(1), Some(2)],
+            psv.columns().iter().map(|c| c.width()).collect::<Vec<_>>()
+        );
+        assert_eq!(HorizontalAlignment::Right, psv.columns()[1].halign());
+
+        let [head] = psv.head() else {
+            panic!("not expected")
+        };
+        assert_eq!(
+            Some(inlines("Name")),
+            head[0].inlines().map(without_locations)
+        );
+        assert_eq!(CellStyle::Asciidoc, head[1].style());
+        assert!(head[1].blocks().is_none());
+
+        let [spanned, tall, second] = psv.body() else {
+            panic!("not expected")
+        };
+        assert_eq!((1, 2), (spanned.len(), spanned[0].colspan()));
+        assert_eq!((2, 2), (tall.len(), tall[0].rowspan()));
+        assert!(matches!(tall[1].blocks(), Some([Block::AnyList(_)])));
+        assert_eq!(1, second.len());
+        assert_eq!(HorizontalAlignment::Right, second[0].halign());
+
+        assert!(csv.head().is_empty());
+        assert_eq!(
+            Some(inlines("quoted, \"value\"")),
+            csv.body()[0][1].inlines().map(without_locations)
+        );
+        assert_eq!(
+            Some(inlines("total")),
+            csv.foot()[0][0].inlines().map(without_locations)
+        );
     }
 }
@@ -0,0 +1,908 @@
+use serde::{Deserialize, Serialize};
+use serde_with_macros::skip_serializing_none;
+
+use super::{Block, BlockForm, BlockMetadata, BlockParser, Context, PendingMetadata};
+use crate::asg::{Inline, Location, LocationBoundary, NodeType};
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Table {
+    name: String,
+    #[serde(rename = "type")]
+    node_type: NodeType,
+    form: Option<BlockForm>,
+    delimiter: Option<String>,
+    id: Option<String>,
+    title: Option<Vec<Inline>>,
+    reftext: Option<Vec<Inline>>,
+    metadata: Option<BlockMetadata>,
+    columns: Vec<TableColumn>,
+    head: Vec<Vec<TableCell>>,
+    body: Vec<Vec<TableCell>>,
+    foot: Vec<Vec<TableCell>>,
+    location: Option<Location>,
+
+    /// Raw lines of the data, parsed into rows when the table ends.
+    #[serde(skip)]
+    lines: Vec<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    /// The relative width of the column, or `None` for an automatic width.
+    width: Option<usize>,
+    halign: HorizontalAlignment,
+    valign: VerticalAlignment,
+    style: CellStyle,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableCell {
+    name: String,
+    #[serde(rename = "type")]
+    node_type: NodeType,
+    colspan: Option<usize>,
+    rowspan: Option<usize>,
+    halign: HorizontalAlignment,
+    valign: VerticalAlignment,
+    style: CellStyle,
+    /// The content of a cell, unless it is an AsciiDoc cell.
+    inlines: Option<Vec<Inline>>,
+    /// The content of an AsciiDoc cell, parsed as a nested document.
+    blocks: Option<Vec<Block>>,
+    location: Option<Location>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HorizontalAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+impl HorizontalAlignment {
+    fn from_mark(mark: char) -> Option<Self> {
+        match mark {
+            '<' => Some(Self::Left),
+            '^' => Some(Self::Center),
+            '>' => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum VerticalAlignment {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+impl VerticalAlignment {
+    fn from_mark(mark: char) -> Option<Self> {
+        match mark {
+            '<' => Some(Self::Top),
+            '^' => Some(Self::Middle),
+            '>' => Some(Self::Bottom),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CellStyle {
+    Asciidoc,
+    #[default]
+    Default,
+    Emphasis,
+    Header,
+    Literal,
+    Monospaced,
+    Strong,
+}
+impl CellStyle {
+    fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'a' => Some(Self::Asciidoc),
+            'd' => Some(Self::Default),
+            'e' => Some(Self::Emphasis),
+            'h' => Some(Self::Header),
+            'l' => Some(Self::Literal),
+            'm' => Some(Self::Monospaced),
+            's' => Some(Self::Strong),
+            _ => None,
+        }
+    }
+}
+
+/// The format of the data of a table, with its cell separator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DataFormat {
+    /// Prefix-separated values, where each cell starts with a separator.
+    Psv(char),
+    /// Comma-separated values.
+    Csv(char),
+    /// Delimiter-separated values.
+    Dsv(char),
+}
+
+/// The specifier in front of the separator of a cell, like `2+`, `.3+^` or `3*a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CellSpec {
+    duplicate: usize,
+    colspan: usize,
+    rowspan: usize,
+    halign: Option<HorizontalAlignment>,
+    valign: Option<VerticalAlignment>,
+    style: Option<CellStyle>,
+}
+impl Default for CellSpec {
+    fn default() -> Self {
+        Self {
+            duplicate: 1,
+            colspan: 1,
+            rowspan: 1,
+            halign: None,
+            valign: None,
+            style: None,
+        }
+    }
+}
+impl CellSpec {
+    fn parse(spec: &str) -> Option<Self> {
+        if spec.is_empty() {
+            return None;
+        }
+
+        let mut cell = Self::default();
+        let factor_end = spec
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(spec.len());
+        let (factor, mut rest) = spec.split_at(factor_end);
+        if !factor.is_empty() {
+            match rest.chars().next()? {
+                '+' => {
+                    let (colspan, rowspan) = factor.split_once('.').unwrap_or((factor, ""));
+                    cell.colspan = parse_factor(colspan)?;
+                    cell.rowspan = parse_factor(rowspan)?;
+                }
+                '*' => cell.duplicate = factor.parse().ok().filter(|n| *n > 0)?,
+                _ => return None,
+            }
+            rest = &rest[1..];
+        }
+
+        let (halign, valign, rest) = parse_alignments(rest)?;
+        cell.halign = halign;
+        cell.valign = valign;
+
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => {}
+            (Some(letter), None) => cell.style = Some(CellStyle::from_letter(letter)?),
+            _ => return None,
+        }
+
+        Some(cell)
+    }
+}
+
+/// Parses a span factor, which defaults to 1 when it is omitted.
+fn parse_factor(factor: &str) -> Option<usize> {
+    match factor {
+        "" => Some(1),
+        _ => factor.parse().ok().filter(|n| *n > 0),
+    }
+}
+
+/// Parses the `<`, `^` or `>` horizontal alignment and the `.`-prefixed
+/// vertical one at the start of `spec`, returning what follows them.
+fn parse_alignments(
+    spec: &str,
+) -> Option<(Option<HorizontalAlignment>, Option<VerticalAlignment>, &str)> {
+    let mut rest = spec;
+    let halign = rest.chars().next().and_then(HorizontalAlignment::from_mark);
+    if halign.is_some() {
+        rest = &rest[1..];
+    }
+
+    let mut valign = None;
+    if let Some(r) = rest.strip_prefix('.') {
+        valign = Some(r.chars().next().and_then(VerticalAlignment::from_mark)?);
+        rest = &r[1..];
+    }
+
+    Some((halign, valign, rest))
+}
+
+impl TableColumn {
+    /// Parses the `cols` attribute, like `3*`, `1,2a` or `<.^2m,~`.
+    fn parse_list(cols: &str) -> Option<Vec<Self>> {
+        let cols = cols.trim();
+        if let Ok(count) = cols.parse::<usize>() {
+            return Some(vec![Self::default(); count]);
+        }
+
+        let mut columns = Vec::new();
+        for spec in cols.split([',', ';']) {
+            let spec = spec.trim();
+            let (count, spec) = match spec.split_once('*') {
+                Some((count, spec)) => (count.trim().parse().ok()?, spec),
+                None => (1, spec),
+            };
+            columns.extend(std::iter::repeat_n(Self::parse(spec)?, count));
+        }
+
+        Some(columns)
+    }
+
+    fn parse(spec: &str) -> Option<Self> {
+        let (halign, valign, rest) = parse_alignments(spec)?;
+        let width_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '%' && c != '~')
+            .unwrap_or(rest.len());
+        let (width, rest) = rest.split_at(width_end);
+        let width = match width.trim_end_matches('%') {
+            "" => Some(1),
+            "~" => None,
+            width => Some(width.parse().ok()?),
+        };
+
+        let mut chars = rest.chars();
+        let style = match (chars.next(), chars.next()) {
+            (None, _) => CellStyle::Default,
+            (Some(letter), None) => CellStyle::from_letter(letter)?,
+            _ => return None,
+        };
+
+        Some(Self {
+            width,
+            halign: halign.unwrap_or_default(),
+            valign: valign.unwrap_or_default(),
+            style,
+        })
+    }
+
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    pub fn halign(&self) -> HorizontalAlignment {
+        self.halign
+    }
+
+    pub fn valign(&self) -> VerticalAlignment {
+        self.valign
+    }
+
+    /// Returns the style of the cells of the column.
+    pub fn style(&self) -> CellStyle {
+        self.style
+    }
+}
+impl Default for TableColumn {
+    fn default() -> Self {
+        Self {
+            width: Some(1),
+            halign: Default::default(),
+            valign: Default::default(),
+            style: Default::default(),
+        }
+    }
+}
+
+/// A cell read from the data, before it is placed in a row.
+#[derive(Debug, Clone)]
+struct RawCell {
+    spec: CellSpec,
+    text: String,
+    /// The line and the column where the text of the cell starts.
+    start: (usize, usize),
+}
+impl RawCell {
+    /// Returns the text without the spaces around it, and the line and the
+    /// column where it starts.
+    fn trimmed(&self) -> (&str, (usize, usize)) {
+        let (mut line, mut column) = self.start;
+        for c in self.text.chars().take_while(|c| c.is_whitespace()) {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        (self.text.trim(), (line, column))
+    }
+}
+
+/// Places the cells of a table in rows, according to the number of columns
+/// and to the spans of the cells.
+struct RowBuilder {
+    /// The number of rows still covered by a cell, for each column.
+    covered: Vec<usize>,
+    column: usize,
+    row: Vec<(usize, RawCell)>,
+    rows: Vec<Vec<(usize, RawCell)>>,
+}
+impl RowBuilder {
+    fn new(column_count: usize) -> Self {
+        Self {
+            covered: vec![0; column_count],
+            column: 0,
+            row: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    fn skip_covered(&mut self) {
+        while self.column < self.covered.len() && self.covered[self.column] > 0 {
+            self.column += 1;
+        }
+    }
+
+    /// Places `cell`, which is followed by a new row when it fills the last column.
+    fn push(&mut self, cell: RawCell) {
+        self.skip_covered();
+        let end = (self.column + cell.spec.colspan).min(self.covered.len());
+        for covered in &mut self.covered[self.column..end] {
+            *covered = cell.spec.rowspan;
+        }
+        self.row.push((self.column, cell));
+        self.column = end;
+        self.skip_covered();
+
+        if self.column >= self.covered.len() {
+            for covered in &mut self.covered {
+                *covered = covered.saturating_sub(1);
+            }
+            self.column = 0;
+            self.rows.push(std::mem::take(&mut self.row));
+        }
+    }
+
+    /// Returns the complete rows, and whether cells of an incomplete row were dropped.
+    fn finish(self) -> (Vec<Vec<(usize, RawCell)>>, bool) {
+        (self.rows, !self.row.is_empty())
+    }
+}
+
+impl Table {
+    fn new(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self {
+            name: "table".to_owned(),
+            node_type: NodeType::Block,
+            form: Some(BlockForm::Delimited),
+            delimiter: Some(delimiter.to_owned()),
+            id: None,
+            title: None,
+            reftext: None,
+            metadata: None,
+            columns: Vec::with_capacity(0),
+            head: Vec::with_capacity(0),
+            body: Vec::with_capacity(0),
+            foot: Vec::with_capacity(0),
+            location: Some(context.line_location(line)),
+            lines: Vec::with_capacity(0),
+        }
+    }
+
+    pub(crate) fn set_metadata(&mut self, pending: PendingMetadata, context: &mut Context) {
+        (self.id, self.title, self.reftext, self.metadata) = pending.into_fields(context);
+    }
+
+    pub(crate) fn push(&mut self, line: &str, context: &Context) {
+        self.lines.push(line.to_owned());
+        self.extend_location(line, context);
+    }
+
+    /// Extends the location of the table up to its closing delimiter `line`.
+    pub(crate) fn close(&mut self, line: &str, context: &Context) {
+        self.extend_location(line, context);
+    }
+
+    fn extend_location(&mut self, line: &str, context: &Context) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let end = context.line_location(line).end().clone();
+        if let Some(location) = self.location.as_mut() {
+            location.set_end(end);
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    pub(crate) fn delimiter(&self) -> Option<String> {
+        self.delimiter.to_owned()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&[Inline]> {
+        self.title.as_deref()
+    }
+
+    pub fn title_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        self.title.as_mut()
+    }
+
+    pub fn reftext(&self) -> Option<&[Inline]> {
+        self.reftext.as_deref()
+    }
+
+    pub fn metadata(&self) -> Option<&BlockMetadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn columns(&self) -> &[TableColumn] {
+        &self.columns
+    }
+
+    pub fn head(&self) -> &[Vec<TableCell>] {
+        &self.head
+    }
+
+    pub fn body(&self) -> &[Vec<TableCell>] {
+        &self.body
+    }
+
+    pub fn foot(&self) -> &[Vec<TableCell>] {
+        &self.foot
+    }
+
+    /// Returns the rows of the head, the body and the foot in order.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut Vec<TableCell>> {
+        self.head
+            .iter_mut()
+            .chain(self.body.iter_mut())
+            .chain(self.foot.iter_mut())
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.metadata.as_ref().and_then(|m| m.attribute(name))
+    }
+
+    fn has_option(&self, name: &str) -> bool {
+        self.metadata.as_ref().is_some_and(|m| m.has_option(name))
+    }
+
+    /// Returns the format of the data, given by the `format` and `separator`
+    /// attributes or else by the delimiter.
+    pub(crate) fn data_format(&self) -> DataFormat {
+        let separator = self.attribute("separator").and_then(|s| s.chars().next());
+        let delimiter = self.delimiter.as_deref().and_then(|d| d.chars().next());
+
+        match (self.attribute("format"), delimiter) {
+            (Some("csv"), _) | (None, Some(',')) => DataFormat::Csv(separator.unwrap_or(',')),
+            (Some("dsv"), _) | (None, Some(':')) => DataFormat::Dsv(separator.unwrap_or(':')),
+            (_, Some('!')) => DataFormat::Psv(separator.unwrap_or('!')),
+            _ => DataFormat::Psv(separator.unwrap_or('|')),
+        }
+    }
+
+    /// Parses the data into the columns and the rows of the table.
+    pub(crate) fn end(&mut self, context: &mut Context) {
+        let first_line = self.location.as_ref().map_or(1, |l| l.start().line()) + 1;
+        let cells = match self.data_format() {
+            DataFormat::Psv(separator) => parse_psv(&self.lines, first_line, separator),
+            DataFormat::Csv(separator) => parse_csv(&self.lines, first_line, separator),
+            DataFormat::Dsv(separator) => parse_dsv(&self.lines, first_line, separator),
+        };
+
+        // The first line of the data gives the number of columns when `cols` does not.
+        let data_start = self.lines.iter().position(|l| !l.trim().is_empty());
+        self.columns = self
+            .attribute("cols")
+            .and_then(TableColumn::parse_list)
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| {
+                let first_row = data_start.map(|i| first_line + i);
+                let count = cells
+                    .iter()
+                    .filter(|c| Some(c.start.0) == first_row)
+                    .map(|c| c.spec.duplicate * c.spec.colspan)
+                    .sum::<usize>();
+                vec![TableColumn::default(); count.max(1)]
+            });
+
+        let mut builder = RowBuilder::new(self.columns.len());
+        for cell in cells {
+            for _ in 0..cell.spec.duplicate {
+                builder.push(cell.clone());
+            }
+        }
+        let (rows, is_incomplete) = builder.finish();
+        if is_incomplete {
+            context.warn("dropping cells from incomplete row detected end of table".to_owned());
+        }
+
+        // A first line followed by a blank line is an implicit header row.
+        let is_implicit_header =
+            data_start == Some(0) && self.lines.get(1).is_some_and(|l| l.trim().is_empty());
+        let has_header =
+            !self.has_option("noheader") && (self.has_option("header") || is_implicit_header);
+        let has_footer = self.has_option("footer");
+
+        let row_count = rows.len();
+        for (index, row) in rows.into_iter().enumerate() {
+            let is_head = has_header && index == 0;
+            let cells = row
+                .into_iter()
+                .map(|(column, cell)| self.new_cell(column, cell, is_head, context))
+                .collect();
+            if is_head {
+                self.head.push(cells);
+            } else if has_footer && index + 1 == row_count {
+                self.foot.push(cells);
+            } else {
+                self.body.push(cells);
+            }
+        }
+    }
+
+    fn new_cell(
+        &self,
+        column: usize,
+        cell: RawCell,
+        is_head: bool,
+        context: &mut Context,
+    ) -> TableCell {
+        let spec = self.columns.get(column).cloned().unwrap_or_default();
+        let style = cell.spec.style.unwrap_or(spec.style);
+        let (text, (line, column)) = cell.trimmed();
+        let source = context.source.clone();
+        let start = LocationBoundary::new(line, column, source.clone());
+        let end = match text.rsplit_once('\n') {
+            Some((before, last)) => LocationBoundary::new(
+                line + before.matches('\n').count() + 1,
+                last.chars().count().max(1),
+                source,
+            ),
+            None => LocationBoundary::new(line, column + text.chars().count().max(1) - 1, source),
+        };
+        let location = Location::new(start.clone(), end);
+
+        let (inlines, blocks) = match style {
+            // The cells of the header row are never parsed as nested documents.
+            CellStyle::Asciidoc if !is_head => (None, Some(parse_asciidoc(text, line, context))),
+            CellStyle::Literal => (
+                Some(vec![Inline::new_verbatim_text(text, location.clone())]),
+                None,
+            ),
+            _ => (
+                Some(Inline::new(text, &start, &mut context.attributes)),
+                None,
+            ),
+        };
+
+        TableCell {
+            name: "tableCell".to_owned(),
+            node_type: NodeType::Block,
+            colspan: (cell.spec.colspan > 1).then_some(cell.spec.colspan),
+            rowspan: (cell.spec.rowspan > 1).then_some(cell.spec.rowspan),
+            halign: cell.spec.halign.unwrap_or(spec.halign),
+            valign: cell.spec.valign.unwrap_or(spec.valign),
+            style,
+            inlines,
+            blocks,
+            location: Some(location),
+        }
+    }
+}
+
+impl TableCell {
+    pub fn colspan(&self) -> usize {
+        self.colspan.unwrap_or(1)
+    }
+
+    pub fn rowspan(&self) -> usize {
+        self.rowspan.unwrap_or(1)
+    }
+
+    pub fn halign(&self) -> HorizontalAlignment {
+        self.halign
+    }
+
+    pub fn valign(&self) -> VerticalAlignment {
+        self.valign
+    }
+
+    pub fn style(&self) -> CellStyle {
+        self.style
+    }
+
+    pub fn inlines(&self) -> Option<&[Inline]> {
+        self.inlines.as_deref()
+    }
+
+    pub fn inlines_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        self.inlines.as_mut()
+    }
+
+    pub fn blocks(&self) -> Option<&[Block]> {
+        self.blocks.as_deref()
+    }
+
+    pub fn blocks_mut(&mut self) -> Option<&mut Vec<Block>> {
+        self.blocks.as_mut()
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+}
+
+/// Parses the text of an AsciiDoc cell, which starts at `line`, as the
+/// blocks of a nested document.
+fn parse_asciidoc(text: &str, line: usize, context: &mut Context) -> Vec<Block> {
+    let line_number = context.line_number;
+    let mut parser = BlockParser::default();
+    for (index, text_line) in text.lines().enumerate() {
+        context.line_number = line + index;
+        if let Err(error) = parser.push(text_line, context) {
+            context.warn(error.to_string());
+        }
+    }
+    let blocks = parser.end(context);
+    context.line_number = line_number;
+
+    blocks
+}
+
+/// Reads the cells of prefix-separated values, where each cell starts with
+/// `separator` and an optional cell specifier before it.
+fn parse_psv(lines: &[String], first_line: usize, separator: char) -> Vec<RawCell> {
+    let mut cells = Vec::new();
+    let mut current: Option<RawCell> = None;
+    let mut text = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let mut previous = None;
+        for (column, c) in line.chars().enumerate() {
+            if c != separator {
+                text.push(c);
+                previous = Some(c);
+                continue;
+            }
+
+            if previous == Some('\\') {
+                text.pop();
+                text.push(c);
+                previous = Some(c);
+                continue;
+            }
+
+            let (content, spec) = split_cell_spec(&text);
+            if let Some(mut cell) = current.take() {
+                cell.text = content.to_owned();
+                cells.push(cell);
+            }
+            current = Some(RawCell {
+                spec,
+                text: String::new(),
+                start: (first_line + index, column + 2),
+            });
+            text.clear();
+            previous = Some(c);
+        }
+        text.push('\n');
+    }
+
+    if let Some(mut cell) = current {
+        cell.text = text;
+        cells.push(cell);
+    }
+
+    cells
+}
+
+/// Splits the specifier of the next cell off the end of `text`, which
+/// precedes a separator.
+fn split_cell_spec(text: &str) -> (&str, CellSpec) {
+    let start = text.rfind(char::is_whitespace).map_or(0, |i| {
+        i + text[i..].chars().next().map_or(1, |c| c.len_utf8())
+    });
+
+    match CellSpec::parse(&text[start..]) {
+        Some(spec) => (&text[..start], spec),
+        None => (text, CellSpec::default()),
+    }
+}
+
+/// Reads the cells of comma-separated values, whose quoted values may
+/// contain separators, escaped quotes and line breaks.
+fn parse_csv(lines: &[String], first_line: usize, separator: char) -> Vec<RawCell> {
+    let mut cells = Vec::new();
+    let mut text = String::new();
+    let mut start = None;
+    let mut is_quoted = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        if !is_quoted && line.trim().is_empty() {
+            continue;
+        }
+
+        let mut chars = line.chars().enumerate().peekable();
+        while let Some((column, c)) = chars.next() {
+            let position = (first_line + index, column + 1);
+            if is_quoted {
+                if c != '"' {
+                    text.push(c);
+                } else if chars.next_if(|(_, c)| *c == '"').is_some() {
+                    text.push('"');
+                } else {
+                    is_quoted = false;
+                }
+            } else if c == separator {
+                cells.push(RawCell {
+                    spec: Default::default(),
+                    text: std::mem::take(&mut text),
+                    start: start.take().unwrap_or(position),
+                });
+            } else if c == '"' && text.trim().is_empty() {
+                text.clear();
+                is_quoted = true;
+                start = Some((position.0, position.1 + 1));
+            } else {
+                start.get_or_insert(position);
+                text.push(c);
+            }
+        }
+
+        if is_quoted {
+            text.push('\n');
+        } else {
+            cells.push(RawCell {
+                spec: Default::default(),
+                text: std::mem::take(&mut text),
+                start: start.take().unwrap_or((first_line + index, line.len() + 1)),
+            });
+        }
+    }
+
+    cells
+}
+
+/// Reads the cells of delimiter-separated values, where `\` escapes a separator.
+fn parse_dsv(lines: &[String], first_line: usize, separator: char) -> Vec<RawCell> {
+    let mut cells = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut start = 1;
+        let mut previous = None;
+        for (column, c) in line.chars().enumerate() {
+            if c == separator && previous != Some('\\') {
+                cells.push(RawCell {
+                    spec: Default::default(),
+                    text: std::mem::take(&mut text),
+                    start: (first_line + index, start),
+                });
+                start = column + 2;
+            } else if c == separator {
+                text.pop();
+                text.push(c);
+            } else {
+                text.push(c);
+            }
+            previous = Some(c);
+        }
+        cells.push(RawCell {
+            spec: Default::default(),
+            text,
+            start: (first_line + index, start),
+        });
+    }
+
+    cells
+}
+
+impl Block {
+    pub(crate) fn new_table(delimiter: &str, line: &str, context: &Context) -> Self {
+        Self::Table(Table::new(delimiter, line, context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_specs() {
+        assert_eq!(None, CellSpec::parse(""));
+        assert_eq!(None, CellSpec::parse("word"));
+        assert_eq!(
+            Some(CellSpec {
+                colspan: 2,
+                rowspan: 3,
+                ..Default::default()
+            }),
+            CellSpec::parse("2.3+")
+        );
+        assert_eq!(
+            Some(CellSpec {
+                rowspan: 2,
+                halign: Some(HorizontalAlignment::Center),
+                valign: Some(VerticalAlignment::Bottom),
+                style: Some(CellStyle::Asciidoc),
+                ..Default::default()
+            }),
+            CellSpec::parse(".2+^.>a")
+        );
+        assert_eq!(
+            Some(CellSpec {
+                duplicate: 3,
+                ..Default::default()
+            }),
+            CellSpec::parse("3*")
+        );
+        assert_eq!(("text ", CellSpec::default()), split_cell_spec("text "));
+        assert_eq!(
+            (
+                "one ",
+                CellSpec {
+                    colspan: 2,
+                    ..Default::default()
+                }
+            ),
+            split_cell_spec("one 2+")
+        );
+    }
+
+    #[test]
+    fn column_specs() {
+        assert_eq!(
+            Some(vec![TableColumn::default(); 3]),
+            TableColumn::parse_list("3")
+        );
+        assert_eq!(
+            Some(vec![
+                TableColumn::default(),
+                TableColumn::default(),
+                TableColumn {
+                    width: Some(2),
+                    halign: HorizontalAlignment::Right,
+                    valign: VerticalAlignment::Middle,
+                    style: CellStyle::Monospaced,
+                },
+                TableColumn {
+                    width: None,
+                    ..Default::default()
+                },
+            ]),
+            TableColumn::parse_list("2*,>.^2m;~")
+        );
+        assert_eq!(None, TableColumn::parse_list("1,wide"));
+    }
+
+    #[test]
+    fn csv_values() {
+        let lines: Vec<String> = ["a, \"b, \"\"c\"\"\"", "\"multi", "line\",d"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let cells = parse_csv(&lines, 1, ',');
+
+        assert_eq!(
+            vec!["a", "b, \"c\"", "multi\nline", "d"],
+            cells.iter().map(|c| c.trimmed().0).collect::<Vec<_>>()
+        );
+        assert_eq!((2, 2), cells[2].start);
+    }
+}
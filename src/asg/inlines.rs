@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
-use crate::asg::{Location, NodeType};
+use std::collections::HashMap;
+
+use crate::asg::{AttributeSegment, Attributes, Location, LocationBoundary, NodeType};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "name", rename_all = "camelCase")]
@@ -12,35 +14,66 @@ pub enum Inline {
     Raw(InlineLiteral),
 }
 impl Inline {
-    pub(crate) fn new(line: &str) -> Vec<Self> {
-        let mut inlines = Vec::with_capacity(0);
-        inlines.push(Inline::new_text(line));
+    /// Parses `text`, whose first character is at `start` and whose following
+    /// lines begin at the first column.
+    pub(crate) fn new(
+        text: &str,
+        start: &LocationBoundary,
+        attributes: &mut Attributes,
+    ) -> Vec<Self> {
+        let parser = InlineParser::new(text, start, attributes);
 
-        inlines
+        parser.parse(0, parser.chars.len())
     }
 
-    pub(crate) fn append(mut inlines: Vec<Self>, line: &str) -> Vec<Self> {
-        if let Some(Self::Text(last)) = inlines.last_mut() {
-            last.value.push_str(line);
-            inlines
-        } else {
-            inlines.push(Self::new_text(line));
-            inlines
+    /// Keeps `text` as a single text node, without parsing any markup.
+    pub(crate) fn new_verbatim_text(text: &str, location: Location) -> Self {
+        Self::new_text(text).with_location(location)
+    }
+
+    /// Keeps `text` as a single raw node, to be passed through to the output.
+    pub(crate) fn new_verbatim_raw(text: &str, location: Location) -> Self {
+        Self::new_raw(text).with_location(location)
+    }
+
+    /// Returns the indexes of the characters of `text` which open a span
+    /// when it is parsed, at any depth, leaving its attribute references as is.
+    pub(crate) fn span_openings(text: &str) -> Vec<usize> {
+        let parser = InlineParser::new_unsubstituted(text);
+        let mut openings = Vec::with_capacity(0);
+        parser.find_openings(0, parser.chars.len(), &mut openings);
+
+        openings
+    }
+
+    fn new_span(variant: SpanVariant, form: SpanForm, inlines: Vec<Self>) -> Self {
+        Self::Span(InlineParent::new(variant, form, inlines))
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::Span(InlineParent { location, .. }) => location.as_ref(),
+            Self::Text(InlineLiteral { location, .. })
+            | Self::Charref(InlineLiteral { location, .. })
+            | Self::Raw(InlineLiteral { location, .. }) => location.as_ref(),
         }
     }
 
-    fn new_span() -> Self {
-        Self::Span(InlineParent::new())
+    fn with_location(mut self, new_location: Location) -> Self {
+        match &mut self {
+            Self::Span(InlineParent { location, .. }) => *location = Some(new_location),
+            Self::Text(InlineLiteral { location, .. })
+            | Self::Charref(InlineLiteral { location, .. })
+            | Self::Raw(InlineLiteral { location, .. }) => *location = Some(new_location),
+        }
+
+        self
     }
 
     fn new_text(value: &str) -> Self {
         Self::Text(InlineLiteral::new(value.to_owned()))
     }
 
-    fn new_charref(value: &str) -> Self {
-        Self::Charref(InlineLiteral::new(value.to_owned()))
-    }
-
     fn new_raw(value: &str) -> Self {
         Self::Raw(InlineLiteral::new(value.to_owned()))
     }
@@ -51,17 +84,57 @@ impl Inline {
 pub struct InlineParent {
     #[serde(rename = "type")]
     node_type: NodeType,
+    variant: SpanVariant,
+    form: SpanForm,
     inlines: Vec<Inline>,
     location: Option<Location>,
 }
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SpanVariant {
+    Strong,
+    Emphasis,
+    Code,
+    Mark,
+    Superscript,
+    Subscript,
+}
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SpanForm {
+    Constrained,
+    Unconstrained,
+}
 impl InlineParent {
-    fn new() -> Self {
+    fn new(variant: SpanVariant, form: SpanForm, inlines: Vec<Inline>) -> Self {
         Self {
             node_type: NodeType::Inline,
-            inlines: Vec::with_capacity(0),
+            variant,
+            form,
+            inlines,
             location: None,
         }
     }
+
+    pub fn variant(&self) -> SpanVariant {
+        self.variant
+    }
+
+    pub fn form(&self) -> SpanForm {
+        self.form
+    }
+
+    pub fn inlines(&self) -> &[Inline] {
+        &self.inlines
+    }
+
+    pub fn inlines_mut(&mut self) -> &mut Vec<Inline> {
+        &mut self.inlines
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
 }
 
 #[skip_serializing_none]
@@ -80,4 +153,607 @@ impl InlineLiteral {
             location: None,
         }
     }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_owned();
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+}
+impl SpanVariant {
+    fn from_mark(mark: char) -> Option<Self> {
+        match mark {
+            '*' => Some(Self::Strong),
+            '_' => Some(Self::Emphasis),
+            '`' => Some(Self::Code),
+            '#' => Some(Self::Mark),
+            '^' => Some(Self::Superscript),
+            '~' => Some(Self::Subscript),
+            _ => None,
+        }
+    }
+
+    /// Returns the character marking the span, doubled in its unconstrained form.
+    pub(crate) fn mark(&self) -> char {
+        match self {
+            Self::Strong => '*',
+            Self::Emphasis => '_',
+            Self::Code => '`',
+            Self::Mark => '#',
+            Self::Superscript => '^',
+            Self::Subscript => '~',
+        }
+    }
+
+    pub(crate) fn has_unconstrained_pair(&self) -> bool {
+        !matches!(self, Self::Superscript | Self::Subscript)
+    }
+
+    /// Returns whether `c` may follow the closing mark of the constrained form.
+    fn is_closing_boundary(&self, c: char) -> bool {
+        match self {
+            Self::Code => !is_word_char(c) && !matches!(c, '"' | '\'' | '`'),
+            _ => !is_word_char(c),
+        }
+    }
+}
+
+struct SpanMatch {
+    variant: SpanVariant,
+    form: SpanForm,
+    content: (usize, usize),
+    mark_len: usize,
+    next: usize,
+}
+
+/// The indexes, in order, of the marks of a variant which may close a span.
+#[derive(Default)]
+struct ClosingMarks {
+    // First marks of the doubled marks closing the unconstrained form.
+    unconstrained: Vec<usize>,
+    // Marks closing the constrained form, when followed by a boundary in the text.
+    constrained: Vec<usize>,
+}
+impl ClosingMarks {
+    /// Returns the first index of `indexes` in `start..end`.
+    fn first_in(indexes: &[usize], start: usize, end: usize) -> Option<usize> {
+        let j = *indexes.get(indexes.partition_point(|&j| j < start))?;
+
+        (j < end).then_some(j)
+    }
+}
+
+struct InlineParser {
+    chars: Vec<char>,
+    // Characters that come from attribute values are never treated as formatting marks.
+    literals: Vec<bool>,
+    // Line and column of each character in the source.
+    positions: Vec<(usize, usize)>,
+    source: Option<String>,
+    // Indexes of the marks which may close a span, by mark, so that a span is
+    // matched without scanning the rest of the text for each opening mark.
+    closings: HashMap<char, ClosingMarks>,
+}
+impl InlineParser {
+    fn new(text: &str, start: &LocationBoundary, attributes: &mut Attributes) -> Self {
+        let mut chars = Vec::with_capacity(text.len());
+        let mut literals = Vec::with_capacity(text.len());
+        let mut positions = Vec::with_capacity(text.len());
+        let mut previous_end = None;
+
+        for (index, line) in text.split('\n').enumerate() {
+            let line_number = start.line() + index;
+            let first_column = if index == 0 { start.column() } else { 1 };
+            let Some(segments) = attributes.substitute_line(line) else {
+                continue;
+            };
+
+            if let Some(end) = previous_end {
+                chars.push('\n');
+                literals.push(false);
+                positions.push(end);
+            }
+
+            let line: Vec<char> = line.chars().collect();
+            for segment in segments {
+                match segment {
+                    AttributeSegment::Text(start, end) => {
+                        chars.extend(&line[start..end]);
+                        literals.extend(vec![false; end - start]);
+                        positions.extend((start..end).map(|i| (line_number, first_column + i)));
+                    }
+                    AttributeSegment::Value(start, end, value) => {
+                        for (i, c) in value.chars().enumerate() {
+                            let column = if i == 0 { start } else { end - 1 };
+                            chars.push(c);
+                            literals.push(true);
+                            positions.push((line_number, first_column + column));
+                        }
+                    }
+                }
+            }
+            previous_end = Some((line_number, (first_column + line.len()).max(2) - 1));
+        }
+
+        Self {
+            closings: Self::closings(&chars, &literals),
+            chars,
+            literals,
+            positions,
+            source: start.source().map(str::to_owned),
+        }
+    }
+
+    /// Reads `text` as is, without resolving its attribute references.
+    fn new_unsubstituted(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let literals = vec![false; chars.len()];
+
+        Self {
+            closings: Self::closings(&chars, &literals),
+            positions: vec![(1, 1); chars.len()],
+            chars,
+            literals,
+            source: None,
+        }
+    }
+
+    fn closings(chars: &[char], literals: &[bool]) -> HashMap<char, ClosingMarks> {
+        let mut closings: HashMap<char, ClosingMarks> = HashMap::new();
+        let is_mark = |j: usize, mark: char| chars.get(j) == Some(&mark) && !literals[j];
+
+        for (j, &mark) in chars.iter().enumerate() {
+            let Some(variant) = SpanVariant::from_mark(mark).filter(|_| !literals[j]) else {
+                continue;
+            };
+            let marks = closings.entry(mark).or_default();
+            if is_mark(j + 1, mark) {
+                marks.unconstrained.push(j);
+            }
+            if j > 0
+                && !chars[j - 1].is_whitespace()
+                && chars
+                    .get(j + 1)
+                    .is_none_or(|&c| variant.is_closing_boundary(c))
+            {
+                marks.constrained.push(j);
+            }
+        }
+
+        closings
+    }
+
+    fn location(&self, first: usize, last: usize) -> Location {
+        let boundary = |i: usize| {
+            let (line, column) = self.positions[i];
+            LocationBoundary::new(line, column, self.source.clone())
+        };
+
+        Location::new(boundary(first), boundary(last))
+    }
+
+    fn parse(&self, start: usize, end: usize) -> Vec<Inline> {
+        let mut inlines = Vec::with_capacity(0);
+        let mut text = String::new();
+        let mut text_start = start;
+        let mut i = start;
+
+        while i < end {
+            let c = self.chars[i];
+
+            if c == '\\' && !self.literals[i] && i + 1 < end {
+                if let Some(span) = self.match_span(i + 1, start, end) {
+                    if text.is_empty() {
+                        text_start = i;
+                    }
+                    // The backslash is dropped and the opening mark is kept as plain text.
+                    text.extend(&self.chars[i + 1..i + 1 + span.mark_len]);
+                    i += 1 + span.mark_len;
+
+                    continue;
+                }
+            }
+
+            if let Some(span) = self.match_span(i, start, end) {
+                if !text.is_empty() {
+                    let location = self.location(text_start, i - 1);
+                    inlines.push(Inline::new_text(&text).with_location(location));
+                    text.clear();
+                }
+
+                let (content_start, content_end) = span.content;
+                let children = self.parse(content_start, content_end);
+                let location = self.location(i, span.next - 1);
+                inlines.push(
+                    Inline::new_span(span.variant, span.form, children).with_location(location),
+                );
+                i = span.next;
+
+                continue;
+            }
+
+            if text.is_empty() {
+                text_start = i;
+            }
+            text.push(c);
+            i += 1;
+        }
+
+        if !text.is_empty() {
+            let location = self.location(text_start, end - 1);
+            inlines.push(Inline::new_text(&text).with_location(location));
+        }
+
+        inlines
+    }
+
+    /// Collects the indexes where [`parse`](Self::parse) opens a span.
+    fn find_openings(&self, start: usize, end: usize, openings: &mut Vec<usize>) {
+        let mut i = start;
+
+        while i < end {
+            if self.chars[i] == '\\' && !self.literals[i] && i + 1 < end {
+                if let Some(span) = self.match_span(i + 1, start, end) {
+                    i += 1 + span.mark_len;
+
+                    continue;
+                }
+            }
+
+            if let Some(span) = self.match_span(i, start, end) {
+                openings.push(i);
+                let (content_start, content_end) = span.content;
+                self.find_openings(content_start, content_end, openings);
+                i = span.next;
+
+                continue;
+            }
+
+            i += 1;
+        }
+    }
+
+    fn match_span(&self, i: usize, start: usize, end: usize) -> Option<SpanMatch> {
+        if self.literals[i] {
+            return None;
+        }
+
+        let mark = self.chars[i];
+        let variant = SpanVariant::from_mark(mark)?;
+
+        if !variant.has_unconstrained_pair() {
+            return self.match_script(variant, i, end);
+        }
+
+        self.match_unconstrained(variant, i, end)
+            .or_else(|| self.match_constrained(variant, i, start, end))
+    }
+
+    fn match_unconstrained(&self, variant: SpanVariant, i: usize, end: usize) -> Option<SpanMatch> {
+        let mark = self.chars[i];
+        if i + 1 >= end || !self.is_mark(i + 1, mark) {
+            return None;
+        }
+
+        let content_start = i + 2;
+        let content_end = ClosingMarks::first_in(
+            &self.closings.get(&mark)?.unconstrained,
+            content_start + 1,
+            end.saturating_sub(1),
+        )?;
+
+        Some(SpanMatch {
+            variant,
+            form: SpanForm::Unconstrained,
+            content: (content_start, content_end),
+            mark_len: 2,
+            next: content_end + 2,
+        })
+    }
+
+    fn match_constrained(
+        &self,
+        variant: SpanVariant,
+        i: usize,
+        start: usize,
+        end: usize,
+    ) -> Option<SpanMatch> {
+        let mark = self.chars[i];
+        let is_boundary = |c: char| match variant {
+            SpanVariant::Code => {
+                !is_word_char(c) && !matches!(c, ';' | ':' | '}' | '"' | '\'' | '`')
+            }
+            _ => !is_word_char(c) && !matches!(c, ';' | ':' | '}'),
+        };
+
+        if i > start && !is_boundary(self.chars[i - 1]) {
+            return None;
+        }
+
+        let content_start = i + 1;
+        if content_start >= end || self.chars[content_start].is_whitespace() {
+            return None;
+        }
+
+        // The end of the text is a boundary too, even before a mark of the enclosing span.
+        let last = end - 1;
+        let content_end = ClosingMarks::first_in(
+            &self.closings.get(&mark)?.constrained,
+            content_start + 1,
+            last,
+        )
+        .or_else(|| {
+            (last > content_start
+                && self.is_mark(last, mark)
+                && !self.chars[last - 1].is_whitespace())
+            .then_some(last)
+        })?;
+
+        Some(SpanMatch {
+            variant,
+            form: SpanForm::Constrained,
+            content: (content_start, content_end),
+            mark_len: 1,
+            next: content_end + 1,
+        })
+    }
+
+    fn match_script(&self, variant: SpanVariant, i: usize, end: usize) -> Option<SpanMatch> {
+        let mark = self.chars[i];
+        let content_start = i + 1;
+
+        let content_end = (content_start..end)
+            .take_while(|&j| !self.chars[j].is_whitespace())
+            .find(|&j| self.is_mark(j, mark))?;
+        if content_end == content_start {
+            return None;
+        }
+
+        Some(SpanMatch {
+            variant,
+            form: SpanForm::Unconstrained,
+            content: (content_start, content_end),
+            mark_len: 1,
+            next: content_end + 1,
+        })
+    }
+
+    fn is_mark(&self, i: usize, mark: char) -> bool {
+        self.chars[i] == mark && !self.literals[i]
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Parses `text` without the locations, to compare inlines by their structure.
+    pub(crate) fn inlines(text: &str) -> Vec<Inline> {
+        without_locations(&Inline::new(
+            text,
+            &LocationBoundary::new(1, 1, None),
+            &mut Attributes::new(),
+        ))
+    }
+
+    /// Returns `inlines` and their children without their locations.
+    pub(crate) fn without_locations(inlines: &[Inline]) -> Vec<Inline> {
+        inlines
+            .iter()
+            .cloned()
+            .map(|mut inline| {
+                match &mut inline {
+                    Inline::Span(parent) => {
+                        parent.location = None;
+                        parent.inlines = without_locations(&parent.inlines);
+                    }
+                    Inline::Text(literal) | Inline::Charref(literal) | Inline::Raw(literal) => {
+                        literal.location = None;
+                    }
+                }
+                inline
+            })
+            .collect()
+    }
+
+    fn strong(form: SpanForm, inlines: Vec<Inline>) -> Inline {
+        Inline::new_span(SpanVariant::Strong, form, inlines)
+    }
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(vec![Inline::new_text("plain text")], inlines("plain text"));
+    }
+
+    #[test]
+    fn constrained_span() {
+        assert_eq!(
+            vec![
+                Inline::new_text("a "),
+                strong(SpanForm::Constrained, vec![Inline::new_text("strong")]),
+                Inline::new_text(" word"),
+            ],
+            inlines("a *strong* word")
+        );
+        assert_eq!(
+            vec![Inline::new_text("not*strong* word")],
+            inlines("not*strong* word")
+        );
+        assert_eq!(
+            vec![Inline::new_text("not * strong * word")],
+            inlines("not * strong * word")
+        );
+    }
+
+    #[test]
+    fn unconstrained_span() {
+        assert_eq!(
+            vec![
+                Inline::new_text("C"),
+                strong(SpanForm::Unconstrained, vec![Inline::new_text("ooo")]),
+                Inline::new_text("kie"),
+            ],
+            inlines("C**ooo**kie")
+        );
+        assert_eq!(
+            vec![
+                Inline::new_span(
+                    SpanVariant::Code,
+                    SpanForm::Unconstrained,
+                    vec![Inline::new_text("x")]
+                ),
+                Inline::new_text("s"),
+            ],
+            inlines("``x``s")
+        );
+    }
+
+    #[test]
+    fn nested_span() {
+        assert_eq!(
+            vec![strong(
+                SpanForm::Constrained,
+                vec![Inline::new_span(
+                    SpanVariant::Emphasis,
+                    SpanForm::Constrained,
+                    vec![Inline::new_text("bold italic")]
+                )]
+            )],
+            inlines("*_bold italic_*")
+        );
+        assert_eq!(
+            vec![Inline::new_span(
+                SpanVariant::Mark,
+                SpanForm::Constrained,
+                vec![
+                    Inline::new_text("highlight "),
+                    Inline::new_span(
+                        SpanVariant::Code,
+                        SpanForm::Constrained,
+                        vec![Inline::new_text("code")]
+                    ),
+                ]
+            )],
+            inlines("#highlight `code`#")
+        );
+    }
+
+    #[test]
+    fn superscript_and_subscript_span() {
+        assert_eq!(
+            vec![
+                Inline::new_text("E=mc"),
+                Inline::new_span(
+                    SpanVariant::Superscript,
+                    SpanForm::Unconstrained,
+                    vec![Inline::new_text("2")]
+                ),
+                Inline::new_text(" H"),
+                Inline::new_span(
+                    SpanVariant::Subscript,
+                    SpanForm::Unconstrained,
+                    vec![Inline::new_text("2")]
+                ),
+                Inline::new_text("O ~not sub~"),
+            ],
+            inlines("E=mc^2^ H~2~O ~not sub~")
+        );
+    }
+
+    #[test]
+    fn escaped_span() {
+        assert_eq!(
+            vec![Inline::new_text("*not strong*")],
+            inlines("\\*not strong*")
+        );
+    }
+
+    #[test]
+    fn unclosed_marks() {
+        let text = "a *b _c `d ".repeat(2000);
+
+        assert_eq!(vec![Inline::new_text(&text)], inlines(&text));
+        assert_eq!(
+            vec![
+                strong(SpanForm::Constrained, vec![Inline::new_text("a_b")]),
+                Inline::new_text(" c_"),
+            ],
+            inlines("*a_b* c_")
+        );
+    }
+
+    #[test]
+    fn span_openings() {
+        assert_eq!(vec![2, 3, 13], Inline::span_openings("a *_b_* \\*c* ^d^"));
+    }
+
+    #[test]
+    fn multiline_span() {
+        assert_eq!(
+            vec![strong(
+                SpanForm::Constrained,
+                vec![Inline::new_text("first\nsecond")]
+            )],
+            inlines("*first\nsecond*")
+        );
+    }
+
+    #[test]
+    fn attribute_reference() {
+        let mut attributes = Attributes::new();
+        attributes.set("product", "*asciidoc2rs*");
+
+        assert_eq!(
+            vec![
+                Inline::new_text("Welcome to "),
+                Inline::new_span(
+                    SpanVariant::Emphasis,
+                    SpanForm::Constrained,
+                    vec![Inline::new_text("*asciidoc2rs*")]
+                ),
+            ],
+            without_locations(&Inline::new(
+                "Welcome to _{product}_",
+                &LocationBoundary::new(1, 1, None),
+                &mut attributes
+            ))
+        );
+    }
+
+    #[test]
+    fn span_location() {
+        let inlines = Inline::new(
+            "a *b*\nc",
+            &LocationBoundary::new(3, 5, None),
+            &mut Attributes::new(),
+        );
+        let location = |start: (usize, usize), end: (usize, usize)| {
+            Some(Location::new(
+                LocationBoundary::new(start.0, start.1, None),
+                LocationBoundary::new(end.0, end.1, None),
+            ))
+        };
+
+        assert_eq!(
+            vec![
+                location((3, 5), (3, 6)),
+                location((3, 7), (3, 9)),
+                location((3, 9), (4, 1))
+            ],
+            inlines
+                .iter()
+                .map(|i| i.location().cloned())
+                .collect::<Vec<_>>()
+        );
+    }
 }
@@ -0,0 +1,411 @@
+use super::{
+    AnyList, Block, DlistItem, Document, Inline, ListItem, Section, SectionBody, TableCell,
+};
+
+/// Whether a visitor walks into the children of the node it enters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visit {
+    /// Walks the children, then leaves the node.
+    #[default]
+    Continue,
+    /// Leaves the node without walking its children.
+    SkipChildren,
+}
+
+/// Hooks called while walking a document with [`walk_document`], in the
+/// order of the source.
+///
+/// Each `enter_*` hook is called before the children of the node, which it
+/// can skip, and each `leave_*` hook after them. The titles of a section or
+/// a block are walked before its content.
+pub trait Visitor {
+    fn enter_document(&mut self, _document: &Document) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_document(&mut self, _document: &Document) {}
+
+    fn enter_section(&mut self, _section: &Section) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_section(&mut self, _section: &Section) {}
+
+    fn enter_block(&mut self, _block: &Block) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_block(&mut self, _block: &Block) {}
+
+    fn enter_list_item(&mut self, _item: &ListItem) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_list_item(&mut self, _item: &ListItem) {}
+
+    fn enter_dlist_item(&mut self, _item: &DlistItem) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_dlist_item(&mut self, _item: &DlistItem) {}
+
+    fn enter_table_cell(&mut self, _cell: &TableCell) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_table_cell(&mut self, _cell: &TableCell) {}
+
+    fn enter_inline(&mut self, _inline: &Inline) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_inline(&mut self, _inline: &Inline) {}
+}
+
+/// Hooks called while walking a document with [`walk_document_mut`], which
+/// can change the nodes they are given.
+///
+/// The children of a node are walked after its `enter_*` hook has changed it.
+pub trait VisitorMut {
+    fn enter_document(&mut self, _document: &mut Document) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_document(&mut self, _document: &mut Document) {}
+
+    fn enter_section(&mut self, _section: &mut Section) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_section(&mut self, _section: &mut Section) {}
+
+    fn enter_block(&mut self, _block: &mut Block) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_block(&mut self, _block: &mut Block) {}
+
+    fn enter_list_item(&mut self, _item: &mut ListItem) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_list_item(&mut self, _item: &mut ListItem) {}
+
+    fn enter_dlist_item(&mut self, _item: &mut DlistItem) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_dlist_item(&mut self, _item: &mut DlistItem) {}
+
+    fn enter_table_cell(&mut self, _cell: &mut TableCell) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_table_cell(&mut self, _cell: &mut TableCell) {}
+
+    fn enter_inline(&mut self, _inline: &mut Inline) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_inline(&mut self, _inline: &mut Inline) {}
+}
+
+pub fn walk_document<V: Visitor + ?Sized>(visitor: &mut V, document: &Document) {
+    if visitor.enter_document(document) == Visit::Continue {
+        if let Some(header) = document.header() {
+            walk_inlines(visitor, header.title());
+        }
+        walk_section_bodies(visitor, document.blocks());
+    }
+    visitor.leave_document(document);
+}
+
+pub fn walk_section<V: Visitor + ?Sized>(visitor: &mut V, section: &Section) {
+    if visitor.enter_section(section) == Visit::Continue {
+        walk_inlines(visitor, section.title());
+        walk_section_bodies(visitor, section.blocks());
+    }
+    visitor.leave_section(section);
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    if visitor.enter_block(block) == Visit::Continue {
+        walk_inlines(visitor, block.title().unwrap_or_default());
+        match block {
+            Block::BlockParent(parent) => walk_blocks(visitor, parent.body().blocks()),
+            Block::BlockLeaf(leaf) => walk_inlines(visitor, leaf.body().inlines()),
+            Block::BlockMacro(_) | Block::BlockBreak(_) => {}
+            Block::AnyList(AnyList::List { items, .. }) => {
+                for item in items {
+                    walk_list_item(visitor, item);
+                }
+            }
+            Block::AnyList(AnyList::Dlist { items, .. }) => {
+                for item in items {
+                    walk_dlist_item(visitor, item);
+                }
+            }
+            Block::Table(table) => {
+                let rows = table.head().iter().chain(table.body()).chain(table.foot());
+                for cell in rows.flatten() {
+                    walk_table_cell(visitor, cell);
+                }
+            }
+        }
+    }
+    visitor.leave_block(block);
+}
+
+pub fn walk_list_item<V: Visitor + ?Sized>(visitor: &mut V, item: &ListItem) {
+    if visitor.enter_list_item(item) == Visit::Continue {
+        walk_inlines(visitor, item.principal());
+        walk_blocks(visitor, item.blocks());
+    }
+    visitor.leave_list_item(item);
+}
+
+pub fn walk_dlist_item<V: Visitor + ?Sized>(visitor: &mut V, item: &DlistItem) {
+    if visitor.enter_dlist_item(item) == Visit::Continue {
+        for term in item.terms() {
+            walk_inlines(visitor, term);
+        }
+        walk_inlines(visitor, item.principal());
+        walk_blocks(visitor, item.blocks());
+    }
+    visitor.leave_dlist_item(item);
+}
+
+pub fn walk_table_cell<V: Visitor + ?Sized>(visitor: &mut V, cell: &TableCell) {
+    if visitor.enter_table_cell(cell) == Visit::Continue {
+        walk_inlines(visitor, cell.inlines().unwrap_or_default());
+        walk_blocks(visitor, cell.blocks().unwrap_or_default());
+    }
+    visitor.leave_table_cell(cell);
+}
+
+pub fn walk_inline<V: Visitor + ?Sized>(visitor: &mut V, inline: &Inline) {
+    if visitor.enter_inline(inline) == Visit::Continue {
+        if let Inline::Span(span) = inline {
+            walk_inlines(visitor, span.inlines());
+        }
+    }
+    visitor.leave_inline(inline);
+}
+
+fn walk_section_bodies<V: Visitor + ?Sized>(visitor: &mut V, bodies: &[SectionBody]) {
+    for body in bodies {
+        match body {
+            SectionBody::Block(block) => walk_block(visitor, block),
+            SectionBody::Section(section) => walk_section(visitor, section),
+        }
+    }
+}
+
+fn walk_blocks<V: Visitor + ?Sized>(visitor: &mut V, blocks: &[Block]) {
+    for block in blocks {
+        walk_block(visitor, block);
+    }
+}
+
+fn walk_inlines<V: Visitor + ?Sized>(visitor: &mut V, inlines: &[Inline]) {
+    for inline in inlines {
+        walk_inline(visitor, inline);
+    }
+}
+
+pub fn walk_document_mut<V: VisitorMut + ?Sized>(visitor: &mut V, document: &mut Document) {
+    if visitor.enter_document(document) == Visit::Continue {
+        if let Some(header) = document.header_mut() {
+            walk_inlines_mut(visitor, header.title_mut());
+        }
+        walk_section_bodies_mut(visitor, document.blocks_mut());
+    }
+    visitor.leave_document(document);
+}
+
+pub fn walk_section_mut<V: VisitorMut + ?Sized>(visitor: &mut V, section: &mut Section) {
+    if visitor.enter_section(section) == Visit::Continue {
+        walk_inlines_mut(visitor, section.title_mut());
+        walk_section_bodies_mut(visitor, section.blocks_mut());
+    }
+    visitor.leave_section(section);
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    if visitor.enter_block(block) == Visit::Continue {
+        if let Some(title) = block.title_mut() {
+            walk_inlines_mut(visitor, title);
+        }
+        match block {
+            Block::BlockParent(parent) => walk_blocks_mut(visitor, parent.body_mut().blocks_mut()),
+            Block::BlockLeaf(leaf) => walk_inlines_mut(visitor, leaf.body_mut().inlines_mut()),
+            Block::BlockMacro(_) | Block::BlockBreak(_) => {}
+            Block::AnyList(AnyList::List { items, .. }) => {
+                for item in items {
+                    walk_list_item_mut(visitor, item);
+                }
+            }
+            Block::AnyList(AnyList::Dlist { items, .. }) => {
+                for item in items {
+                    walk_dlist_item_mut(visitor, item);
+                }
+            }
+            Block::Table(table) => {
+                for cell in table.rows_mut().flatten() {
+                    walk_table_cell_mut(visitor, cell);
+                }
+            }
+        }
+    }
+    visitor.leave_block(block);
+}
+
+pub fn walk_list_item_mut<V: VisitorMut + ?Sized>(visitor: &mut V, item: &mut ListItem) {
+    if visitor.enter_list_item(item) == Visit::Continue {
+        walk_inlines_mut(visitor, item.principal_mut());
+        walk_blocks_mut(visitor, item.blocks_mut());
+    }
+    visitor.leave_list_item(item);
+}
+
+pub fn walk_dlist_item_mut<V: VisitorMut + ?Sized>(visitor: &mut V, item: &mut DlistItem) {
+    if visitor.enter_dlist_item(item) == Visit::Continue {
+        for term in item.terms_mut() {
+            walk_inlines_mut(visitor, term);
+        }
+        walk_inlines_mut(visitor, item.principal_mut());
+        walk_blocks_mut(visitor, item.blocks_mut());
+    }
+    visitor.leave_dlist_item(item);
+}
+
+pub fn walk_table_cell_mut<V: VisitorMut + ?Sized>(visitor: &mut V, cell: &mut TableCell) {
+    if visitor.enter_table_cell(cell) == Visit::Continue {
+        if let Some(inlines) = cell.inlines_mut() {
+            walk_inlines_mut(visitor, inlines);
+        }
+        if let Some(blocks) = cell.blocks_mut() {
+            walk_blocks_mut(visitor, blocks);
+        }
+    }
+    visitor.leave_table_cell(cell);
+}
+
+pub fn walk_inline_mut<V: VisitorMut + ?Sized>(visitor: &mut V, inline: &mut Inline) {
+    if visitor.enter_inline(inline) == Visit::Continue {
+        if let Inline::Span(span) = inline {
+            walk_inlines_mut(visitor, span.inlines_mut());
+        }
+    }
+    visitor.leave_inline(inline);
+}
+
+fn walk_section_bodies_mut<V: VisitorMut + ?Sized>(visitor: &mut V, bodies: &mut [SectionBody]) {
+    for body in bodies {
+        match body {
+            SectionBody::Block(block) => walk_block_mut(visitor, block),
+            SectionBody::Section(section) => walk_section_mut(visitor, section),
+        }
+    }
+}
+
+fn walk_blocks_mut<V: VisitorMut + ?Sized>(visitor: &mut V, blocks: &mut [Block]) {
+    for block in blocks {
+        walk_block_mut(visitor, block);
+    }
+}
+
+fn walk_inlines_mut<V: VisitorMut + ?Sized>(visitor: &mut V, inlines: &mut [Inline]) {
+    for inline in inlines {
+        walk_inline_mut(visitor, inline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asg::BlockLeaf;
+    use crate::Parser;
+
+    const TEXT: &str = "= Title\n\n== Section\n\nSome *text*.\n\n----\nskipped\n----\n\n* item\n+\nimage::old.png[]\n\n=== Nested";
+
+    /// Collects the texts and the nesting of the nodes, outside listing blocks.
+    #[derive(Default)]
+    struct Texts {
+        texts: Vec<String>,
+        depth: usize,
+        max_depth: usize,
+    }
+    impl Visitor for Texts {
+        fn enter_block(&mut self, block: &Block) -> Visit {
+            self.depth += 1;
+            self.max_depth = self.max_depth.max(self.depth);
+            match block {
+                Block::BlockLeaf(BlockLeaf::Listing(_)) => Visit::SkipChildren,
+                _ => Visit::Continue,
+            }
+        }
+
+        fn leave_block(&mut self, _block: &Block) {
+            self.depth -= 1;
+        }
+
+        fn enter_inline(&mut self, inline: &Inline) -> Visit {
+            if let Inline::Text(text) = inline {
+                self.texts.push(text.value().to_owned());
+            }
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn visitor() {
+        let doc = Parser::new(TEXT).parse_to_asg().unwrap();
+        let mut texts = Texts::default();
+        walk_document(&mut texts, &doc);
+
+        assert_eq!(
+            vec!["Title", "Section", "Some ", "text", ".", "item", "Nested"],
+            texts.texts
+        );
+        assert_eq!((0, 2), (texts.depth, texts.max_depth));
+    }
+
+    /// Moves the sections one level up and the images to a directory.
+    struct Rewriter;
+    impl VisitorMut for Rewriter {
+        fn enter_section(&mut self, section: &mut Section) -> Visit {
+            section.set_level(section.level() - 1);
+            Visit::Continue
+        }
+
+        fn enter_block(&mut self, block: &mut Block) -> Visit {
+            if let Block::BlockMacro(image) = block {
+                let target = format!("images/{}", image.target().unwrap_or_default());
+                image.body_mut().set_target(&target);
+            }
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn mutable_visitor() {
+        let mut doc = Parser::new(TEXT).parse_to_asg().unwrap();
+        walk_document_mut(&mut Rewriter, &mut doc);
+
+        let [SectionBody::Section(section)] = doc.blocks() else {
+            panic!("expected a section");
+        };
+        assert_eq!(0, section.level());
+        let [.., SectionBody::Block(Block::AnyList(list)), SectionBody::Section(nested)] =
+            section.blocks()
+        else {
+            panic!("expected a list and a section");
+        };
+        assert_eq!(1, nested.level());
+        let [Block::BlockMacro(image)] = list.items()[0].blocks() else {
+            panic!("expected an image");
+        };
+        assert_eq!(Some("images/old.png"), image.target());
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+
+/// An error which stops the parsing of a document, at the line and the
+/// column where it was found.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A line of the document header is not a header line.
+    InvalidHeader { line: usize, column: usize },
+    /// An attribute entry has a name which is not a valid attribute name.
+    InvalidAttributeName {
+        name: String,
+        line: usize,
+        column: usize,
+    },
+    /// The author line of the document header is malformed.
+    InvalidAuthor { line: usize, column: usize },
+    /// The revision line of the document header is malformed.
+    InvalidRevision { line: usize, column: usize },
+    /// A manpage has no document title.
+    MissingDocumentTitle { line: usize, column: usize },
+    /// A section heading is more than one level below its parent.
+    SkippedSectionLevel {
+        expected: usize,
+        found: usize,
+        line: usize,
+        column: usize,
+    },
+    /// A level 0 section heading is used in a document which is not a book.
+    Level0InArticle { line: usize, column: usize },
+    /// A line was given to a block which cannot take it.
+    UnexpectedLine { line: usize, column: usize },
+    /// A warning reported as an error by a strict
+    /// [`Parser`](crate::Parser::with_strict).
+    Warning {
+        message: String,
+        /// The path of the included file the warning is about, or of the
+        /// document given to [`Parser::with_source`](crate::Parser::with_source).
+        source: Option<String>,
+        line: usize,
+        column: usize,
+    },
+    /// The ASG read by [`Parser::parse_from_asg`](crate::Parser::parse_from_asg) is not valid.
+    InvalidAsg {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+}
+impl Error {
+    /// Returns the line of the source where the error was found, starting at 1.
+    pub fn line(&self) -> usize {
+        match self {
+            Self::InvalidHeader { line, .. }
+            | Self::InvalidAttributeName { line, .. }
+            | Self::InvalidAuthor { line, .. }
+            | Self::InvalidRevision { line, .. }
+            | Self::MissingDocumentTitle { line, .. }
+            | Self::SkippedSectionLevel { line, .. }
+            | Self::Level0InArticle { line, .. }
+            | Self::UnexpectedLine { line, .. }
+            | Self::Warning { line, .. }
+            | Self::InvalidAsg { line, .. } => *line,
+        }
+    }
+
+    /// Returns the column of the line where the error was found, starting at 1.
+    pub fn column(&self) -> usize {
+        match self {
+            Self::InvalidHeader { column, .. }
+            | Self::InvalidAttributeName { column, .. }
+            | Self::InvalidAuthor { column, .. }
+            | Self::InvalidRevision { column, .. }
+            | Self::MissingDocumentTitle { column, .. }
+            | Self::SkippedSectionLevel { column, .. }
+            | Self::Level0InArticle { column, .. }
+            | Self::UnexpectedLine { column, .. }
+            | Self::Warning { column, .. }
+            | Self::InvalidAsg { column, .. } => *column,
+        }
+    }
+
+    /// Returns the description of the error, without its position.
+    pub fn message(&self) -> String {
+        match self {
+            Self::InvalidHeader { .. } => "invalid document header".to_owned(),
+            Self::InvalidAttributeName { name, .. } => {
+                format!("invalid document attribute: {}", name)
+            }
+            Self::InvalidAuthor { .. } => "invalid author format".to_owned(),
+            Self::InvalidRevision { .. } => "invalid revision format".to_owned(),
+            Self::MissingDocumentTitle { .. } => {
+                "document title is required for doctype manpage".to_owned()
+            }
+            Self::SkippedSectionLevel {
+                expected, found, ..
+            } => format!(
+                "cannot skip section level: expected level {}, got {}",
+                expected, found
+            ),
+            Self::Level0InArticle { .. } => {
+                "level 0 sections can only be used when doctype is book".to_owned()
+            }
+            Self::UnexpectedLine { .. } => "line does not belong to the current block".to_owned(),
+            Self::Warning { message, .. } => message.clone(),
+            Self::InvalidAsg { message, .. } => format!("invalid ASG: {}", message),
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Self::Warning {
+            source: Some(source),
+            ..
+        } = self
+        {
+            write!(f, "{}: ", source)?;
+        }
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line(),
+            self.column(),
+            self.message()
+        )
+    }
+}
+impl std::error::Error for Error {}
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The parser worked around the problem.
+    Warning,
+    /// The parser recovered from an error which a strict parse stops at.
+    Error,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem reported by [`Parser::parse_with_diagnostics`](crate::Parser::parse_with_diagnostics).
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// The path of the file the line is in, when the document has one or
+    /// the line comes from an included file.
+    pub source: Option<String>,
+    /// The line of the source, starting at 1.
+    pub line: usize,
+    /// The column of the line, starting at 1.
+    pub column: usize,
+}
+impl Diagnostic {
+    /// Returns the diagnostic of an error found in the file `source`, where a
+    /// warning keeps its severity unless `is_strict` is set.
+    pub(crate) fn new(error: &Error, source: Option<&str>, is_strict: bool) -> Self {
+        let severity = match error {
+            Error::Warning { .. } if !is_strict => Severity::Warning,
+            _ => Severity::Error,
+        };
+
+        Self {
+            severity,
+            message: error.message(),
+            source: source.map(str::to_owned),
+            line: error.line(),
+            column: error.column(),
+        }
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}: ", source)?;
+        }
+        write!(
+            f,
+            "line {}, column {}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::InvalidAsg {
+            message: error.to_string(),
+            line: error.line(),
+            column: error.column(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_display() {
+        let error = Error::SkippedSectionLevel {
+            expected: 2,
+            found: 3,
+            line: 7,
+            column: 1,
+        };
+
+        assert_eq!((7, 1), (error.line(), error.column()));
+        assert_eq!(
+            "line 7, column 1: cannot skip section level: expected level 2, got 3",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,1788 @@
+//! Converts the ASG to HTML, in the structure and with the class names of
+//! the `html5` converter of Asciidoctor, so that its stylesheets apply.
+
+use crate::asg::{
+    walk_document, walk_section, AnyList, Block, BlockBreak, BlockBreakVariant, BlockForm,
+    BlockLeaf, BlockLeafBody, BlockMacro, BlockMetadata, BlockParent, CellStyle, DlistStyle,
+    Document, HorizontalAlignment, Inline, ListVariant, NumberingStyle, Section, SectionBody,
+    SpanVariant, Table, TableCell, TableColumn, VerticalAlignment, Visit, Visitor,
+};
+
+use std::collections::{HashMap, HashSet};
+
+const WEBFONTS: &str = "Open+Sans:300,300italic,400,400italic,600,600italic%7CNoto+Serif:400,400italic,700,700italic%7CDroid+Sans+Mono:400,700";
+const FONT_AWESOME: &str =
+    "https://cdnjs.cloudflare.com/ajax/libs/font-awesome/4.7.0/css/font-awesome.min.css";
+
+impl Document {
+    /// Converts the document to the body of an HTML page, like the embedded
+    /// output of Asciidoctor, which has the document title only with the
+    /// `showtitle` attribute.
+    pub fn to_html(&self) -> String {
+        let mut converter = Converter::new(Some(self));
+        converter.index_sections(|indexer| walk_document(indexer, self));
+
+        converter.embedded(self)
+    }
+
+    /// Converts the document to a full HTML page, like the standalone output
+    /// of Asciidoctor, which links its stylesheet rather than embedding it.
+    pub fn to_standalone_html(&self) -> String {
+        let mut converter = Converter::new(Some(self));
+        converter.index_sections(|indexer| walk_document(indexer, self));
+
+        converter.standalone(self)
+    }
+}
+
+impl Section {
+    /// Converts the section to HTML, from its `sectN` wrapper.
+    pub fn to_html(&self) -> String {
+        let mut converter = Converter::new(None);
+        converter.index_sections(|indexer| walk_section(indexer, self));
+
+        converter.section(self)
+    }
+}
+
+impl Block {
+    /// Converts the block to HTML.
+    pub fn to_html(&self) -> String {
+        Converter::new(None).block(self)
+    }
+}
+
+/// The id of a section, and its number when the sections are numbered.
+#[derive(Debug, Clone)]
+struct SectionEntry {
+    id: String,
+    number: Option<String>,
+}
+impl SectionEntry {
+    fn heading(&self, section: &Section) -> String {
+        let title = inline_html(section.title());
+        match &self.number {
+            Some(number) => format!("{number} {title}"),
+            None => title,
+        }
+    }
+}
+
+/// Gives each section its id and its number in the order of the source, as
+/// Asciidoctor does while parsing: a generated id is kept apart from the
+/// ids of the sections and the blocks before it.
+struct SectionIndexer<'a> {
+    attributes: Option<&'a HashMap<String, String>>,
+    ids: HashSet<String>,
+    /// The numbers of the current section and of its parents.
+    numbers: Vec<usize>,
+    sections: Vec<SectionEntry>,
+}
+impl SectionIndexer<'_> {
+    /// Returns the id Asciidoctor generates from the title of a section: the
+    /// words of its text lowercased and joined by the `idseparator`, after
+    /// the `idprefix`, with a counter when it is taken.
+    fn generate_id(&self, title: &str) -> String {
+        let prefix = attribute_value(self.attributes, "idprefix").unwrap_or("_");
+        let separator: String = attribute_value(self.attributes, "idseparator")
+            .unwrap_or("_")
+            .chars()
+            .take(1)
+            .collect();
+
+        let chars: Vec<char> = title.to_lowercase().chars().collect();
+        let mut id = String::new();
+        let mut is_separated = false;
+        let mut i = 0;
+        while i < chars.len() {
+            // The tags and the character references are left out.
+            let skipped = match chars[i] {
+                '<' => chars[i..].iter().position(|c| *c == '>').map(|end| end + 1),
+                '&' => entity_length(&chars[i..]),
+                _ => None,
+            };
+            if let Some(length) = skipped {
+                i += length;
+                continue;
+            }
+
+            match chars[i] {
+                ' ' | '.' | '-' if !is_separated => {
+                    id.push_str(&separator);
+                    is_separated = true;
+                }
+                ' ' | '.' | '-' => {}
+                c if c.is_alphanumeric() || c == '_' => {
+                    id.push(c);
+                    is_separated = false;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let mut id = id.as_str();
+        if !separator.is_empty() {
+            id = id.strip_suffix(&separator).unwrap_or(id);
+            if prefix.is_empty() {
+                id = id.strip_prefix(&separator).unwrap_or(id);
+            }
+        }
+
+        let base = format!("{prefix}{id}");
+        let mut id = base.clone();
+        let mut counter = 2;
+        while self.ids.contains(&id) {
+            id = format!("{base}{separator}{counter}");
+            counter += 1;
+        }
+
+        id
+    }
+
+    /// Counts a section of `level`, returning its number like `1.2.` when
+    /// the `sectnums` attribute numbers it.
+    fn number(&mut self, level: usize) -> Option<String> {
+        if level == 0 {
+            return None;
+        }
+        self.numbers.resize(level, 0);
+        self.numbers[level - 1] += 1;
+
+        let levels = attribute_value(self.attributes, "sectnumlevels")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(3);
+        let is_numbered = attribute_value(self.attributes, "sectnums").is_some();
+
+        (is_numbered && level <= levels)
+            .then(|| self.numbers.iter().map(|n| format!("{n}.")).collect())
+    }
+}
+impl Visitor for SectionIndexer<'_> {
+    fn enter_section(&mut self, section: &Section) -> Visit {
+        let id = match section.id() {
+            Some(id) => id.to_owned(),
+            None => self.generate_id(&inline_html(section.title())),
+        };
+        self.ids.insert(id.clone());
+        let number = self.number(section.level());
+        self.sections.push(SectionEntry { id, number });
+
+        Visit::Continue
+    }
+
+    fn enter_block(&mut self, block: &Block) -> Visit {
+        if let Some(id) = block.id() {
+            self.ids.insert(id.to_owned());
+        }
+
+        Visit::Continue
+    }
+}
+
+/// Where the table of contents goes, given by the `toc` attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TocPlacement {
+    /// In the header, or beside the content at a position like `left`.
+    Auto(Option<&'static str>),
+    /// After the preamble.
+    Preamble,
+    /// At the `toc::[]` block macro.
+    Macro,
+}
+
+/// The kinds of blocks whose titles are numbered, like `Table 1.`.
+#[derive(Debug, Clone, Copy)]
+enum Caption {
+    Example,
+    Table,
+    Figure,
+}
+
+/// Converts the nodes of a document, keeping what spans them: the ids and
+/// the numbers of the sections, and the numbers of the captions.
+struct Converter<'a> {
+    document: Option<&'a Document>,
+    sections: Vec<SectionEntry>,
+    next_section: usize,
+    examples: usize,
+    tables: usize,
+    figures: usize,
+}
+impl<'a> Converter<'a> {
+    fn new(document: Option<&'a Document>) -> Self {
+        Self {
+            document,
+            sections: Vec::with_capacity(0),
+            next_section: 0,
+            examples: 0,
+            tables: 0,
+            figures: 0,
+        }
+    }
+
+    /// Gives the sections walked by `walk` their ids and numbers, which they
+    /// have to be converted in the same order.
+    fn index_sections(&mut self, walk: impl FnOnce(&mut SectionIndexer<'a>)) {
+        let mut indexer = SectionIndexer {
+            attributes: self.document.and_then(|d| d.attributes()),
+            ids: HashSet::new(),
+            numbers: Vec::new(),
+            sections: Vec::new(),
+        };
+        walk(&mut indexer);
+
+        self.sections = indexer.sections;
+    }
+
+    /// Returns the value of an attribute set in the header of the document.
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        attribute_value(self.document.and_then(|d| d.attributes()), name)
+    }
+
+    fn has_attribute(&self, name: &str) -> bool {
+        self.attribute(name).is_some()
+    }
+
+    fn toc_placement(&self) -> Option<TocPlacement> {
+        Some(match self.attribute("toc")? {
+            "left" | "<" | "&lt;" => TocPlacement::Auto(Some("left")),
+            "right" | ">" | "&gt;" => TocPlacement::Auto(Some("right")),
+            "top" | "^" => TocPlacement::Auto(Some("top")),
+            "bottom" | "v" => TocPlacement::Auto(Some("bottom")),
+            "preamble" => TocPlacement::Preamble,
+            "macro" => TocPlacement::Macro,
+            _ => TocPlacement::Auto(None),
+        })
+    }
+
+    /// Returns the class of the table of contents, `toc2` for one beside the content.
+    fn toc_class(&self) -> &'a str {
+        self.attribute("toc-class")
+            .unwrap_or(match self.toc_placement() {
+                Some(TocPlacement::Auto(Some(_))) => "toc2",
+                _ => "toc",
+            })
+    }
+
+    fn toc_title(&self) -> String {
+        text_html(self.attribute("toc-title").unwrap_or("Table of Contents"))
+    }
+
+    /// Returns the table of contents of the header or the preamble, if the
+    /// document has sections.
+    fn toc(&self, doc: &Document) -> Option<String> {
+        let levels = self
+            .attribute("toclevels")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(2);
+        let outline = self.outline(doc.blocks(), levels, &mut 0)?;
+
+        Some(format!(
+            "<div id=\"toc\" class=\"{}\">\n<div id=\"toctitle\">{}</div>\n{outline}\n</div>",
+            self.toc_class(),
+            self.toc_title()
+        ))
+    }
+
+    /// Returns the nested lists of links to the sections of `bodies`, down
+    /// to `levels`; `index` counts the sections walked, deeper ones included.
+    fn outline(&self, bodies: &[SectionBody], levels: usize, index: &mut usize) -> Option<String> {
+        let mut lines = Vec::new();
+        for body in bodies {
+            let SectionBody::Section(section) = body else {
+                continue;
+            };
+            let entry = &self.sections[*index];
+            *index += 1;
+
+            if lines.is_empty() {
+                lines.push(format!("<ul class=\"sectlevel{}\">", section.level()));
+            }
+            let link = format!(
+                "<li><a href=\"#{}\">{}</a>",
+                entry.id,
+                entry.heading(section)
+            );
+            match self
+                .outline(section.blocks(), levels, index)
+                .filter(|_| section.level() < levels)
+            {
+                Some(children) => {
+                    lines.push(link);
+                    lines.push(children);
+                    lines.push("</li>".to_owned());
+                }
+                None => lines.push(format!("{link}</li>")),
+            }
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        lines.push("</ul>".to_owned());
+
+        Some(lines.join("\n"))
+    }
+
+    fn embedded(&mut self, doc: &Document) -> String {
+        let mut lines = Vec::new();
+        if let Some(header) = doc.header() {
+            if self.has_attribute("showtitle") && !self.has_attribute("notitle") {
+                lines.push(format!("<h1>{}</h1>", inline_html(header.title())));
+            }
+        }
+        if let Some(TocPlacement::Auto(_)) = self.toc_placement() {
+            lines.extend(self.toc(doc));
+        }
+        lines.push(self.document_content(doc));
+
+        lines.join("\n")
+    }
+
+    fn standalone(&mut self, doc: &Document) -> String {
+        let mut lines = vec!["<!DOCTYPE html>".to_owned()];
+        match self.has_attribute("nolang") {
+            true => lines.push("<html>".to_owned()),
+            false => lines.push(format!(
+                "<html lang=\"{}\">",
+                self.attribute("lang").unwrap_or("en")
+            )),
+        }
+        lines.extend(self.head(doc));
+
+        let doctype = self
+            .attribute("doctype")
+            .unwrap_or_else(|| doc.doctype().name());
+        let mut classes = vec![doctype];
+        let has_sections = doc
+            .blocks()
+            .iter()
+            .any(|b| matches!(b, SectionBody::Section(_)));
+        if let (Some(TocPlacement::Auto(Some(position))), true) =
+            (self.toc_placement(), has_sections)
+        {
+            classes.push(self.toc_class());
+            classes.push(match position {
+                "left" => "toc-left",
+                "right" => "toc-right",
+                "top" => "toc-top",
+                _ => "toc-bottom",
+            });
+        }
+        lines.push(format!("<body class=\"{}\">", classes.join(" ")));
+
+        if !self.has_attribute("noheader") {
+            lines.push("<div id=\"header\">".to_owned());
+            if let Some(header) = doc.header().filter(|_| !self.has_attribute("notitle")) {
+                lines.push(format!("<h1>{}</h1>", inline_html(header.title())));
+            }
+            let details = self.details();
+            if !details.is_empty() {
+                lines.push("<div class=\"details\">".to_owned());
+                lines.extend(details);
+                lines.push("</div>".to_owned());
+            }
+            if let Some(TocPlacement::Auto(_)) = self.toc_placement() {
+                lines.extend(self.toc(doc));
+            }
+            lines.push("</div>".to_owned());
+        }
+
+        lines.push(format!(
+            "<div id=\"content\">\n{}\n</div>",
+            self.document_content(doc)
+        ));
+
+        if !self.has_attribute("nofooter") {
+            lines.push("<div id=\"footer\">".to_owned());
+            lines.push("<div id=\"footer-text\">".to_owned());
+            let version_label = self.attribute("version-label").unwrap_or("Version");
+            if let Some(revnumber) = self.attribute("revnumber") {
+                lines.push(format!("{version_label} {}<br>", escape(revnumber)));
+            }
+            if let Some(docdate) = self.attribute("docdate") {
+                let label = self
+                    .attribute("last-update-label")
+                    .unwrap_or("Last updated");
+                let doctime = self.attribute("doctime").unwrap_or_default();
+                lines.push(
+                    format!("{label} {} {}", escape(docdate), escape(doctime))
+                        .trim_end()
+                        .to_owned(),
+                );
+            }
+            lines.push("</div>".to_owned());
+            lines.push("</div>".to_owned());
+        }
+        lines.push("</body>".to_owned());
+        lines.push("</html>".to_owned());
+
+        lines.join("\n")
+    }
+
+    /// Returns the lines of the `head` element of a standalone page.
+    fn head(&self, doc: &Document) -> Vec<String> {
+        let mut lines = vec![
+            "<head>".to_owned(),
+            format!(
+                "<meta charset=\"{}\">",
+                self.attribute("encoding").unwrap_or("UTF-8")
+            ),
+            "<meta http-equiv=\"X-UA-Compatible\" content=\"IE=edge\">".to_owned(),
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">".to_owned(),
+            format!(
+                "<meta name=\"generator\" content=\"asciidoc2rs {}\">",
+                env!("CARGO_PKG_VERSION")
+            ),
+        ];
+        for (attribute, name) in [
+            ("app-name", "application-name"),
+            ("description", "description"),
+            ("keywords", "keywords"),
+        ] {
+            if let Some(value) = self.attribute(attribute) {
+                lines.push(format!(
+                    "<meta name=\"{name}\" content=\"{}\">",
+                    escape_attribute(value)
+                ));
+            }
+        }
+        let authors: Vec<&str> = (1..)
+            .map_while(|n| self.attribute(&author_key("author", n)))
+            .collect();
+        if !authors.is_empty() {
+            lines.push(format!(
+                "<meta name=\"author\" content=\"{}\">",
+                escape_attribute(&authors.join(", "))
+            ));
+        }
+        if let Some(copyright) = self.attribute("copyright") {
+            lines.push(format!(
+                "<meta name=\"copyright\" content=\"{}\">",
+                escape_attribute(copyright)
+            ));
+        }
+
+        // The title falls back to the one of the first section.
+        let title = match (doc.header(), doc.blocks().first()) {
+            (Some(header), _) => strip_tags(&inline_html(header.title())),
+            (None, Some(SectionBody::Section(section))) => {
+                strip_tags(&inline_html(section.title()))
+            }
+            _ => text_html(self.attribute("untitled-label").unwrap_or("Untitled")),
+        };
+        lines.push(format!("<title>{title}</title>"));
+
+        let stylesdir = self.attribute("stylesdir").unwrap_or(".");
+        match self.attribute("stylesheet") {
+            None | Some("" | "DEFAULT") => {
+                let webfonts = self.attribute("webfonts").filter(|w| !w.is_empty());
+                lines.push(format!(
+                    "<link rel=\"stylesheet\" href=\"https://fonts.googleapis.com/css?family={}\">",
+                    webfonts.unwrap_or(WEBFONTS)
+                ));
+                lines.push(format!(
+                    "<link rel=\"stylesheet\" href=\"{}\">",
+                    web_path("asciidoctor.css", stylesdir)
+                ));
+            }
+            Some(stylesheet) => lines.push(format!(
+                "<link rel=\"stylesheet\" href=\"{}\">",
+                web_path(stylesheet, stylesdir)
+            )),
+        }
+        if self.attribute("icons") == Some("font") {
+            lines.push(format!("<link rel=\"stylesheet\" href=\"{FONT_AWESOME}\">"));
+        }
+        lines.push("</head>".to_owned());
+
+        lines
+    }
+
+    /// Returns the lines of the authors and the revision in the header.
+    fn details(&self) -> Vec<String> {
+        let mut details = Vec::new();
+        for n in 1.. {
+            let Some(author) = self.attribute(&author_key("author", n)) else {
+                break;
+            };
+            let suffix = if n > 1 { n.to_string() } else { String::new() };
+            details.push(format!(
+                "<span id=\"author{suffix}\" class=\"author\">{}</span><br>",
+                text_html(author)
+            ));
+            if let Some(email) = self.attribute(&author_key("email", n)) {
+                let email = escape_attribute(email);
+                details.push(format!(
+                    "<span id=\"email{suffix}\" class=\"email\"><a href=\"mailto:{email}\">{email}</a></span><br>"
+                ));
+            }
+        }
+
+        if let Some(revnumber) = self.attribute("revnumber") {
+            let label = self.attribute("version-label").unwrap_or("Version");
+            let comma = if self.has_attribute("revdate") {
+                ","
+            } else {
+                ""
+            };
+            details.push(format!(
+                "<span id=\"revnumber\">{} {}{comma}</span>",
+                label.to_lowercase(),
+                escape(revnumber)
+            ));
+        }
+        if let Some(revdate) = self.attribute("revdate") {
+            details.push(format!("<span id=\"revdate\">{}</span>", escape(revdate)));
+        }
+        if let Some(revremark) = self.attribute("revremark") {
+            details.push(format!(
+                "<br><span id=\"revremark\">{}</span>",
+                text_html(revremark)
+            ));
+        }
+
+        details
+    }
+
+    /// Converts the blocks and the sections of the document, wrapping the
+    /// blocks before the first section in a preamble when it has a header.
+    fn document_content(&mut self, doc: &Document) -> String {
+        let bodies = doc.blocks();
+        let preamble_length = bodies
+            .iter()
+            .take_while(|b| matches!(b, SectionBody::Block(_)))
+            .count();
+        let is_book = self.attribute("doctype").unwrap_or(doc.doctype().name()) == "book";
+        // A preamble without sections after it is unwrapped.
+        if preamble_length == 0
+            || preamble_length == bodies.len()
+            || (doc.header().is_none() && !is_book)
+        {
+            return self.section_bodies(bodies);
+        }
+
+        let preamble = self.section_bodies(&bodies[..preamble_length]);
+        let toc = match self.toc_placement() {
+            Some(TocPlacement::Preamble) => self.toc(doc).map(|toc| format!("\n{toc}")),
+            _ => None,
+        };
+        let sections = self.section_bodies(&bodies[preamble_length..]);
+
+        format!(
+            "<div id=\"preamble\">\n<div class=\"sectionbody\">\n{preamble}\n</div>{}\n</div>\n{sections}",
+            toc.unwrap_or_default()
+        )
+    }
+
+    fn section_bodies(&mut self, bodies: &[SectionBody]) -> String {
+        bodies
+            .iter()
+            .map(|body| match body {
+                SectionBody::Block(block) => self.block(block),
+                SectionBody::Section(section) => self.section(section),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn section(&mut self, section: &Section) -> String {
+        let entry = self.sections[self.next_section].clone();
+        self.next_section += 1;
+
+        let heading = entry.heading(section);
+        let role = roles(section.metadata());
+        let content = self.section_bodies(section.blocks());
+        let level = section.level();
+        match level {
+            0 => format!(
+                "<h1 id=\"{}\" class=\"sect0{role}\">{heading}</h1>\n{content}",
+                entry.id
+            ),
+            1 => format!(
+                "<div class=\"sect1{role}\">\n<h2 id=\"{}\">{heading}</h2>\n<div class=\"sectionbody\">\n{content}\n</div>\n</div>",
+                entry.id
+            ),
+            _ => format!(
+                "<div class=\"sect{level}{role}\">\n<h{0} id=\"{1}\">{heading}</h{0}>\n{content}\n</div>",
+                level + 1,
+                entry.id
+            ),
+        }
+    }
+
+    /// Converts the blocks of a compound block, one after another.
+    fn blocks(&mut self, blocks: &[Block]) -> String {
+        blocks
+            .iter()
+            .map(|block| self.block(block))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn block(&mut self, block: &Block) -> String {
+        match block {
+            Block::BlockParent(parent) => self.block_parent(block, parent),
+            Block::BlockLeaf(leaf) => self.block_leaf(block, leaf),
+            Block::BlockMacro(block_macro) => self.block_macro(block, block_macro),
+            Block::BlockBreak(block_break) => block_break_html(block_break),
+            Block::AnyList(list) => self.list(block, list),
+            Block::Table(table) => self.table(block, table),
+        }
+    }
+
+    /// Returns the title of a block with its caption, like `Example 1. `,
+    /// which the `caption` attribute of the block replaces.
+    fn captioned_title(&mut self, block: &Block, caption: Caption) -> Option<String> {
+        let title = inline_html(block.title()?);
+        if let Some(caption) = block.metadata().and_then(|m| m.attribute("caption")) {
+            return Some(format!("{}{title}", text_html(caption)));
+        }
+
+        let (label, count) = match caption {
+            Caption::Example => (
+                self.attribute("example-caption").unwrap_or("Example"),
+                &mut self.examples,
+            ),
+            Caption::Table => (
+                self.attribute("table-caption").unwrap_or("Table"),
+                &mut self.tables,
+            ),
+            Caption::Figure => (
+                self.attribute("figure-caption").unwrap_or("Figure"),
+                &mut self.figures,
+            ),
+        };
+        *count += 1;
+
+        Some(format!("{} {count}. {title}", text_html(label)))
+    }
+
+    /// Returns the content of a paragraph, whose line breaks are kept with
+    /// the `hardbreaks` option.
+    fn paragraph_text(&self, inlines: &[Inline], metadata: Option<&BlockMetadata>) -> String {
+        let is_hard = metadata.is_some_and(|m| m.has_option("hardbreaks"))
+            || self.has_attribute("hardbreaks-option")
+            || self.has_attribute("hardbreaks");
+
+        line_breaks(&spans_html(inlines), is_hard)
+    }
+
+    fn block_parent(&mut self, block: &Block, parent: &BlockParent) -> String {
+        let body = parent.body();
+        let metadata = block.metadata();
+        let id = id_attribute(block.id());
+        let role = roles(metadata);
+        let style = metadata.and_then(|m| m.attribute("1"));
+        let title = block.title().map(inline_html);
+        let title_line = title
+            .as_ref()
+            .map_or_else(String::new, |t| format!("<div class=\"title\">{t}</div>\n"));
+
+        match parent {
+            BlockParent::Admonition(_) => {
+                let label = body.variant().map_or("NOTE", |v| v.label());
+                let name = label.to_lowercase();
+                let caption = self
+                    .attribute(&format!("{name}-caption"))
+                    .map_or_else(|| format!("{}{}", &label[..1], &name[1..]), text_html);
+                let icon = match self.attribute("icons") {
+                    Some("font") => format!("<i class=\"fa icon-{name}\" title=\"{caption}\"></i>"),
+                    Some(_) => format!("<img src=\"{}\" alt=\"{caption}\">", self.icon_uri(&name)),
+                    None => format!("<div class=\"title\">{caption}</div>"),
+                };
+                // The content of an admonition paragraph is its text.
+                let content = match (body.form(), body.blocks()) {
+                    (
+                        Some(BlockForm::Paragraph),
+                        [Block::BlockLeaf(BlockLeaf::Paragraph(paragraph))],
+                    ) => self.paragraph_text(paragraph.inlines(), metadata),
+                    (_, blocks) => self.blocks(blocks),
+                };
+
+                format!(
+                    "<div{id} class=\"admonitionblock {name}{role}\">\n<table>\n<tr>\n<td class=\"icon\">\n{icon}\n</td>\n<td class=\"content\">\n{title_line}{content}\n</td>\n</tr>\n</table>\n</div>"
+                )
+            }
+            BlockParent::Example(_) => {
+                let title_line = self
+                    .captioned_title(block, Caption::Example)
+                    .map_or_else(String::new, |t| format!("<div class=\"title\">{t}</div>\n"));
+                let content = self.blocks(body.blocks());
+
+                format!(
+                    "<div{id} class=\"exampleblock{role}\">\n{title_line}<div class=\"content\">\n{content}\n</div>\n</div>"
+                )
+            }
+            BlockParent::Sidebar(_) => {
+                let content = self.blocks(body.blocks());
+
+                format!(
+                    "<div{id} class=\"sidebarblock{role}\">\n<div class=\"content\">\n{title_line}{content}\n</div>\n</div>"
+                )
+            }
+            BlockParent::Open(_) if style == Some("abstract") => {
+                let content = self.blocks(body.blocks());
+
+                format!(
+                    "<div{id} class=\"quoteblock abstract{role}\">\n{title_line}<blockquote>\n{content}\n</blockquote>\n</div>"
+                )
+            }
+            BlockParent::Open(_) => {
+                let style = style
+                    .filter(|s| *s != "open")
+                    .map_or_else(String::new, |s| format!(" {s}"));
+                let content = self.blocks(body.blocks());
+
+                format!(
+                    "<div{id} class=\"openblock{style}{role}\">\n{title_line}<div class=\"content\">\n{content}\n</div>\n</div>"
+                )
+            }
+            BlockParent::Quote(_) => {
+                let title =
+                    title.map_or_else(String::new, |t| format!("\n<div class=\"title\">{t}</div>"));
+                let content = self.blocks(body.blocks());
+
+                format!(
+                    "<div{id} class=\"quoteblock{role}\">{title}\n<blockquote>\n{content}\n</blockquote>{}\n</div>",
+                    attribution(metadata)
+                )
+            }
+        }
+    }
+
+    fn block_leaf(&mut self, block: &Block, leaf: &BlockLeaf) -> String {
+        let body = leaf.body();
+        let metadata = block.metadata();
+        let id = id_attribute(block.id());
+        let role = roles(metadata);
+        let style = metadata.and_then(|m| m.attribute("1"));
+        let title = block.title().map(inline_html);
+        let title_line = title
+            .as_ref()
+            .map_or_else(String::new, |t| format!("<div class=\"title\">{t}</div>\n"));
+        let is_nowrap = metadata.is_some_and(|m| m.has_option("nowrap"));
+
+        match leaf {
+            BlockLeaf::Paragraph(_) => format!(
+                "<div{id} class=\"paragraph{role}\">\n{title_line}<p>{}</p>\n</div>",
+                self.paragraph_text(body.inlines(), metadata)
+            ),
+            BlockLeaf::Listing(_) => {
+                let content = self.verbatim_html(body);
+                let pre = match style {
+                    Some("source") => {
+                        let language = metadata
+                            .and_then(|m| m.attribute("language").or(m.attribute("2")))
+                            .map_or_else(String::new, |l| {
+                                format!(" class=\"language-{l}\" data-lang=\"{l}\"")
+                            });
+                        let nowrap = if is_nowrap { " nowrap" } else { "" };
+                        format!("<pre class=\"highlight{nowrap}\"><code{language}>{content}</code></pre>")
+                    }
+                    _ => format!("<pre{}>{content}</pre>", nowrap_class(is_nowrap)),
+                };
+
+                format!(
+                    "<div{id} class=\"listingblock{role}\">\n{title_line}<div class=\"content\">\n{pre}\n</div>\n</div>"
+                )
+            }
+            BlockLeaf::Literal(_) => format!(
+                "<div{id} class=\"literalblock{role}\">\n{title_line}<div class=\"content\">\n<pre{}>{}</pre>\n</div>\n</div>",
+                nowrap_class(is_nowrap),
+                self.verbatim_html(body)
+            ),
+            BlockLeaf::Pass(_) => verbatim_text(body.inlines()),
+            BlockLeaf::Stem(_) => {
+                let notation = match style {
+                    Some("stem") | None => self.attribute("stem").unwrap_or("asciimath"),
+                    Some(style) => style,
+                };
+                let (open, close) = match notation {
+                    "latexmath" | "latex" | "tex" => ("\\[", "\\]"),
+                    _ => ("\\$", "\\$"),
+                };
+                let equation = verbatim_text(body.inlines());
+                let equation = match equation.starts_with(open) && equation.ends_with(close) {
+                    true => equation,
+                    false => format!("{open}{equation}{close}"),
+                };
+
+                format!(
+                    "<div{id} class=\"stemblock{role}\">\n{title_line}<div class=\"content\">\n{equation}\n</div>\n</div>"
+                )
+            }
+            BlockLeaf::Verse(_) => {
+                let title = title.map_or_else(String::new, |t| {
+                    format!("\n<div class=\"title\">{t}</div>")
+                });
+
+                format!(
+                    "<div{id} class=\"verseblock{role}\">{title}\n<pre class=\"content\">{}</pre>{}\n</div>",
+                    inline_html(body.inlines()),
+                    attribution(metadata)
+                )
+            }
+        }
+    }
+
+    /// Returns the escaped content of a listing or a literal block, whose
+    /// callout markers become numbered badges.
+    fn verbatim_html(&self, body: &BlockLeafBody) -> String {
+        let text = verbatim_text(body.inlines());
+        let callouts = body.callouts();
+
+        text.split('\n')
+            .enumerate()
+            .map(|(index, line)| {
+                let mut markers = callouts.iter().filter(|c| c.line_index() == index);
+                let Some(first) = markers.next() else {
+                    return escape(line);
+                };
+                let column = first.location().start().column();
+                let start = line
+                    .char_indices()
+                    .nth(column.saturating_sub(1))
+                    .map_or(line.len(), |(i, _)| i);
+                // Like Asciidoctor, the `//`, `#`, `--` or `;;` comment prefix in
+                // front of the markers is dropped with them.
+                let (before, source) = line.split_at(start);
+                let is_xml = source.contains("<!--");
+                let badges: Vec<String> = std::iter::once(first)
+                    .chain(markers)
+                    .map(|c| self.callout_badge(c.number(), is_xml))
+                    .collect();
+
+                format!("{}{}", escape(before), badges.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn callout_badge(&self, number: usize, is_xml: bool) -> String {
+        match self.attribute("icons") {
+            Some("font") => {
+                format!("<i class=\"conum\" data-value=\"{number}\"></i><b>({number})</b>")
+            }
+            Some(_) => format!(
+                "<img src=\"{}\" alt=\"{number}\">",
+                self.icon_uri(&format!("callouts/{number}"))
+            ),
+            None if is_xml => format!("&lt;!--<b class=\"conum\">({number})</b>--&gt;"),
+            None => format!("<b class=\"conum\">({number})</b>"),
+        }
+    }
+
+    /// Returns the path of an icon image in the `iconsdir`.
+    fn icon_uri(&self, name: &str) -> String {
+        let iconsdir = self.attribute("iconsdir").map_or_else(
+            || {
+                format!(
+                    "{}/icons",
+                    self.attribute("imagesdir").unwrap_or("./images")
+                )
+            },
+            str::to_owned,
+        );
+        let icontype = self.attribute("icontype").unwrap_or("png");
+
+        web_path(&format!("{name}.{icontype}"), &iconsdir)
+    }
+
+    /// Returns the path of an image or a media file in the `imagesdir`.
+    fn image_uri(&self, target: &str) -> String {
+        web_path(target, self.attribute("imagesdir").unwrap_or_default())
+    }
+
+    fn block_macro(&mut self, block: &Block, block_macro: &BlockMacro) -> String {
+        let metadata = block.metadata();
+        let attribute = |name: &str| metadata.and_then(|m| m.attribute(name));
+        let has_option = |name: &str| metadata.is_some_and(|m| m.has_option(name));
+        let id = id_attribute(block.id());
+        let role = roles(metadata);
+        let target = block_macro.target().unwrap_or_default();
+        let size = |name: &str| {
+            attribute(name).map_or_else(String::new, |v| {
+                format!(" {name}=\"{}\"", escape_attribute(v))
+            })
+        };
+        let title = block.title().map_or_else(String::new, |t| {
+            format!("\n<div class=\"title\">{}</div>", inline_html(t))
+        });
+
+        match block_macro {
+            BlockMacro::Image(_) => {
+                let alt = attribute("alt").map_or_else(|| default_alt(target), str::to_owned);
+                let mut image = format!(
+                    "<img src=\"{}\" alt=\"{}\"{}{}>",
+                    escape_attribute(&self.image_uri(target)),
+                    escape_attribute(&alt),
+                    size("width"),
+                    size("height")
+                );
+                if let Some(link) = attribute("link") {
+                    image = format!(
+                        "<a class=\"image\" href=\"{}\">{image}</a>",
+                        escape_attribute(link)
+                    );
+                }
+                let mut classes = "imageblock".to_owned();
+                if let Some(align) = attribute("align") {
+                    classes.push_str(&format!(" text-{align}"));
+                }
+                if let Some(float) = attribute("float") {
+                    classes.push_str(&format!(" {float}"));
+                }
+                let title = self
+                    .captioned_title(block, Caption::Figure)
+                    .map_or_else(String::new, |t| format!("\n<div class=\"title\">{t}</div>"));
+
+                format!(
+                    "<div{id} class=\"{classes}{role}\">\n<div class=\"content\">\n{image}\n</div>{title}\n</div>"
+                )
+            }
+            BlockMacro::Video(_) => {
+                let poster = attribute("poster").map_or_else(String::new, |p| {
+                    format!(" poster=\"{}\"", escape_attribute(&self.image_uri(p)))
+                });
+                let mut flags = String::new();
+                for (option, flag) in [("autoplay", " autoplay"), ("muted", " muted")] {
+                    if has_option(option) {
+                        flags.push_str(flag);
+                    }
+                }
+                if !has_option("nocontrols") {
+                    flags.push_str(" controls");
+                }
+                if has_option("loop") {
+                    flags.push_str(" loop");
+                }
+                if let Some(preload) = attribute("preload") {
+                    flags.push_str(&format!(" preload=\"{}\"", escape_attribute(preload)));
+                }
+
+                format!(
+                    "<div{id} class=\"videoblock{role}\">{title}\n<div class=\"content\">\n<video src=\"{}\"{}{}{poster}{flags}>\nYour browser does not support the video tag.\n</video>\n</div>\n</div>",
+                    escape_attribute(&self.image_uri(target)),
+                    size("width"),
+                    size("height")
+                )
+            }
+            BlockMacro::Audio(_) => {
+                let autoplay = if has_option("autoplay") {
+                    " autoplay"
+                } else {
+                    ""
+                };
+                let controls = if has_option("nocontrols") {
+                    ""
+                } else {
+                    " controls"
+                };
+                let repeat = if has_option("loop") { " loop" } else { "" };
+
+                format!(
+                    "<div{id} class=\"audioblock{role}\">{title}\n<div class=\"content\">\n<audio src=\"{}\"{autoplay}{controls}{repeat}>\nYour browser does not support the audio tag.\n</audio>\n</div>\n</div>",
+                    escape_attribute(&self.image_uri(target))
+                )
+            }
+            BlockMacro::Toc(_) => {
+                const DISABLED: &str = "<!-- toc disabled -->";
+                let Some(doc) = self
+                    .document
+                    .filter(|_| self.toc_placement() == Some(TocPlacement::Macro))
+                else {
+                    return DISABLED.to_owned();
+                };
+                let levels = attribute("levels")
+                    .or(self.attribute("toclevels"))
+                    .and_then(|l| l.parse().ok())
+                    .unwrap_or(2);
+                let Some(outline) = self.outline(doc.blocks(), levels, &mut 0) else {
+                    return DISABLED.to_owned();
+                };
+
+                let (id, title_id) = match block.id() {
+                    Some(id) => (id.to_owned(), format!("{id}title")),
+                    None => ("toc".to_owned(), "toctitle".to_owned()),
+                };
+                let title = block.title().map_or_else(|| self.toc_title(), inline_html);
+                let class = match metadata.map_or(&[][..], |m| m.roles()) {
+                    [] => self.toc_class().to_owned(),
+                    roles => roles.join(" "),
+                };
+
+                format!(
+                    "<div id=\"{id}\" class=\"{class}\">\n<div id=\"{title_id}\" class=\"title\">{title}</div>\n{outline}\n</div>"
+                )
+            }
+        }
+    }
+
+    fn list(&mut self, block: &Block, list: &AnyList) -> String {
+        let metadata = block.metadata();
+        let id = id_attribute(block.id());
+        let role = roles(metadata);
+        let style = metadata.and_then(|m| m.attribute("1"));
+        let mut lines = Vec::new();
+        let push_title = |lines: &mut Vec<String>| {
+            if let Some(title) = block.title() {
+                lines.push(format!("<div class=\"title\">{}</div>", inline_html(title)));
+            }
+        };
+
+        match list.variant() {
+            Some(ListVariant::Unordered) => {
+                let is_checklist = metadata.is_some_and(|m| m.has_option("checklist"));
+                let (checked, unchecked) = match self.attribute("icons") {
+                    _ if metadata.is_some_and(|m| m.has_option("interactive")) => (
+                        "<input type=\"checkbox\" data-item-complete=\"1\" checked> ",
+                        "<input type=\"checkbox\" data-item-complete=\"0\"> ",
+                    ),
+                    Some("font") => (
+                        "<i class=\"fa fa-check-square-o\"></i> ",
+                        "<i class=\"fa fa-square-o\"></i> ",
+                    ),
+                    _ => ("&#10003; ", "&#10063; "),
+                };
+                let checklist = if is_checklist { " checklist" } else { "" };
+                let style_class = style.map_or_else(String::new, |s| format!(" {s}"));
+                lines.push(format!(
+                    "<div{id} class=\"ulist{checklist}{style_class}{role}\">"
+                ));
+                push_title(&mut lines);
+                lines.push(match (is_checklist, style) {
+                    (true, _) => "<ul class=\"checklist\">".to_owned(),
+                    (false, Some(style)) => format!("<ul class=\"{style}\">"),
+                    (false, None) => "<ul>".to_owned(),
+                });
+                for item in list.items() {
+                    let marker = match item.checked().filter(|_| is_checklist) {
+                        Some(true) => checked,
+                        Some(false) => unchecked,
+                        None => "",
+                    };
+                    lines.push("<li>".to_owned());
+                    lines.push(format!("<p>{marker}{}</p>", inline_html(item.principal())));
+                    if !item.blocks().is_empty() {
+                        lines.push(self.blocks(item.blocks()));
+                    }
+                    lines.push("</li>".to_owned());
+                }
+                lines.push("</ul>".to_owned());
+            }
+            Some(ListVariant::Ordered) => {
+                let numbering = list.numbering_style().unwrap_or(NumberingStyle::Arabic);
+                let name = numbering.name();
+                let keyword = match numbering {
+                    NumberingStyle::Loweralpha => " type=\"a\"",
+                    NumberingStyle::Upperalpha => " type=\"A\"",
+                    NumberingStyle::Lowerroman => " type=\"i\"",
+                    NumberingStyle::Upperroman => " type=\"I\"",
+                    _ => "",
+                };
+                // The start is given by the attribute, or by a first marker
+                // numbered from other than 1.
+                let has_start = metadata.is_some_and(|m| m.attribute("start").is_some())
+                    || list.items().first().is_some_and(|i| {
+                        NumberingStyle::parse_marker(i.marker()).is_some_and(|(_, n)| n != 1)
+                    });
+                let start = match (has_start, list.start()) {
+                    (true, Some(start)) => format!(" start=\"{start}\""),
+                    _ => String::new(),
+                };
+                let reversed = match metadata.is_some_and(|m| m.has_option("reversed")) {
+                    true => " reversed",
+                    false => "",
+                };
+
+                lines.push(format!("<div{id} class=\"olist {name}{role}\">"));
+                push_title(&mut lines);
+                lines.push(format!("<ol class=\"{name}\"{keyword}{start}{reversed}>"));
+                for item in list.items() {
+                    lines.push("<li>".to_owned());
+                    lines.push(format!("<p>{}</p>", inline_html(item.principal())));
+                    if !item.blocks().is_empty() {
+                        lines.push(self.blocks(item.blocks()));
+                    }
+                    lines.push("</li>".to_owned());
+                }
+                lines.push("</ol>".to_owned());
+            }
+            Some(ListVariant::Callout) => {
+                lines.push(format!("<div{id} class=\"colist arabic{role}\">"));
+                push_title(&mut lines);
+                let has_icons = self.has_attribute("icons");
+                lines.push(if has_icons { "<table>" } else { "<ol>" }.to_owned());
+                for (index, item) in list.items().iter().enumerate() {
+                    let text = inline_html(item.principal());
+                    let blocks = match item.blocks() {
+                        [] => String::new(),
+                        blocks => format!("\n{}", self.blocks(blocks)),
+                    };
+                    lines.push(match has_icons {
+                        true => format!(
+                            "<tr>\n<td>{}</td>\n<td>{text}{blocks}</td>\n</tr>",
+                            self.callout_badge(index + 1, false)
+                        ),
+                        false => format!("<li>\n<p>{text}</p>{blocks}\n</li>"),
+                    });
+                }
+                lines.push(if has_icons { "</table>" } else { "</ol>" }.to_owned());
+            }
+            None => {
+                let dlist_style = list.dlist_style();
+                lines.push(match dlist_style {
+                    Some(DlistStyle::Qanda) => format!("<div{id} class=\"qlist qanda{role}\">"),
+                    Some(DlistStyle::Horizontal) => format!("<div{id} class=\"hdlist{role}\">"),
+                    None => {
+                        let style = style.map_or_else(String::new, |s| format!(" {s}"));
+                        format!("<div{id} class=\"dlist{style}{role}\">")
+                    }
+                });
+                push_title(&mut lines);
+                self.dlist_items(list, dlist_style, style.is_some(), &mut lines);
+            }
+        }
+        lines.push("</div>".to_owned());
+
+        lines.join("\n")
+    }
+
+    fn dlist_items(
+        &mut self,
+        list: &AnyList,
+        dlist_style: Option<DlistStyle>,
+        has_style: bool,
+        lines: &mut Vec<String>,
+    ) {
+        let strong = match list.metadata().is_some_and(|m| m.has_option("strong")) {
+            true => " strong",
+            false => "",
+        };
+        match dlist_style {
+            Some(DlistStyle::Qanda) => lines.push("<ol>".to_owned()),
+            Some(DlistStyle::Horizontal) => lines.push("<table>".to_owned()),
+            None => lines.push("<dl>".to_owned()),
+        }
+
+        for item in list.dlist_items() {
+            let terms: Vec<String> = item.terms().iter().map(|t| inline_html(t)).collect();
+            let mut description = Vec::new();
+            if !item.principal().is_empty() {
+                description.push(format!("<p>{}</p>", inline_html(item.principal())));
+            }
+            if !item.blocks().is_empty() {
+                description.push(self.blocks(item.blocks()));
+            }
+
+            match dlist_style {
+                Some(DlistStyle::Qanda) => {
+                    lines.push("<li>".to_owned());
+                    lines.extend(terms.iter().map(|t| format!("<p><em>{t}</em></p>")));
+                    lines.extend(description);
+                    lines.push("</li>".to_owned());
+                }
+                Some(DlistStyle::Horizontal) => {
+                    lines.push("<tr>".to_owned());
+                    lines.push(format!("<td class=\"hdlist1{strong}\">"));
+                    lines.push(terms.join("\n<br>\n"));
+                    lines.push("</td>".to_owned());
+                    lines.push("<td class=\"hdlist2\">".to_owned());
+                    lines.extend(description);
+                    lines.push("</td>".to_owned());
+                    lines.push("</tr>".to_owned());
+                }
+                None => {
+                    let class = if has_style { "" } else { " class=\"hdlist1\"" };
+                    lines.extend(terms.iter().map(|t| format!("<dt{class}>{t}</dt>")));
+                    if !description.is_empty() {
+                        lines.push("<dd>".to_owned());
+                        lines.extend(description);
+                        lines.push("</dd>".to_owned());
+                    }
+                }
+            }
+        }
+
+        match dlist_style {
+            Some(DlistStyle::Qanda) => lines.push("</ol>".to_owned()),
+            Some(DlistStyle::Horizontal) => lines.push("</table>".to_owned()),
+            None => lines.push("</dl>".to_owned()),
+        }
+    }
+
+    fn table(&mut self, block: &Block, table: &Table) -> String {
+        let metadata = block.metadata();
+        let attribute = |name: &str| metadata.and_then(|m| m.attribute(name));
+        let frame = attribute("frame")
+            .or(self.attribute("table-frame"))
+            .unwrap_or("all");
+        let grid = attribute("grid")
+            .or(self.attribute("table-grid"))
+            .unwrap_or("all");
+        let mut classes = vec![
+            "tableblock".to_owned(),
+            format!("frame-{frame}"),
+            format!("grid-{grid}"),
+        ];
+        if let Some(stripes) = attribute("stripes").or(self.attribute("table-stripes")) {
+            classes.push(format!("stripes-{stripes}"));
+        }
+
+        let is_autowidth = metadata.is_some_and(|m| m.has_option("autowidth"));
+        let width = attribute("width")
+            .and_then(|w| w.trim_end_matches('%').parse::<usize>().ok())
+            .map_or(100, |w| w.clamp(1, 100));
+        let mut style = String::new();
+        if is_autowidth && attribute("width").is_none() {
+            classes.push("fit-content".to_owned());
+        } else if width == 100 {
+            classes.push("stretch".to_owned());
+        } else {
+            style = format!(" style=\"width: {width}%;\"");
+        }
+        if let Some(float) = attribute("float") {
+            classes.push(float.to_owned());
+        }
+        classes.extend(metadata.map_or(&[][..], |m| m.roles()).iter().cloned());
+
+        let mut lines = vec![format!(
+            "<table{} class=\"{}\"{style}>",
+            id_attribute(block.id()),
+            classes.join(" ")
+        )];
+        if let Some(title) = self.captioned_title(block, Caption::Table) {
+            lines.push(format!("<caption class=\"title\">{title}</caption>"));
+        }
+
+        let sections = [
+            ("thead", table.head()),
+            ("tbody", table.body()),
+            ("tfoot", table.foot()),
+        ];
+        if sections.iter().any(|(_, rows)| !rows.is_empty()) {
+            lines.push("<colgroup>".to_owned());
+            let widths = match is_autowidth {
+                true => vec![None; table.columns().len()],
+                false => column_widths(table.columns()),
+            };
+            for width in widths {
+                lines.push(match width {
+                    Some(width) => format!("<col style=\"width: {width}%;\">"),
+                    None => "<col>".to_owned(),
+                });
+            }
+            lines.push("</colgroup>".to_owned());
+        }
+
+        for (tag, rows) in sections {
+            if rows.is_empty() {
+                continue;
+            }
+            lines.push(format!("<{tag}>"));
+            for row in rows {
+                lines.push("<tr>".to_owned());
+                for cell in row {
+                    lines.push(self.table_cell(cell, tag == "thead"));
+                }
+                lines.push("</tr>".to_owned());
+            }
+            lines.push(format!("</{tag}>"));
+        }
+        lines.push("</table>".to_owned());
+
+        lines.join("\n")
+    }
+
+    fn table_cell(&mut self, cell: &TableCell, is_head: bool) -> String {
+        let inlines = cell.inlines().unwrap_or_default();
+        let content = match cell.style() {
+            _ if is_head => inline_html(inlines),
+            CellStyle::Asciidoc => format!(
+                "<div class=\"content\">{}</div>",
+                self.blocks(cell.blocks().unwrap_or_default())
+            ),
+            CellStyle::Literal => format!(
+                "<div class=\"literal\"><pre>{}</pre></div>",
+                escape(&verbatim_text(inlines))
+            ),
+            style => paragraphs(&inline_html(inlines))
+                .into_iter()
+                .map(|p| match style {
+                    CellStyle::Emphasis => format!("<em>{p}</em>"),
+                    CellStyle::Strong => format!("<strong>{p}</strong>"),
+                    CellStyle::Monospaced => format!("<code>{p}</code>"),
+                    _ => p,
+                })
+                .map(|p| format!("<p class=\"tableblock\">{p}</p>"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+
+        let tag = match is_head || cell.style() == CellStyle::Header {
+            true => "th",
+            false => "td",
+        };
+        let halign = match cell.halign() {
+            HorizontalAlignment::Left => "left",
+            HorizontalAlignment::Center => "center",
+            HorizontalAlignment::Right => "right",
+        };
+        let valign = match cell.valign() {
+            VerticalAlignment::Top => "top",
+            VerticalAlignment::Middle => "middle",
+            VerticalAlignment::Bottom => "bottom",
+        };
+        let mut spans = String::new();
+        if cell.colspan() > 1 {
+            spans.push_str(&format!(" colspan=\"{}\"", cell.colspan()));
+        }
+        if cell.rowspan() > 1 {
+            spans.push_str(&format!(" rowspan=\"{}\"", cell.rowspan()));
+        }
+
+        format!(
+            "<{tag} class=\"tableblock halign-{halign} valign-{valign}\"{spans}>{content}</{tag}>"
+        )
+    }
+}
+
+fn attribute_value<'a>(
+    attributes: Option<&'a HashMap<String, String>>,
+    name: &str,
+) -> Option<&'a str> {
+    attributes.and_then(|a| a.get(name)).map(String::as_str)
+}
+
+/// Returns the name of the attribute of the `n`th author, like `author_2`.
+fn author_key(name: &str, n: usize) -> String {
+    match n {
+        1 => name.to_owned(),
+        n => format!("{name}_{n}"),
+    }
+}
+
+fn id_attribute(id: Option<&str>) -> String {
+    id.map_or_else(String::new, |id| format!(" id=\"{id}\""))
+}
+
+/// Returns the roles of a node as classes, each after a space.
+fn roles(metadata: Option<&BlockMetadata>) -> String {
+    metadata
+        .map_or(&[][..], |m| m.roles())
+        .iter()
+        .map(|role| format!(" {role}"))
+        .collect()
+}
+
+fn nowrap_class(is_nowrap: bool) -> &'static str {
+    match is_nowrap {
+        true => " class=\"nowrap\"",
+        false => "",
+    }
+}
+
+/// Returns the attribution of a quote or a verse, from its `attribution`
+/// and `citetitle` attributes or the positional ones.
+fn attribution(metadata: Option<&BlockMetadata>) -> String {
+    let attribute = |name: &str, position: &str| {
+        metadata
+            .and_then(|m| m.attribute(name).or(m.attribute(position)))
+            .map(text_html)
+    };
+    let attribution = attribute("attribution", "2");
+    let citetitle = attribute("citetitle", "3");
+    if attribution.is_none() && citetitle.is_none() {
+        return String::new();
+    }
+
+    let cite = citetitle
+        .as_ref()
+        .map_or_else(String::new, |c| format!("<cite>{c}</cite>"));
+    let attribution = attribution.map_or_else(String::new, |a| {
+        let br = if citetitle.is_some() { "<br>\n" } else { "" };
+        format!("&#8212; {a}{br}")
+    });
+
+    format!("\n<div class=\"attribution\">\n{attribution}{cite}\n</div>")
+}
+
+fn block_break_html(block_break: &BlockBreak) -> String {
+    match block_break.variant() {
+        BlockBreakVariant::Thematic => "<hr>".to_owned(),
+        BlockBreakVariant::Page => "<div style=\"page-break-after: always;\"></div>".to_owned(),
+    }
+}
+
+/// Returns the alternative text of an image without one, from the name of
+/// its file.
+fn default_alt(target: &str) -> String {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    let name = match name.rfind('.') {
+        Some(dot) if dot > 0 => &name[..dot],
+        _ => name,
+    };
+
+    name.replace(['_', '-'], " ")
+}
+
+/// Returns the path of `target` in the directory `dir`, unless it is an
+/// absolute path or a URL.
+fn web_path(target: &str, dir: &str) -> String {
+    if dir.is_empty() || target.starts_with('/') || target.contains("://") {
+        return target.to_owned();
+    }
+
+    format!("{}/{target}", dir.trim_end_matches('/'))
+}
+
+/// Returns the widths of the columns in percent, in the precision of
+/// Asciidoctor, the last column taking what is left by the rounding.
+fn column_widths(columns: &[TableColumn]) -> Vec<Option<String>> {
+    let total: usize = columns.iter().filter_map(TableColumn::width).sum();
+    if total == 0 {
+        return vec![None; columns.len()];
+    }
+
+    let mut widths: Vec<Option<f64>> = columns
+        .iter()
+        .map(|c| {
+            c.width()
+                .map(|w| (w as f64 * 100.0 / total as f64 * 10000.0).trunc() / 10000.0)
+        })
+        .collect();
+    let sum: f64 = widths.iter().flatten().sum();
+    let mut is_last_rounded = false;
+    if sum != 100.0 {
+        if let Some(Some(last)) = widths.last_mut() {
+            *last = ((100.0 - sum + *last) * 10000.0).round() / 10000.0;
+            is_last_rounded = true;
+        }
+    }
+
+    let last = widths.len() - 1;
+    widths
+        .into_iter()
+        .enumerate()
+        .map(|(i, width)| {
+            // A whole width is written as an integer, unless it was rounded.
+            width.map(|w| match w.fract() == 0.0 {
+                true if is_last_rounded && i == last => format!("{w:.1}"),
+                true => format!("{}", w as u64),
+                false => format!("{w}"),
+            })
+        })
+        .collect()
+}
+
+/// Splits `html` into the paragraphs separated by blank lines.
+fn paragraphs(html: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    for line in html.split('\n') {
+        if line.trim().is_empty() {
+            if !lines.is_empty() {
+                paragraphs.push(lines.join("\n"));
+                lines.clear();
+            }
+        } else {
+            lines.push(line);
+        }
+    }
+    if !lines.is_empty() {
+        paragraphs.push(lines.join("\n"));
+    }
+
+    paragraphs
+}
+
+/// Returns the text of the verbatim nodes of a block, as is.
+fn verbatim_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(literal) | Inline::Charref(literal) | Inline::Raw(literal) => {
+                literal.value().to_owned()
+            }
+            Inline::Span(_) => spans_html(std::slice::from_ref(inline)),
+        })
+        .collect()
+}
+
+/// Converts `inlines`, where a line ending in ` +` is followed by a line break.
+fn inline_html(inlines: &[Inline]) -> String {
+    line_breaks(&spans_html(inlines), false)
+}
+
+fn spans_html(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Span(span) => {
+                let tag = match span.variant() {
+                    SpanVariant::Strong => "strong",
+                    SpanVariant::Emphasis => "em",
+                    SpanVariant::Code => "code",
+                    SpanVariant::Mark => "mark",
+                    SpanVariant::Superscript => "sup",
+                    SpanVariant::Subscript => "sub",
+                };
+                format!("<{tag}>{}</{tag}>", spans_html(span.inlines()))
+            }
+            Inline::Text(text) => text_html(text.value()),
+            Inline::Charref(literal) | Inline::Raw(literal) => literal.value().to_owned(),
+        })
+        .collect()
+}
+
+/// Adds a line break to each line of `html` ending in ` +`, or to every line
+/// but the last when `is_hard`.
+fn line_breaks(html: &str, is_hard: bool) -> String {
+    let lines: Vec<&str> = html.split('\n').collect();
+    let last = lines.len() - 1;
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match (line.strip_suffix(" +"), is_hard) {
+            (text, true) if i < last => format!("{}<br>", text.unwrap_or(line)),
+            (Some(text), false) => format!("{text}<br>"),
+            _ => (*line).to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escapes the special characters of `text` and makes the replacements of
+/// Asciidoctor, like `(C)`, `--` and `...`, which a backslash keeps as is.
+fn text_html(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut html = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let is_escaped = chars[i] == '\\';
+        let start = i + usize::from(is_escaped);
+        let previous = i.checked_sub(1).map(|p| chars[p]);
+        if let Some((length, replacement, is_spaced)) =
+            replacement(&chars[start..], previous, is_escaped)
+        {
+            if is_escaped {
+                html.push_str(&escape(
+                    &chars[start..start + length].iter().collect::<String>(),
+                ));
+            } else {
+                // A spaced dash replaces the spaces around it.
+                if is_spaced && matches!(previous, Some(' ' | '\n')) {
+                    html.pop();
+                }
+                html.push_str(replacement);
+            }
+            i = start + length;
+            continue;
+        }
+
+        match chars[i] {
+            '&' if entity_length(&chars[i..]).is_some() => html.push('&'),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            c => html.push(c),
+        }
+        i += 1;
+    }
+
+    html
+}
+
+/// Returns the length of the replaced characters at the start of `chars`,
+/// their replacement, and whether it is a spaced dash.
+fn replacement(
+    chars: &[char],
+    previous: Option<char>,
+    is_escaped: bool,
+) -> Option<(usize, &'static str, bool)> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    match chars {
+        ['(', 'C', ')', ..] => Some((3, "&#169;", false)),
+        ['(', 'R', ')', ..] => Some((3, "&#174;", false)),
+        ['(', 'T', 'M', ')', ..] => Some((4, "&#8482;", false)),
+        ['-', '-', ..] => {
+            let next = chars.get(2).copied();
+            let is_spaced = is_escaped || matches!(previous, None | Some(' ' | '\n'));
+            if is_spaced && matches!(next, None | Some(' ' | '\n')) {
+                Some((
+                    2 + usize::from(next.is_some()),
+                    "&#8201;&#8212;&#8201;",
+                    true,
+                ))
+            } else if is_word(previous) && is_word(next) {
+                Some((2, "&#8212;&#8203;", false))
+            } else {
+                None
+            }
+        }
+        ['.', '.', '.', ..] => Some((3, "&#8230;&#8203;", false)),
+        ['`', '\'', ..] => Some((2, "&#8217;", false)),
+        ['\'', next, ..] if previous.is_some_and(char::is_alphanumeric) && next.is_alphabetic() => {
+            Some((1, "&#8217;", false))
+        }
+        ['-', '>', ..] => Some((2, "&#8594;", false)),
+        ['=', '>', ..] => Some((2, "&#8658;", false)),
+        ['<', '-', ..] => Some((2, "&#8592;", false)),
+        ['<', '=', ..] => Some((2, "&#8656;", false)),
+        _ => None,
+    }
+}
+
+/// Returns the length of the character reference at the start of `chars`,
+/// like `&amp;`, `&#169;` or `&#x2014;`.
+fn entity_length(chars: &[char]) -> Option<usize> {
+    let count =
+        |from: usize, f: fn(&char) -> bool| chars.iter().skip(from).take_while(|c| f(c)).count();
+
+    let end = match (chars.get(1), chars.get(2)) {
+        (Some('#'), Some('x')) => {
+            let digits = count(3, char::is_ascii_hexdigit);
+            (2..=5).contains(&digits).then_some(3 + digits)
+        }
+        (Some('#'), _) => {
+            let digits = count(2, char::is_ascii_digit);
+            (2..=6).contains(&digits).then_some(2 + digits)
+        }
+        _ => {
+            let letters = count(1, char::is_ascii_alphabetic);
+            let digits = count(1 + letters, char::is_ascii_digit);
+            (letters >= 2 && digits <= 2).then_some(1 + letters + digits)
+        }
+    }?;
+
+    (chars.get(end) == Some(&';')).then_some(end + 1)
+}
+
+/// Escapes the characters which HTML reads as markup.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escapes `value` to be quoted as the value of an HTML attribute.
+fn escape_attribute(value: &str) -> String {
+    escape(value).replace('"', "&quot;")
+}
+
+/// Removes the tags of `html`, for a title in plain text.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut is_in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => is_in_tag = true,
+            '>' if is_in_tag => is_in_tag = false,
+            c if !is_in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parser;
+
+    fn html(text: &str) -> String {
+        Parser::new(text).parse_to_asg().unwrap().to_html()
+    }
+
+    #[test]
+    fn sections() {
+        let text = "= Title\n\nPreamble.\n\n== First\n\n=== Sub & Co\n\nText.\n\n== First\n";
+        let expected = "<div id=\"preamble\">\n<div class=\"sectionbody\">\n<div class=\"paragraph\">\n<p>Preamble.</p>\n</div>\n</div>\n</div>\n<div class=\"sect1\">\n<h2 id=\"_first\">First</h2>\n<div class=\"sectionbody\">\n<div class=\"sect2\">\n<h3 id=\"_sub_co\">Sub &amp; Co</h3>\n<div class=\"paragraph\">\n<p>Text.</p>\n</div>\n</div>\n</div>\n</div>\n<div class=\"sect1\">\n<h2 id=\"_first_2\">First</h2>\n<div class=\"sectionbody\">\n\n</div>\n</div>";
+
+        assert_eq!(html(text), expected);
+    }
+
+    #[test]
+    fn section_numbers_and_toc() {
+        let text = "= Title\n:toc:\n:sectnums:\n\n== One\n\n=== Two\n\n== Three\n";
+        let html = html(text);
+
+        assert!(html.starts_with("<div id=\"toc\" class=\"toc\">\n<div id=\"toctitle\">Table of Contents</div>\n<ul class=\"sectlevel1\">\n<li><a href=\"#_one\">1. One</a>\n<ul class=\"sectlevel2\">\n<li><a href=\"#_two\">1.1. Two</a></li>\n</ul>\n</li>\n<li><a href=\"#_three\">2. Three</a></li>\n</ul>\n</div>\n"));
+        assert!(html.contains("<h3 id=\"_two\">1.1. Two</h3>"));
+    }
+
+    #[test]
+    fn generated_ids() {
+        let text = "[#_a_b]\n== Other\n\n== A.B!\n\n== A -- B\n\n== Don't <stop> &amp; go...\n";
+        let html = html(text);
+
+        assert!(html.contains("<h2 id=\"_a_b_2\">"));
+        assert!(html.contains("<h2 id=\"_ab\">"));
+        assert!(html.contains("<h2 id=\"_dont_stop_go\">"));
+    }
+
+    #[test]
+    fn lists() {
+        let text = "* one\n* [x] two\n\n//\n\n. first\n. second\n\n//\n\nterm:: def\n";
+        let expected = "<div class=\"ulist checklist\">\n<ul class=\"checklist\">\n<li>\n<p>one</p>\n</li>\n<li>\n<p>&#10003; two</p>\n</li>\n</ul>\n</div>\n<div class=\"olist arabic\">\n<ol class=\"arabic\">\n<li>\n<p>first</p>\n</li>\n<li>\n<p>second</p>\n</li>\n</ol>\n</div>\n<div class=\"dlist\">\n<dl>\n<dt class=\"hdlist1\">term</dt>\n<dd>\n<p>def</p>\n</dd>\n</dl>\n</div>";
+
+        assert_eq!(html(text), expected);
+    }
+
+    #[test]
+    fn blocks() {
+        let text = "NOTE: Be careful.\n\n.Title\n[source,rust]\n----\nfn main() {} // <1>\n----\n<1> The main\n\n'''\n";
+        let expected = "<div class=\"admonitionblock note\">\n<table>\n<tr>\n<td class=\"icon\">\n<div class=\"title\">Note</div>\n</td>\n<td class=\"content\">\nBe careful.\n</td>\n</tr>\n</table>\n</div>\n<div class=\"listingblock\">\n<div class=\"title\">Title</div>\n<div class=\"content\">\n<pre class=\"highlight\"><code class=\"language-rust\" data-lang=\"rust\">fn main() {} <b class=\"conum\">(1)</b></code></pre>\n</div>\n</div>\n<div class=\"colist arabic\">\n<ol>\n<li>\n<p>The main</p>\n</li>\n</ol>\n</div>\n<hr>";
+
+        assert_eq!(html(text), expected);
+    }
+
+    #[test]
+    fn callout_comment_prefixes() {
+        let text = "----\na # <1>\nb -- <2>\nc ;; <3> <4>\nd <!--5-->\n----\n<1> A\n<2> B\n<3> C\n<4> D\n<5> E\n";
+
+        assert!(html(text).contains("<pre>a <b class=\"conum\">(1)</b>\nb <b class=\"conum\">(2)</b>\nc <b class=\"conum\">(3)</b> <b class=\"conum\">(4)</b>\nd &lt;!--<b class=\"conum\">(5)</b>--&gt;</pre>"));
+    }
+
+    #[test]
+    fn table() {
+        let text = ".Data\n[cols=\"1,2\"]\n|===\n|A |B\n\n|1 |2\n|===\n";
+        let expected = "<table class=\"tableblock frame-all grid-all stretch\">\n<caption class=\"title\">Table 1. Data</caption>\n<colgroup>\n<col style=\"width: 33.3333%;\">\n<col style=\"width: 66.6667%;\">\n</colgroup>\n<thead>\n<tr>\n<th class=\"tableblock halign-left valign-top\">A</th>\n<th class=\"tableblock halign-left valign-top\">B</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td class=\"tableblock halign-left valign-top\"><p class=\"tableblock\">1</p></td>\n<td class=\"tableblock halign-left valign-top\"><p class=\"tableblock\">2</p></td>\n</tr>\n</tbody>\n</table>";
+
+        assert_eq!(html(text), expected);
+    }
+
+    #[test]
+    fn replacements() {
+        let text = "(C) it's -- ok... \\(C) a--b -> <b> &amp; &\n";
+
+        assert_eq!(
+            html(text),
+            "<div class=\"paragraph\">\n<p>&#169; it&#8217;s&#8201;&#8212;&#8201;ok&#8230;&#8203; (C) a&#8212;&#8203;b &#8594; &lt;b&gt; &amp; &amp;</p>\n</div>"
+        );
+    }
+
+    #[test]
+    fn attribute_values() {
+        let text = ":b: <i>x</i>\n\n{b} & <b> {lt}script{gt} {amp}\n";
+
+        assert_eq!(
+            html(text),
+            "<div class=\"paragraph\">\n<p>&lt;i&gt;x&lt;/i&gt; &amp; &lt;b&gt; &lt;script&gt; &amp;</p>\n</div>"
+        );
+    }
+
+    #[test]
+    fn standalone() {
+        let text = "= Title\nJane Doe <jane@example.org>\nv1.0, 2024-01-01\n\nText.\n";
+        let expected = "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n<meta http-equiv=\"X-UA-Compatible\" content=\"IE=edge\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n<meta name=\"generator\" content=\"asciidoc2rs ".to_owned()
+            + env!("CARGO_PKG_VERSION")
+            + "\">\n<meta name=\"author\" content=\"Jane Doe\">\n<title>Title</title>\n<link rel=\"stylesheet\" href=\"https://fonts.googleapis.com/css?family=Open+Sans:300,300italic,400,400italic,600,600italic%7CNoto+Serif:400,400italic,700,700italic%7CDroid+Sans+Mono:400,700\">\n<link rel=\"stylesheet\" href=\"./asciidoctor.css\">\n</head>\n<body class=\"article\">\n<div id=\"header\">\n<h1>Title</h1>\n<div class=\"details\">\n<span id=\"author\" class=\"author\">Jane Doe</span><br>\n<span id=\"email\" class=\"email\"><a href=\"mailto:jane@example.org\">jane@example.org</a></span><br>\n<span id=\"revnumber\">version 1.0,</span>\n<span id=\"revdate\">2024-01-01</span>\n</div>\n</div>\n<div id=\"content\">\n<div class=\"paragraph\">\n<p>Text.</p>\n</div>\n</div>\n<div id=\"footer\">\n<div id=\"footer-text\">\nVersion 1.0<br>\n</div>\n</div>\n</body>\n</html>";
+        let doc = Parser::new(text).parse_to_asg().unwrap();
+
+        assert_eq!(doc.to_standalone_html(), expected);
+    }
+}
@@ -1,27 +1,45 @@
 #![doc = include_str!("../README.md")]
 
-mod asg;
+pub mod asg;
+mod error;
+mod html;
+mod reader;
+mod writer;
+
+pub use error::{Diagnostic, Error, Severity};
+pub use reader::{FileResolver, IncludeFile, IncludeResolver, MemoryResolver};
 
 use asg::block::Document;
-use asg::Inline;
+use asg::{Attributes, Inline, LocationBoundary};
+use reader::{Reader, MAX_INCLUDE_DEPTH};
 
-use std::error::Error;
+use std::convert::Infallible;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Doctype {
+    #[default]
     Article,
     Book,
     Manpage,
 }
-impl Default for Doctype {
-    fn default() -> Self {
-        Self::Article
+impl Doctype {
+    /// Returns the name of the doctype, as in the `doctype` attribute.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Article => "article",
+            Self::Book => "book",
+            Self::Manpage => "manpage",
+        }
     }
 }
 
 pub struct Parser<'input> {
     text: &'input str,
     doctype: Doctype,
+    source: Option<String>,
+    include_resolver: Option<Box<dyn IncludeResolver + 'input>>,
+    max_include_depth: usize,
+    is_strict: bool,
 }
 
 impl<'input> Parser<'input> {
@@ -30,26 +48,280 @@ impl<'input> Parser<'input> {
     }
 
     pub fn new_with_doctype(text: &'input str, doctype: Doctype) -> Self {
-        Self { text, doctype }
+        Self {
+            text,
+            doctype,
+            source: None,
+            include_resolver: None,
+            max_include_depth: MAX_INCLUDE_DEPTH,
+            is_strict: false,
+        }
+    }
+
+    /// Sets the path of the parsed text, which is recorded in the location of every node.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_owned());
+        self
+    }
+
+    /// Sets the resolver reading the files of include directives, which are
+    /// kept as text without one.
+    pub fn with_include_resolver(mut self, resolver: impl IncludeResolver + 'input) -> Self {
+        self.include_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Sets the limit of nested include directives, 64 by default.
+    pub fn with_max_include_depth(mut self, depth: usize) -> Self {
+        self.max_include_depth = depth;
+        self
     }
 
-    fn parse_inline(self) -> Vec<Inline> {
-        Inline::new(self.text)
+    /// Sets whether the warnings are errors, which stop
+    /// [`parse_to_asg`](Self::parse_to_asg) like the other errors.
+    pub fn with_strict(mut self, is_strict: bool) -> Self {
+        self.is_strict = is_strict;
+        self
     }
 
-    pub fn parse_to_asg(self) -> Result<Document, Box<dyn Error>> {
+    /// Parses the text as the inlines of a single paragraph, as the TCK does for
+    /// its inline tests.
+    pub fn parse_inline(self) -> Vec<Inline> {
+        Inline::new(
+            self.text,
+            &LocationBoundary::new(1, 1, self.source),
+            &mut Attributes::new(),
+        )
+    }
+
+    /// Parses the document, stopping at the first error.
+    pub fn parse_to_asg(self) -> Result<Document, Error> {
+        let is_strict = self.is_strict;
+
+        self.parse(|error, _| match error {
+            Error::Warning { .. } if !is_strict => Ok(()),
+            error => Err(error),
+        })
+    }
+
+    /// Parses the document, recovering from the errors like Asciidoctor does,
+    /// and returns it with the problems in the order they are found: the
+    /// callouts are only checked against their lists once the whole document
+    /// is read, so their warnings come last.
+    pub fn parse_with_diagnostics(self) -> (Document, Vec<Diagnostic>) {
+        let is_strict = self.is_strict;
+        let mut diagnostics = Vec::new();
+        let result = self.parse(|error, source| {
+            diagnostics.push(Diagnostic::new(&error, source, is_strict));
+            Ok::<_, Infallible>(())
+        });
+        let Ok(doc) = result;
+
+        (doc, diagnostics)
+    }
+
+    /// Parses the document, giving every error and warning to `report` with
+    /// the file it was found in, and `report` decides whether the parsing stops.
+    fn parse<E>(
+        self,
+        mut report: impl FnMut(Error, Option<&str>) -> Result<(), E>,
+    ) -> Result<Document, E> {
         let mut doc = Document::new(self.doctype);
-        for line in self.text.lines() {
-            doc.push(line)?;
-        }
-        doc.end();
+        doc.set_source(self.source.clone());
+        let mut reader = Reader::new(
+            self.text,
+            self.source,
+            self.include_resolver.as_deref(),
+            self.max_include_depth,
+        );
+        let mut reported = 0;
+        loop {
+            let line = reader.next_line(doc.context_mut());
+            let is_end = line.is_none();
+            let result = match line {
+                Some(line) => doc.push_source_line(line),
+                None => {
+                    doc.end();
+                    Ok(())
+                }
+            };
 
-        Ok(doc)
+            for warning in &doc.warnings()[reported..] {
+                let position = &warning.position;
+                let error = Error::Warning {
+                    message: warning.message.clone(),
+                    source: position.source().map(str::to_owned),
+                    line: position.line(),
+                    column: position.column(),
+                };
+                report(error, position.source())?;
+            }
+            reported = doc.warnings().len();
+            if let Err(error) = result {
+                // The other errors are about the line just parsed.
+                report(error, doc.context_mut().source.as_deref())?;
+            }
+
+            if is_end {
+                return Ok(doc);
+            }
+        }
     }
 
-    pub fn parse_from_asg(self) -> Result<Document, Box<dyn Error>> {
+    pub fn parse_from_asg(self) -> Result<Document, Error> {
         let doc = serde_json::from_str(self.text)?;
 
         Ok(doc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str =
+        "= Title\nAuthor <bad\n\n= Level 0\n\n== Section\n\n==== Skipped\n\n----\ncode <1>\n----";
+
+    #[test]
+    fn diagnostics() {
+        let (doc, diagnostics) = Parser::new(TEXT).parse_with_diagnostics();
+
+        let blocks = &serde_json::to_value(&doc).unwrap()["blocks"];
+        assert_eq!("paragraph", blocks[0]["name"]);
+        assert_eq!("section", blocks[1]["name"]);
+        assert_eq!(
+            vec![
+                "line 2, column 1: error: invalid author format",
+                "line 4, column 1: error: level 0 sections can only be used when doctype is book",
+                "line 8, column 1: error: cannot skip section level: expected level 2, got 3",
+                "line 11, column 6: warning: no callout list item found for <1>",
+            ],
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn navigable_asg() {
+        use asg::{Block, BlockLeaf, Inline, ListVariant, SectionBody, SpanVariant};
+
+        let doc = Parser::new(
+            "= Title\n:product: Alpha\n\n[#intro.lead]\nAbout *{product}*.\n\n== Usage\n\n* [x] one\n* two",
+        )
+        .parse_to_asg()
+        .unwrap();
+
+        let header = doc.header().unwrap();
+        assert!(matches!(&header.title()[0], Inline::Text(t) if t.value() == "Title"));
+        assert_eq!(
+            Some("Alpha"),
+            doc.attributes().unwrap().get("product").map(|v| v.as_str())
+        );
+
+        let [SectionBody::Block(paragraph), SectionBody::Section(usage)] = doc.blocks() else {
+            panic!("expected a paragraph and a section");
+        };
+        assert_eq!(Some("intro"), paragraph.id());
+        assert_eq!(vec!["lead"], paragraph.metadata().unwrap().roles());
+        let Block::BlockLeaf(BlockLeaf::Paragraph(body)) = paragraph else {
+            panic!("expected a paragraph");
+        };
+        let Inline::Span(strong) = &body.inlines()[1] else {
+            panic!("expected a span");
+        };
+        assert_eq!(SpanVariant::Strong, strong.variant());
+        assert_eq!(5, strong.location().unwrap().start().line());
+
+        assert_eq!(1, usage.level());
+        let [SectionBody::Block(Block::AnyList(list))] = usage.blocks() else {
+            panic!("expected a list");
+        };
+        assert_eq!(Some(ListVariant::Unordered), list.variant());
+        assert_eq!(
+            vec![Some(true), None],
+            list.items().iter().map(|i| i.checked()).collect::<Vec<_>>()
+        );
+        let end = list.location().unwrap().end();
+        assert_eq!((10, 5), (end.line(), end.column()));
+    }
+
+    #[test]
+    fn inline_input() {
+        use asg::{Inline, SpanVariant};
+
+        let inlines = Parser::new("a *b*\nc").parse_inline();
+
+        let [Inline::Text(a), Inline::Span(b), Inline::Text(c)] = inlines.as_slice() else {
+            panic!("expected a text, a span and a text");
+        };
+        assert_eq!(("a ", "\nc"), (a.value(), c.value()));
+        assert_eq!(SpanVariant::Strong, b.variant());
+        let end = c.location().unwrap().end();
+        assert_eq!((2, 1), (end.line(), end.column()));
+    }
+
+    #[test]
+    fn missing_attribute_warning() {
+        let (doc, diagnostics) =
+            Parser::new(":attribute-missing: warn\n\n{nope}\n").parse_with_diagnostics();
+
+        assert_eq!(
+            vec!["line 3, column 1: warning: skipping reference to missing attribute: nope"],
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        );
+        let blocks = &serde_json::to_value(&doc).unwrap()["blocks"];
+        assert_eq!("{nope}", blocks[0]["inlines"][0]["value"]);
+    }
+
+    #[test]
+    fn included_warning_source() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("code.adoc", "text\n\n----\ncode <1>\n----");
+
+        let (_, diagnostics) =
+            Parser::new("= Title\n\ninclude::code.adoc[]\n\n----\nmain <1>\n----")
+                .with_source("main.adoc")
+                .with_include_resolver(resolver)
+                .parse_with_diagnostics();
+        assert_eq!(
+            vec![
+                "code.adoc: line 4, column 6: warning: no callout list item found for <1>",
+                "main.adoc: line 6, column 6: warning: no callout list item found for <1>",
+            ],
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn strict_warnings() {
+        let text = "----\ncode <1>\n----";
+        assert!(Parser::new(text).parse_to_asg().is_ok());
+        assert_eq!(
+            Err(Error::Warning {
+                message: "no callout list item found for <1>".to_owned(),
+                source: None,
+                line: 2,
+                column: 6
+            }),
+            Parser::new(text)
+                .with_strict(true)
+                .parse_to_asg()
+                .map(|_| ())
+        );
+
+        let (_, diagnostics) = Parser::new(TEXT).with_strict(true).parse_with_diagnostics();
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+        assert_eq!(
+            Err(Error::InvalidAuthor { line: 2, column: 1 }),
+            Parser::new(TEXT).parse_to_asg().map(|_| ())
+        );
+    }
+}
@@ -62,14 +62,16 @@ fn run_with_arguments(args: &[String]) -> Result<String, Box<dyn Error>> {
 
 fn run_for_tck() -> Result<String, Box<dyn Error>> {
     match read_stdin() {
-        Ok(input) => {
-            let (Stdin::Block(content) | Stdin::Inline(content)) =
-                serde_json::from_str(input.as_str())?;
-            let InputBody { contents: text, .. } = content;
-            let parser = Parser::new(text.as_str());
-            let doc = parser.parse_to_asg()?;
-            Ok((serde_json::to_string(&doc)?).to_string())
-        }
+        Ok(input) => match serde_json::from_str(input.as_str())? {
+            Stdin::Block(InputBody { contents: text, .. }) => {
+                let doc = Parser::new(text.as_str()).parse_to_asg()?;
+                Ok(serde_json::to_string(&doc)?)
+            }
+            Stdin::Inline(InputBody { contents: text, .. }) => {
+                let inlines = Parser::new(text.as_str()).parse_inline();
+                Ok(serde_json::to_string(&inlines)?)
+            }
+        },
         err => err,
     }
 }
@@ -0,0 +1,455 @@
+mod conditional;
+mod include;
+
+pub use include::{FileResolver, IncludeFile, IncludeResolver, MemoryResolver};
+
+use conditional::{ConditionalDirective, ConditionalKeyword};
+use include::{decode, IncludeDirective};
+
+use crate::asg::block::{Context, Warning};
+use crate::asg::LocationBoundary;
+
+/// The default limit of nested include directives.
+pub(crate) const MAX_INCLUDE_DEPTH: usize = 64;
+
+/// A line read by the preprocessor, with the position it comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SourceLine {
+    pub(crate) text: String,
+    pub(crate) line_number: usize,
+    pub(crate) source: Option<String>,
+    /// The offset added to the level of a section heading, set by the
+    /// `leveloffset` attribute of an include directive.
+    pub(crate) level_offset: isize,
+}
+
+/// The lines left to read from the document or from an included file.
+struct Frame {
+    lines: std::vec::IntoIter<(usize, String)>,
+    source: Option<String>,
+    level_offset: isize,
+}
+
+/// What an include directive is replaced with.
+enum Include {
+    /// The lines of the file are read next.
+    Lines(Frame),
+    /// The directive line is kept as text.
+    Keep,
+    /// The directive line is dropped.
+    Drop,
+    /// The directive line is replaced with a line of text.
+    Replace(String),
+}
+
+/// An open block conditional directive, which `endif` closes.
+struct Conditional {
+    directive: ConditionalDirective,
+    position: LocationBoundary,
+    /// Whether the lines up to `endif` are skipped.
+    is_skipping: bool,
+}
+
+/// Reads the lines of a document, replacing the preprocessor directives as
+/// they are reached, so that they see the attributes defined before them.
+pub(crate) struct Reader<'a> {
+    frames: Vec<Frame>,
+    conditionals: Vec<Conditional>,
+    resolver: Option<&'a dyn IncludeResolver>,
+    max_include_depth: usize,
+    /// The path of the document as the resolver spells it, to find an include
+    /// of the document itself whatever the path it is given as.
+    root_path: Option<String>,
+}
+impl<'a> Reader<'a> {
+    pub(crate) fn new(
+        text: &str,
+        source: Option<String>,
+        resolver: Option<&'a dyn IncludeResolver>,
+        max_include_depth: usize,
+    ) -> Self {
+        let lines: Vec<_> = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.to_owned()))
+            .collect();
+        let root_path = resolver
+            .zip(source.as_deref())
+            .and_then(|(resolver, source)| resolver.resolve(source, None).ok())
+            .map(|file| file.path);
+
+        Self {
+            frames: vec![Frame {
+                lines: lines.into_iter(),
+                source,
+                level_offset: 0,
+            }],
+            conditionals: Vec::with_capacity(0),
+            resolver,
+            max_include_depth,
+            root_path,
+        }
+    }
+
+    pub(crate) fn next_line(&mut self, context: &mut Context) -> Option<SourceLine> {
+        loop {
+            let Some(frame) = self.frames.last_mut() else {
+                self.end(context);
+                return None;
+            };
+            let Some((line_number, text)) = frame.lines.next() else {
+                self.frames.pop();
+                continue;
+            };
+            let mut line = SourceLine {
+                text,
+                line_number,
+                source: frame.source.clone(),
+                level_offset: frame.level_offset,
+            };
+
+            if let Some(directive) = ConditionalDirective::parse(&line.text) {
+                if let Some(text) = self.conditional(directive, &line, context) {
+                    line.text = text;
+                    return Some(line);
+                }
+                continue;
+            }
+
+            if self.is_skipping() {
+                continue;
+            }
+
+            // An escaped directive is kept as text, without the backslash.
+            if let Some(escaped) = line.text.strip_prefix('\\') {
+                if IncludeDirective::parse(escaped).is_some()
+                    || ConditionalDirective::parse(escaped).is_some()
+                {
+                    line.text = escaped.to_owned();
+                    return Some(line);
+                }
+            }
+
+            let Some(directive) = IncludeDirective::parse(&line.text) else {
+                return Some(line);
+            };
+            match self.include(&directive, &line, context) {
+                Include::Lines(frame) => self.frames.push(frame),
+                Include::Keep => return Some(line),
+                Include::Drop => {}
+                Include::Replace(text) => {
+                    line.text = text;
+                    return Some(line);
+                }
+            }
+        }
+    }
+
+    /// Returns whether the lines are skipped by a conditional directive.
+    fn is_skipping(&self) -> bool {
+        self.conditionals.last().is_some_and(|c| c.is_skipping)
+    }
+
+    /// Opens or closes a conditional directive, returning the content of a
+    /// single-line directive whose condition holds.
+    fn conditional(
+        &mut self,
+        directive: ConditionalDirective,
+        line: &SourceLine,
+        context: &mut Context,
+    ) -> Option<String> {
+        let position = LocationBoundary::new(line.line_number, 1, line.source.clone());
+        let mut warn = |message: String| {
+            context.warnings.push(Warning {
+                position: position.clone(),
+                message,
+            })
+        };
+
+        if directive.keyword == ConditionalKeyword::Endif {
+            let Some(open) = self.conditionals.last() else {
+                warn(format!(
+                    "unmatched preprocessor directive: {}",
+                    directive.source()
+                ));
+                return None;
+            };
+            if !directive.target.is_empty() && directive.target != open.directive.target {
+                warn(format!(
+                    "mismatched preprocessor directive: {}, expected endif::{}[]",
+                    directive.source(),
+                    open.directive.target
+                ));
+                return None;
+            }
+            self.conditionals.pop();
+
+            return None;
+        }
+
+        if self.is_skipping() {
+            // The nested directives are only tracked to find their `endif`.
+            if !directive.is_single_line() {
+                self.conditionals.push(Conditional {
+                    directive,
+                    position,
+                    is_skipping: true,
+                });
+            }
+            return None;
+        }
+
+        let Some(holds) = directive.evaluate(&mut context.attributes) else {
+            warn(format!(
+                "malformed preprocessor directive - invalid expression: {}",
+                directive.source()
+            ));
+            return None;
+        };
+        if directive.is_single_line() {
+            return holds.then_some(directive.text).flatten();
+        }
+        self.conditionals.push(Conditional {
+            directive,
+            position,
+            is_skipping: !holds,
+        });
+
+        None
+    }
+
+    /// Reports the conditional directives left open at the end of the document.
+    fn end(&mut self, context: &mut Context) {
+        for conditional in self.conditionals.drain(..) {
+            context.warnings.push(Warning {
+                position: conditional.position,
+                message: format!(
+                    "detected unterminated preprocessor conditional directive: {}",
+                    conditional.directive.source()
+                ),
+            });
+        }
+    }
+
+    fn include(
+        &self,
+        directive: &IncludeDirective,
+        line: &SourceLine,
+        context: &mut Context,
+    ) -> Include {
+        let Some(resolver) = self.resolver else {
+            return Include::Keep;
+        };
+        let mut warn = |message: String| {
+            context.warnings.push(Warning {
+                position: LocationBoundary::new(line.line_number, 1, line.source.clone()),
+                message,
+            })
+        };
+
+        if self.frames.len() > self.max_include_depth {
+            warn(format!(
+                "maximum include depth of {} exceeded",
+                self.max_include_depth
+            ));
+            return Include::Keep;
+        }
+
+        let target = context.attributes.substitute(&directive.target);
+        if target.is_empty() {
+            return Include::Drop;
+        }
+
+        let file = match resolver.resolve(&target, line.source.as_deref()) {
+            Ok(file) => file,
+            Err(_) if directive.is_optional() => return Include::Drop,
+            Err(error) => {
+                warn(format!("include file not readable: {}: {}", target, error));
+                return Include::Replace(format!(
+                    "Unresolved directive in {} - {}",
+                    line.source.as_deref().unwrap_or("<stdin>"),
+                    directive.source()
+                ));
+            }
+        };
+
+        // The included files are spelled by the resolver, unlike the document.
+        if self.root_path.as_ref() == Some(&file.path)
+            || self.frames[1..]
+                .iter()
+                .any(|f| f.source.as_ref() == Some(&file.path))
+        {
+            warn(format!("include cycle detected: {}", file.path));
+            return Include::Drop;
+        }
+
+        let text = decode(&file.contents, directive.encoding()).unwrap_or_else(|| {
+            warn(format!(
+                "unknown encoding of include file: {}",
+                directive.encoding().unwrap_or_default()
+            ));
+            String::from_utf8_lossy(&file.contents).into_owned()
+        });
+        let (lines, messages) = directive.select_lines(&text, &file.path);
+        messages.into_iter().for_each(&mut warn);
+
+        Include::Lines(Frame {
+            lines: lines.into_iter(),
+            source: Some(file.path),
+            level_offset: directive.level_offset(line.level_offset),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str, resolver: &MemoryResolver) -> (Vec<SourceLine>, Vec<Warning>) {
+        let mut context = Context::default();
+        let mut reader = Reader::new(text, None, Some(resolver), 3);
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line(&mut context) {
+            lines.push(line);
+        }
+
+        (lines, context.warnings)
+    }
+
+    #[test]
+    fn include_of_the_document() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("main.adoc", "x\ninclude::main.adoc[]");
+
+        for source in ["./main.adoc", "/main.adoc", "dir/../main.adoc"] {
+            let mut context = Context::default();
+            let mut reader = Reader::new(
+                "include::main.adoc[]",
+                Some(source.to_owned()),
+                Some(&resolver),
+                3,
+            );
+            let mut lines = Vec::new();
+            while let Some(line) = reader.next_line(&mut context) {
+                lines.push(line.text);
+            }
+
+            assert_eq!(Vec::<String>::new(), lines, "{source}");
+            assert_eq!(
+                vec!["include cycle detected: main.adoc".to_owned()],
+                context
+                    .warnings
+                    .into_iter()
+                    .map(|w| w.message)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn include_lines() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("a.adoc", "== A\ninclude::b/b.adoc[leveloffset=+1]");
+        resolver.insert("b/b.adoc", "== B\n\\include::c.adoc[]");
+
+        let (lines, warnings) = read("before\ninclude::a.adoc[]\nafter", &resolver);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            vec![
+                ("before", 1, None, 0),
+                ("== A", 1, Some("a.adoc"), 0),
+                ("== B", 1, Some("b/b.adoc"), 1),
+                ("include::c.adoc[]", 2, Some("b/b.adoc"), 1),
+                ("after", 3, None, 0),
+            ],
+            lines
+                .iter()
+                .map(|l| (
+                    l.text.as_str(),
+                    l.line_number,
+                    l.source.as_deref(),
+                    l.level_offset
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn unresolved_includes() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("loop.adoc", "x\ninclude::loop.adoc[]");
+        resolver.insert("deep.adoc", "include::deeper.adoc[]");
+        resolver.insert("deeper.adoc", "include::deepest.adoc[]");
+        resolver.insert("deepest.adoc", "include::deep.adoc[]");
+
+        let (lines, warnings) = read(
+            ":dir: missing\ninclude::{dir}.adoc[]\ninclude::optional.adoc[opts=optional]\ninclude::loop.adoc[]\ninclude::deep.adoc[]",
+            &resolver,
+        );
+        assert_eq!(
+            vec![
+                ":dir: missing",
+                "Unresolved directive in <stdin> - include::{dir}.adoc[]",
+                "x",
+                "include::deep.adoc[]",
+            ],
+            lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                (
+                    2,
+                    "include file not readable: {dir}.adoc: no such file: {dir}.adoc".to_owned()
+                ),
+                (2, "include cycle detected: loop.adoc".to_owned()),
+                (1, "maximum include depth of 3 exceeded".to_owned()),
+            ],
+            warnings
+                .into_iter()
+                .map(|w| (w.position.line(), w.message))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn conditional_lines() {
+        let text = ":flag:\nifdef::flag[]\na\nifndef::flag[]\nb\nifeval::[1 > 2]\nc\nendif::[]\nendif::flag[]\nendif::flag[]\nifdef::flag,other[d]\nifdef::flag+other[e]\n\\ifdef::flag[]\nifeval::[1 2]\nendif::[]\nifndef::flag[]";
+        let mut context = Context::default();
+        context.attributes.set("flag", "");
+        let mut reader = Reader::new(text, None, None, MAX_INCLUDE_DEPTH);
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line(&mut context) {
+            lines.push((line.line_number, line.text));
+        }
+
+        assert_eq!(
+            vec![
+                (1, ":flag:".to_owned()),
+                (3, "a".to_owned()),
+                (11, "d".to_owned()),
+                (13, "ifdef::flag[]".to_owned()),
+            ],
+            lines
+        );
+        assert_eq!(
+            vec![
+                (
+                    14,
+                    "malformed preprocessor directive - invalid expression: ifeval::[1 2]"
+                        .to_owned()
+                ),
+                (15, "unmatched preprocessor directive: endif::[]".to_owned()),
+                (
+                    16,
+                    "detected unterminated preprocessor conditional directive: ifndef::flag[]"
+                        .to_owned()
+                ),
+            ],
+            context
+                .warnings
+                .into_iter()
+                .map(|w| (w.position.line(), w.message))
+                .collect::<Vec<_>>()
+        );
+    }
+}
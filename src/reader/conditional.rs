@@ -0,0 +1,232 @@
+use crate::asg::Attributes;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ConditionalKeyword {
+    Ifdef,
+    Ifndef,
+    Ifeval,
+    Endif,
+}
+
+/// An `ifdef`, `ifndef`, `ifeval` or `endif` preprocessor directive.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConditionalDirective {
+    pub(crate) keyword: ConditionalKeyword,
+    /// The attribute names, separated by `,` when any of them has to be set
+    /// and by `+` when all of them have to.
+    pub(crate) target: String,
+    /// The content of a single-line directive, or the expression of `ifeval`.
+    pub(crate) text: Option<String>,
+}
+impl ConditionalDirective {
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let (keyword, rest) = line.split_once("::")?;
+        let keyword = match keyword {
+            "ifdef" => ConditionalKeyword::Ifdef,
+            "ifndef" => ConditionalKeyword::Ifndef,
+            "ifeval" => ConditionalKeyword::Ifeval,
+            "endif" => ConditionalKeyword::Endif,
+            _ => return None,
+        };
+        let (target, text) = rest.strip_suffix(']')?.split_once('[')?;
+        if target.contains(char::is_whitespace) {
+            return None;
+        }
+        let text = (!text.is_empty()).then(|| text.to_owned());
+
+        let is_valid = match keyword {
+            ConditionalKeyword::Ifdef | ConditionalKeyword::Ifndef => !target.is_empty(),
+            ConditionalKeyword::Ifeval => target.is_empty() && text.is_some(),
+            ConditionalKeyword::Endif => text.is_none(),
+        };
+        if !is_valid {
+            return None;
+        }
+
+        Some(Self {
+            keyword,
+            target: target.to_owned(),
+            text,
+        })
+    }
+
+    /// Returns the directive as written, for the warnings about it.
+    pub(crate) fn source(&self) -> String {
+        let keyword = match self.keyword {
+            ConditionalKeyword::Ifdef => "ifdef",
+            ConditionalKeyword::Ifndef => "ifndef",
+            ConditionalKeyword::Ifeval => "ifeval",
+            ConditionalKeyword::Endif => "endif",
+        };
+
+        format!(
+            "{}::{}[{}]",
+            keyword,
+            self.target,
+            self.text.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Returns whether the directive has its content in brackets, instead of
+    /// applying to the lines up to `endif`.
+    pub(crate) fn is_single_line(&self) -> bool {
+        self.keyword != ConditionalKeyword::Ifeval && self.text.is_some()
+    }
+
+    /// Evaluates the condition with the attributes set so far, or returns
+    /// `None` for an invalid `ifeval` expression.
+    pub(crate) fn evaluate(&self, attributes: &mut Attributes) -> Option<bool> {
+        let is_any = self.target.contains(',');
+        let names = self.target.split(if is_any { ',' } else { '+' });
+        let is_set = |name: &str| attributes.get(name).is_some();
+        let (any, all) = (names.clone().any(is_set), names.clone().all(is_set));
+
+        match self.keyword {
+            ConditionalKeyword::Ifdef => Some(if is_any { any } else { all }),
+            ConditionalKeyword::Ifndef => Some(if is_any { !any } else { !all }),
+            ConditionalKeyword::Ifeval => evaluate_expression(self.text.as_deref()?, attributes),
+            ConditionalKeyword::Endif => None,
+        }
+    }
+}
+
+/// A value of an `ifeval` expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+impl Value {
+    fn parse(text: &str, attributes: &mut Attributes) -> Self {
+        let text = text.trim();
+        for quote in ['"', '\''] {
+            if let Some(quoted) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+                return Self::String(attributes.substitute(quoted));
+            }
+        }
+
+        let value = attributes.substitute(text);
+        match value.as_str() {
+            "" => Self::Nil,
+            "true" => Self::Bool(true),
+            "false" => Self::Bool(false),
+            v if v.trim().is_empty() => Self::String(" ".to_owned()),
+            v if v.contains('.') => Self::Float(v.parse().unwrap_or(0.0)),
+            v => Self::Integer(v.parse().unwrap_or(0)),
+        }
+    }
+
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a.partial_cmp(b),
+            (Self::Integer(a), Self::Float(b)) => (*a as f64).partial_cmp(b),
+            (Self::Float(a), Self::Integer(b)) => a.partial_cmp(&(*b as f64)),
+            (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
+            (Self::String(a), Self::String(b)) => a.partial_cmp(b),
+            (Self::Bool(a), Self::Bool(b)) => a.partial_cmp(b),
+            (Self::Nil, Self::Nil) => Some(std::cmp::Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+/// Evaluates an expression like `{sectnumlevels} >= 2` or `"{backend}" == "html5"`.
+fn evaluate_expression(expression: &str, attributes: &mut Attributes) -> Option<bool> {
+    use std::cmp::Ordering::*;
+
+    let (index, operator) = find_operator(expression)?;
+    let (lhs, rhs) = (&expression[..index], &expression[index + operator.len()..]);
+    if lhs.trim().is_empty() || rhs.trim().is_empty() {
+        return None;
+    }
+
+    let ordering = Value::parse(lhs, attributes).partial_cmp(&Value::parse(rhs, attributes));
+    Some(match operator {
+        "==" => ordering == Some(Equal),
+        "!=" => ordering != Some(Equal),
+        "<" => ordering == Some(Less),
+        "<=" => matches!(ordering, Some(Less | Equal)),
+        ">" => ordering == Some(Greater),
+        _ => matches!(ordering, Some(Greater | Equal)),
+    })
+}
+
+/// Returns the index and the first operator of `expression` outside of its
+/// quoted values, reading from the left.
+fn find_operator(expression: &str) -> Option<(usize, &'static str)> {
+    let mut quote = None;
+
+    for (index, c) in expression.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None => {
+                let rest = &expression[index..];
+                if let Some(operator) = ["==", "!=", "<=", ">=", "<", ">"]
+                    .into_iter()
+                    .find(|op| rest.starts_with(op))
+                {
+                    return Some((index, operator));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(line: &str) -> Option<bool> {
+        let mut attributes = Attributes::new();
+        attributes.set("level", "3");
+        attributes.set("backend", "html5");
+
+        ConditionalDirective::parse(line)?.evaluate(&mut attributes)
+    }
+
+    #[test]
+    fn conditional_directives() {
+        let directive = ConditionalDirective::parse("ifdef::env-github[Text]").unwrap();
+        assert_eq!(ConditionalKeyword::Ifdef, directive.keyword);
+        assert!(directive.is_single_line());
+        assert_eq!("ifdef::env-github[Text]", directive.source());
+
+        assert!(ConditionalDirective::parse("ifeval::[1 < 2]").is_some_and(|d| !d.is_single_line()));
+        assert_eq!(None, ConditionalDirective::parse("ifdef::[]"));
+        assert_eq!(None, ConditionalDirective::parse("ifeval::a[1 < 2]"));
+        assert_eq!(None, ConditionalDirective::parse("endif::a[text]"));
+        assert_eq!(None, ConditionalDirective::parse("ifdef::a b[]"));
+    }
+
+    #[test]
+    fn attribute_conditions() {
+        assert_eq!(Some(true), evaluate("ifdef::level[]"));
+        assert_eq!(Some(true), evaluate("ifdef::missing,level[]"));
+        assert_eq!(Some(false), evaluate("ifdef::missing+level[]"));
+        assert_eq!(Some(true), evaluate("ifdef::backend+level[]"));
+        assert_eq!(Some(false), evaluate("ifndef::missing,level[]"));
+        assert_eq!(Some(true), evaluate("ifndef::missing+level[]"));
+        assert_eq!(Some(true), evaluate("ifndef::missing[]"));
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(Some(true), evaluate("ifeval::[{level} > 2]"));
+        assert_eq!(Some(true), evaluate("ifeval::[{level} <= 3.0]"));
+        assert_eq!(Some(false), evaluate("ifeval::[{level} != 3]"));
+        assert_eq!(Some(true), evaluate("ifeval::[\"{backend}\" == \"html5\"]"));
+        assert_eq!(Some(false), evaluate("ifeval::['{backend}' == 'docbook5']"));
+        assert_eq!(Some(false), evaluate("ifeval::[\"{backend}\" > 2]"));
+        assert_eq!(None, evaluate("ifeval::[{level}]"));
+        assert_eq!(Some(true), evaluate("ifeval::[\"a==b\" < \"c\"]"));
+        assert_eq!(Some(true), evaluate("ifeval::[\"x<y\" == \"x<y\"]"));
+        assert_eq!(Some(false), evaluate("ifeval::['a>=b' != 'a>=b']"));
+    }
+}
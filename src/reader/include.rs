@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::asg::block::AttributeEntry;
+
+/// A file read for an include directive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludeFile {
+    /// The resolved path, which is the source of the included lines and the
+    /// parent of the include directives in them.
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+/// Reads the files targeted by include directives.
+pub trait IncludeResolver {
+    /// Reads `target`, which is relative to the `parent` file containing the
+    /// directive, or to the document when `parent` is `None`.
+    fn resolve(&self, target: &str, parent: Option<&str>) -> io::Result<IncludeFile>;
+}
+
+/// Reads included files from the file system, without leaving a base directory.
+#[derive(Debug, Clone)]
+pub struct FileResolver {
+    base_dir: PathBuf,
+}
+impl FileResolver {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+}
+impl IncludeResolver for FileResolver {
+    fn resolve(&self, target: &str, parent: Option<&str>) -> io::Result<IncludeFile> {
+        let dir = parent
+            .and_then(|p| Path::new(p).parent())
+            .map_or(self.base_dir.clone(), |d| self.base_dir.join(d));
+        let path = fs::canonicalize(dir.join(target))?;
+        if !path.starts_with(fs::canonicalize(&self.base_dir)?) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "include file is outside of the base directory",
+            ));
+        }
+
+        let contents = fs::read(&path)?;
+        let path = path
+            .strip_prefix(fs::canonicalize(&self.base_dir)?)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .into_owned();
+
+        Ok(IncludeFile { path, contents })
+    }
+}
+
+/// Reads included files from memory, for documents which do not live in a
+/// file system.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, Vec<u8>>,
+}
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the file at `path`, a `/`-separated path relative to the document.
+    pub fn insert(&mut self, path: &str, contents: impl Into<Vec<u8>>) {
+        self.files
+            .insert(normalize(Path::new(path)), contents.into());
+    }
+}
+impl IncludeResolver for MemoryResolver {
+    fn resolve(&self, target: &str, parent: Option<&str>) -> io::Result<IncludeFile> {
+        let dir = parent.and_then(|p| Path::new(p).parent());
+        let path = normalize(&dir.unwrap_or(Path::new("")).join(target));
+        let contents = self.files.get(&path).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("no such file: {}", path))
+        })?;
+
+        Ok(IncludeFile { path, contents })
+    }
+}
+
+/// Removes the `.` and `..` components of a relative path.
+fn normalize(path: &Path) -> String {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::Normal(name) => components.push(name.to_str().unwrap_or_default()),
+            _ => {}
+        }
+    }
+
+    components.join("/")
+}
+
+/// An `include::target[attributes]` preprocessor directive.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IncludeDirective {
+    pub(crate) target: String,
+    attrlist: String,
+    entries: Vec<AttributeEntry>,
+}
+impl IncludeDirective {
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let (target, attrlist) = line
+            .strip_prefix("include::")?
+            .strip_suffix(']')?
+            .split_once('[')?;
+        if target.is_empty() || target.starts_with(' ') || target.ends_with(' ') {
+            return None;
+        }
+
+        Some(Self {
+            target: target.to_owned(),
+            attrlist: attrlist.to_owned(),
+            entries: AttributeEntry::parse_list(attrlist),
+        })
+    }
+
+    /// Returns the directive as written, for an unresolved one.
+    pub(crate) fn source(&self) -> String {
+        format!("include::{}[{}]", self.target, self.attrlist)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.entries.iter().find_map(|e| match e {
+            AttributeEntry::Named(n, v) if n == name => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn is_optional(&self) -> bool {
+        self.attribute("opts")
+            .or(self.attribute("options"))
+            .is_some_and(|o| o.split(',').any(|o| o.trim() == "optional"))
+    }
+
+    pub(crate) fn encoding(&self) -> Option<&str> {
+        self.attribute("encoding")
+    }
+
+    /// Returns the level offset of the included sections, given the one of
+    /// the file containing the directive.
+    pub(crate) fn level_offset(&self, parent: isize) -> isize {
+        match self.attribute("leveloffset") {
+            Some(offset) if offset.starts_with(['+', '-']) => {
+                parent + offset.trim_start_matches('+').parse().unwrap_or(0)
+            }
+            Some(offset) => offset.parse().unwrap_or(parent),
+            None => parent,
+        }
+    }
+
+    /// Selects the included lines with the `lines`, `tag` or `tags` attribute,
+    /// along with their line numbers, and indents them as `indent` asks.
+    ///
+    /// The problems found with the tags are returned as messages.
+    pub(crate) fn select_lines(
+        &self,
+        text: &str,
+        path: &str,
+    ) -> (Vec<(usize, String)>, Vec<String>) {
+        let lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.to_owned()));
+        let (mut selected, messages) = if let Some(ranges) = self.attribute("lines") {
+            let ranges = parse_ranges(ranges);
+            let selected = lines
+                .filter(|(n, _)| ranges.iter().any(|(start, end)| start <= n && n <= end))
+                .collect();
+            (selected, Vec::with_capacity(0))
+        } else if let Some(tags) = self.attribute("tags").or(self.attribute("tag")) {
+            select_tags(lines, &parse_tags(tags), path)
+        } else {
+            (lines.collect(), Vec::with_capacity(0))
+        };
+
+        if let Some(indent) = self.attribute("indent").and_then(|i| i.parse().ok()) {
+            reindent(&mut selected, indent);
+        }
+
+        (selected, messages)
+    }
+}
+
+/// Parses line ranges like `1..3;7;10..-1`, where `-1` or nothing ends at the last line.
+fn parse_ranges(ranges: &str) -> Vec<(usize, usize)> {
+    ranges
+        .split([';', ','])
+        .filter_map(|range| {
+            let range = range.trim();
+            match range.split_once("..") {
+                Some((start, end)) => {
+                    let end = match end {
+                        "" | "-1" => usize::MAX,
+                        end => end.parse().ok()?,
+                    };
+                    Some((start.parse().ok()?, end))
+                }
+                None => range.parse().ok().map(|line| (line, line)),
+            }
+        })
+        .collect()
+}
+
+/// Parses tag names like `intro;!*`, where `!` excludes a tag, `*` stands
+/// for any tag and `**` for the lines outside of tags.
+fn parse_tags(tags: &str) -> Vec<(String, bool)> {
+    tags.split([';', ','])
+        .map(str::trim)
+        .filter(|t| !t.is_empty() && *t != "!")
+        .map(|t| match t.strip_prefix('!') {
+            Some(name) => (name.to_owned(), false),
+            None => (t.to_owned(), true),
+        })
+        .collect()
+}
+
+/// Returns the name of the tag of a `tag::name[]` or `end::name[]` line,
+/// and whether it ends the region.
+fn parse_tag_marker(line: &str) -> Option<(&str, bool)> {
+    ["tag::", "end::"].iter().find_map(|prefix| {
+        let (before, rest) = line.split_once(prefix)?;
+        if before.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let (name, after) = rest.split_once("[]")?;
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        if !after.is_empty() && !after.starts_with([' ', '\r']) {
+            return None;
+        }
+
+        Some((name, *prefix == "end::"))
+    })
+}
+
+/// Selects the lines of the tagged regions, dropping the tag marker lines.
+fn select_tags(
+    lines: impl Iterator<Item = (usize, String)>,
+    tags: &[(String, bool)],
+    path: &str,
+) -> (Vec<(usize, String)>, Vec<String>) {
+    let first_is_wildcard = tags.first().is_some_and(|(n, _)| n == "*");
+    let first_value = tags.first().map(|(_, v)| *v);
+    let mut tags = tags.to_vec();
+    let mut take = |name: &str| {
+        let index = tags.iter().position(|(n, _)| n == name)?;
+        Some(tags.remove(index).1)
+    };
+
+    // Whether the lines outside of the tags are selected, and whether the
+    // regions of the tags which are not named are.
+    let (base, wildcard) = if let Some(base) = take("**") {
+        let wildcard = take("*").or((!base && first_value == Some(false)).then_some(true));
+        (base, wildcard)
+    } else if let Some(wildcard) = take("*") {
+        match first_is_wildcard {
+            true => (!wildcard, Some(wildcard)),
+            false => (false, Some(wildcard)),
+        }
+    } else {
+        (!tags.iter().any(|(_, v)| *v), None)
+    };
+
+    let mut selected = Vec::new();
+    let mut messages = Vec::with_capacity(0);
+    let mut used = Vec::new();
+    let mut stack: Vec<(String, bool, usize)> = Vec::new();
+    let mut select = base;
+
+    for (number, line) in lines {
+        let Some((name, is_end)) = parse_tag_marker(&line) else {
+            if select {
+                selected.push((number, line));
+            }
+            continue;
+        };
+
+        let known = tags.iter().find(|(n, _)| n == name).map(|(_, v)| *v);
+        if is_end {
+            if stack.last().is_some_and(|(active, _, _)| active == name) {
+                stack.pop();
+                select = stack.last().map_or(base, |(_, s, _)| *s);
+            } else if known.is_some() {
+                messages.push(match stack.last() {
+                    Some((active, _, _)) => format!(
+                        "mismatched end tag (expected '{}' but found '{}') at line {} of include file: {}",
+                        active, name, number, path
+                    ),
+                    None => format!(
+                        "unexpected end tag '{}' at line {} of include file: {}",
+                        name, number, path
+                    ),
+                });
+            }
+        } else if let Some(value) = known {
+            used.push(name.to_owned());
+            select = value;
+            stack.push((name.to_owned(), select, number));
+        } else if let Some(wildcard) = wildcard {
+            select = if !stack.is_empty() && !select {
+                false
+            } else {
+                wildcard
+            };
+            stack.push((name.to_owned(), select, number));
+        }
+    }
+
+    for (name, _, number) in stack {
+        if tags.iter().any(|(n, _)| *n == name) {
+            messages.push(format!(
+                "detected unclosed tag '{}' starting at line {} of include file: {}",
+                name, number, path
+            ));
+        }
+    }
+    let missing: Vec<_> = tags
+        .iter()
+        .filter(|(name, _)| !used.contains(name))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        messages.push(format!(
+            "tag{} '{}' not found in include file: {}",
+            if missing.len() > 1 { "s" } else { "" },
+            missing.join(", "),
+            path
+        ));
+    }
+
+    (selected, messages)
+}
+
+/// Replaces the common indentation of the lines with `indent` spaces.
+fn reindent(lines: &mut [(usize, String)], indent: usize) {
+    let common = lines
+        .iter()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(_, l)| l.len() - l.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+
+    for (_, line) in lines.iter_mut() {
+        if line.trim().is_empty() {
+            line.clear();
+        } else {
+            *line = " ".repeat(indent) + &line[common..];
+        }
+    }
+}
+
+/// Decodes the contents of an included file, following a byte order mark or
+/// else the `encoding` attribute of the directive.
+///
+/// Returns `None` for an unknown encoding.
+pub(crate) fn decode(bytes: &[u8], encoding: Option<&str>) -> Option<String> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| from_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return Some(String::from_utf8_lossy(rest).into_owned());
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return Some(utf16(rest, u16::from_le_bytes));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return Some(utf16(rest, u16::from_be_bytes));
+    }
+
+    match encoding.map(|e| e.to_lowercase()).as_deref() {
+        None | Some("utf-8" | "utf8") => Some(String::from_utf8_lossy(bytes).into_owned()),
+        Some("utf-16le") => Some(utf16(bytes, u16::from_le_bytes)),
+        Some("utf-16" | "utf-16be") => Some(utf16(bytes, u16::from_be_bytes)),
+        Some("iso-8859-1" | "latin1" | "us-ascii" | "ascii") => {
+            Some(bytes.iter().map(|b| char::from(*b)).collect())
+        }
+        Some("windows-1252" | "cp1252") => Some(bytes.iter().map(|b| cp1252(*b)).collect()),
+        Some(_) => None,
+    }
+}
+
+/// Maps a byte of Windows-1252, which differs from ISO-8859-1 in `0x80..0xA0`.
+fn cp1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        _ => char::from(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(attrlist: &str, text: &str) -> (Vec<String>, Vec<String>) {
+        let directive = IncludeDirective::parse(&format!("include::a.adoc[{}]", attrlist)).unwrap();
+        let (lines, messages) = directive.select_lines(text, "a.adoc");
+
+        (lines.into_iter().map(|(_, l)| l).collect(), messages)
+    }
+
+    #[test]
+    fn include_directive() {
+        let directive =
+            IncludeDirective::parse("include::{dir}/a.adoc[leveloffset=+1,opts=optional]").unwrap();
+        assert_eq!("{dir}/a.adoc", directive.target);
+        assert!(directive.is_optional());
+        assert_eq!(2, directive.level_offset(1));
+        assert_eq!(
+            -1,
+            IncludeDirective::parse("include::a.adoc[leveloffset=-1]")
+                .unwrap()
+                .level_offset(0)
+        );
+        assert_eq!(None, IncludeDirective::parse("include:: a.adoc[]"));
+        assert_eq!(None, IncludeDirective::parse("include::a.adoc"));
+    }
+
+    #[test]
+    fn line_ranges() {
+        let text = "1\n2\n3\n4\n5";
+        assert_eq!(
+            vec!["1", "3", "4", "5"],
+            select("lines=\"1;3..-1\"", text).0
+        );
+        assert_eq!(vec!["2", "3"], select("lines=2..3", text).0);
+        assert_eq!(vec!["4", "5"], select("lines=4..", text).0);
+    }
+
+    #[test]
+    fn tagged_regions() {
+        let text = "a\n// tag::one[]\nb\n// tag::two[]\nc\n// end::two[]\n// end::one[]\nd";
+        assert_eq!(vec!["b", "c"], select("tag=one", text).0);
+        assert_eq!(vec!["b"], select("tags=one;!two", text).0);
+        assert_eq!(vec!["a", "b", "d"], select("tags=!two", text).0);
+        assert_eq!(vec!["a", "d"], select("tags=**;!*", text).0);
+        assert_eq!(vec!["b", "c"], select("tags=*", text).0);
+
+        let (lines, messages) = select("tag=three", text);
+        assert!(lines.is_empty());
+        assert_eq!(
+            vec!["tag 'three' not found in include file: a.adoc"],
+            messages
+        );
+
+        let (_, messages) = select("tag=one", "# tag::one[]\nx\n# end::two[]");
+        assert_eq!(
+            vec!["detected unclosed tag 'one' starting at line 1 of include file: a.adoc"],
+            messages
+        );
+    }
+
+    #[test]
+    fn indentation() {
+        assert_eq!(
+            vec!["  fn main() {", "      run();", "", "  }"],
+            select("indent=2", "\tfn main() {\n\t    run();\n  \n\t}").0
+        );
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(Some("é".to_owned()), decode(&[0xE9], Some("ISO-8859-1")));
+        assert_eq!(Some("€".to_owned()), decode(&[0x80], Some("cp1252")));
+        assert_eq!(
+            Some("ab".to_owned()),
+            decode(&[0xFF, 0xFE, b'a', 0, b'b', 0], None)
+        );
+        assert_eq!(Some("a".to_owned()), decode(&[0, b'a'], Some("utf-16be")));
+        assert_eq!(None, decode(b"a", Some("klingon")));
+    }
+
+    #[test]
+    fn memory_resolver() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("chapters/one.adoc", "one");
+        resolver.insert("shared.adoc", "shared");
+
+        assert_eq!(
+            "chapters/one.adoc",
+            resolver.resolve("chapters/one.adoc", None).unwrap().path
+        );
+        assert_eq!(
+            b"shared".to_vec(),
+            resolver
+                .resolve("../shared.adoc", Some("chapters/one.adoc"))
+                .unwrap()
+                .contents
+        );
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            resolver.resolve("two.adoc", None).unwrap_err().kind()
+        );
+    }
+}
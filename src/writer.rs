@@ -0,0 +1,847 @@
+//! Writes the ASG back to AsciiDoc source, in a canonical form which parses
+//! into the same nodes.
+
+use crate::asg::block::DataFormat;
+use crate::asg::{
+    AnyList, Attributes, Block, BlockBreak, BlockBreakVariant, BlockForm, BlockLeaf, BlockMacro,
+    BlockMetadata, BlockParent, CellStyle, DlistItem, Document, HorizontalAlignment, Inline,
+    ListItem, Section, SectionBody, SpanForm, Table, TableCell, TableColumn, VerticalAlignment,
+};
+
+impl Document {
+    /// Writes the document as AsciiDoc source: the header with its attribute
+    /// entries, then the blocks and the sections.
+    pub fn to_asciidoc(&self) -> String {
+        let mut writer = Writer::default();
+        writer.document(self);
+
+        writer.into_text()
+    }
+}
+
+impl Section {
+    /// Writes the section as AsciiDoc source, from its heading.
+    pub fn to_asciidoc(&self) -> String {
+        let mut writer = Writer::default();
+        writer.section(self);
+
+        writer.into_text()
+    }
+}
+
+impl Block {
+    /// Writes the block as AsciiDoc source, with its title and attribute line.
+    pub fn to_asciidoc(&self) -> String {
+        let mut writer = Writer::default();
+        writer.block(self);
+
+        writer.into_text()
+    }
+}
+
+impl Inline {
+    /// Writes the inline as AsciiDoc markup, escaping the text which would
+    /// be parsed as markup.
+    pub fn to_asciidoc(&self) -> String {
+        inline_markup(std::slice::from_ref(self))
+    }
+}
+
+/// Collects the lines of the source being written.
+#[derive(Debug, Default)]
+struct Writer {
+    lines: Vec<String>,
+}
+impl Writer {
+    fn into_text(self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+
+        text
+    }
+
+    fn push(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+    }
+
+    /// Pushes the lines of `text`, which may span several lines.
+    fn push_text(&mut self, text: &str) {
+        self.lines.extend(text.split('\n').map(str::to_owned));
+    }
+
+    /// Separates what follows from the lines written so far by a blank line.
+    fn push_blank(&mut self) {
+        if self.lines.last().is_some_and(|l| !l.is_empty()) {
+            self.push("");
+        }
+    }
+
+    fn document(&mut self, doc: &Document) {
+        if let Some(header) = doc.header() {
+            self.push(format!("= {}", inline_markup(header.title())));
+        }
+        if let Some(attributes) = doc.attributes() {
+            let mut attributes: Vec<_> = attributes.iter().collect();
+            attributes.sort();
+            for (name, value) in attributes {
+                self.attribute_entry(name, value);
+            }
+        }
+
+        self.section_bodies(doc.blocks());
+    }
+
+    /// Writes an attribute entry, whose value continues over the next lines
+    /// after a ` + \` for each line break.
+    fn attribute_entry(&mut self, name: &str, value: &str) {
+        let value = escape_references(value);
+        if value.is_empty() {
+            self.push(format!(":{name}:"));
+
+            return;
+        }
+
+        let mut lines = value.split('\n').peekable();
+        let mut line = format!(":{name}: ");
+        while let Some(value) = lines.next() {
+            line.push_str(value);
+            if lines.peek().is_some() {
+                line.push_str(" + \\");
+            }
+            self.push(std::mem::take(&mut line));
+        }
+    }
+
+    fn section_bodies(&mut self, bodies: &[SectionBody]) {
+        let mut previous = None;
+        for body in bodies {
+            self.push_blank();
+            match body {
+                SectionBody::Block(block) => {
+                    self.separate_lists(previous, block);
+                    self.block(block);
+                    previous = Some(block);
+                }
+                SectionBody::Section(section) => {
+                    self.section(section);
+                    previous = None;
+                }
+            }
+        }
+    }
+
+    fn section(&mut self, section: &Section) {
+        let entries = attribute_entries(
+            section.id(),
+            section.reftext(),
+            section.metadata(),
+            None,
+            &[],
+            true,
+        );
+        self.attribute_line(&entries);
+        self.push(format!(
+            "{} {}",
+            "=".repeat(section.level() + 1),
+            inline_markup(section.title())
+        ));
+
+        self.section_bodies(section.blocks());
+    }
+
+    /// Writes the blocks of a compound block, separated by blank lines.
+    fn blocks(&mut self, blocks: &[Block]) {
+        let mut previous = None;
+        for block in blocks {
+            if previous.is_some() {
+                self.push("");
+            }
+            self.separate_lists(previous, block);
+            self.block(block);
+            previous = Some(block);
+        }
+    }
+
+    /// Keeps `block` from being read as a part of the list before it, when it
+    /// is a list too, by a line comment between blank lines.
+    fn separate_lists(&mut self, previous: Option<&Block>, block: &Block) {
+        if matches!(
+            (previous, block),
+            (Some(Block::AnyList(_)), Block::AnyList(_))
+        ) {
+            self.push("//");
+            self.push("");
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::BlockParent(parent) => self.block_parent(block, parent),
+            Block::BlockLeaf(leaf) => self.block_leaf(block, leaf),
+            Block::BlockMacro(block_macro) => self.block_macro(block, block_macro),
+            Block::BlockBreak(block_break) => self.block_break(block, block_break),
+            Block::AnyList(list) => self.list(block, list),
+            Block::Table(table) => self.table(block, table),
+        }
+    }
+
+    /// Writes the title and the attribute line of `block`, with `style`
+    /// when its metadata has none.
+    fn block_metadata(&mut self, block: &Block, style: Option<&str>, implied_options: &[&str]) {
+        if let Some(title) = block.title() {
+            self.push(format!(".{}", inline_markup(title)));
+        }
+
+        // The attributes of a block macro are written between its brackets.
+        let has_attributes = !matches!(block, Block::BlockMacro(_));
+        let entries = attribute_entries(
+            block.id(),
+            block.reftext(),
+            block.metadata(),
+            style,
+            implied_options,
+            has_attributes,
+        );
+        self.attribute_line(&entries);
+    }
+
+    fn attribute_line(&mut self, entries: &[String]) {
+        if !entries.is_empty() {
+            self.push(format!("[{}]", entries.join(",")));
+        }
+    }
+
+    fn block_parent(&mut self, block: &Block, parent: &BlockParent) {
+        let body = parent.body();
+        let label = body.variant().map(|v| v.label());
+
+        if body.form() == Some(BlockForm::Paragraph) {
+            let text = match body.blocks() {
+                [Block::BlockLeaf(BlockLeaf::Paragraph(paragraph))] => {
+                    inline_markup(paragraph.inlines())
+                }
+                _ => String::new(),
+            };
+            // A `[NOTE]` style already makes the admonition, but any other style
+            // is kept along with the label.
+            let style = block.metadata().and_then(|m| m.attribute("1"));
+            self.block_metadata(block, None, &[]);
+            match label.filter(|&l| style != Some(l)) {
+                Some(label) => self.push_text(&format!("{label}: {text}")),
+                None => self.push_text(&text),
+            }
+
+            return;
+        }
+
+        let delimiter = body.delimiter().unwrap_or(match parent {
+            BlockParent::Admonition(_) | BlockParent::Example(_) => "====",
+            BlockParent::Sidebar(_) => "****",
+            BlockParent::Open(_) => "--",
+            BlockParent::Quote(_) => "____",
+        });
+        self.block_metadata(block, label, &[]);
+        self.push(delimiter);
+        self.blocks(body.blocks());
+        self.push(delimiter);
+    }
+
+    fn block_leaf(&mut self, block: &Block, leaf: &BlockLeaf) {
+        let body = leaf.body();
+        let (style, delimiter) = match leaf {
+            BlockLeaf::Listing(_) => (None, "----"),
+            BlockLeaf::Literal(_) => (None, "...."),
+            BlockLeaf::Paragraph(_) => (None, ""),
+            BlockLeaf::Pass(_) => (None, "++++"),
+            BlockLeaf::Stem(_) => (Some("stem"), "++++"),
+            BlockLeaf::Verse(_) => (Some("verse"), "____"),
+        };
+        self.block_metadata(block, style, &[]);
+
+        let content = match leaf {
+            BlockLeaf::Paragraph(_) | BlockLeaf::Verse(_) => inline_markup(body.inlines()),
+            _ => verbatim_text(body.inlines()),
+        };
+        if let BlockLeaf::Paragraph(_) = leaf {
+            self.push_text(&content);
+
+            return;
+        }
+
+        let delimiter = body.delimiter().unwrap_or(delimiter);
+        self.push(delimiter);
+        if !content.is_empty() {
+            self.push_text(&content);
+        }
+        self.push(delimiter);
+    }
+
+    fn block_macro(&mut self, block: &Block, block_macro: &BlockMacro) {
+        let name = match block_macro {
+            BlockMacro::Audio(_) => "audio",
+            BlockMacro::Video(_) => "video",
+            BlockMacro::Image(_) => "image",
+            BlockMacro::Toc(_) => "toc",
+        };
+        self.block_metadata(block, None, &[]);
+
+        let attributes = block_macro.metadata().map_or_else(Vec::new, |m| {
+            sorted_attributes(m)
+                .into_iter()
+                .map(|(name, value)| format!("{name}={}", quote(value)))
+                .collect()
+        });
+        self.push(format!(
+            "{name}::{}[{}]",
+            escape_references(block_macro.target().unwrap_or_default()),
+            attributes.join(",")
+        ));
+    }
+
+    fn block_break(&mut self, block: &Block, block_break: &BlockBreak) {
+        self.block_metadata(block, None, &[]);
+        self.push(match block_break.variant() {
+            BlockBreakVariant::Thematic => "'''",
+            BlockBreakVariant::Page => "<<<",
+        });
+    }
+
+    fn list(&mut self, block: &Block, list: &AnyList) {
+        // The parser adds the `checklist` option to a list with checkboxes.
+        let implied_options: &[&str] = match list.items().iter().any(|i| i.checked().is_some()) {
+            true => &["checklist"],
+            false => &[],
+        };
+        self.block_metadata(block, None, implied_options);
+
+        match list {
+            AnyList::List { items, .. } => items.iter().for_each(|item| self.list_item(item)),
+            AnyList::Dlist { items, .. } => items.iter().for_each(|item| self.dlist_item(item)),
+        }
+    }
+
+    fn list_item(&mut self, item: &ListItem) {
+        let checkbox = match item.checked() {
+            Some(true) => "[x] ",
+            Some(false) => "[ ] ",
+            None => "",
+        };
+        self.push_text(&format!(
+            "{} {checkbox}{}",
+            item.marker(),
+            inline_markup(item.principal())
+        ));
+
+        self.attached_blocks(item.blocks());
+    }
+
+    fn dlist_item(&mut self, item: &DlistItem) {
+        // Each term is on its own line, and the last one is followed by the description.
+        let terms = item.terms();
+        for (index, term) in terms.iter().enumerate() {
+            let mut line = format!("{}{}", inline_markup(term), item.marker());
+            if index + 1 == terms.len() && !item.principal().is_empty() {
+                line.push(' ');
+                line.push_str(&inline_markup(item.principal()));
+            }
+            self.push_text(&line);
+        }
+
+        self.attached_blocks(item.blocks());
+    }
+
+    /// Writes the blocks attached to a list item, after a list continuation
+    /// unless it is a nested list without metadata.
+    fn attached_blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            let is_bare_list = match block {
+                Block::AnyList(list) => {
+                    let has_checkboxes = list.items().iter().any(|i| i.checked().is_some());
+                    let implied_options: &[&str] =
+                        if has_checkboxes { &["checklist"] } else { &[] };
+
+                    block.title().is_none()
+                        && attribute_entries(
+                            block.id(),
+                            block.reftext(),
+                            block.metadata(),
+                            None,
+                            implied_options,
+                            true,
+                        )
+                        .is_empty()
+                }
+                _ => false,
+            };
+            if !is_bare_list {
+                self.push("+");
+            }
+            self.block(block);
+        }
+    }
+
+    fn table(&mut self, block: &Block, table: &Table) {
+        self.block_metadata(block, None, &[]);
+
+        let delimiter = table.delimiter().unwrap_or_else(|| "|===".to_owned());
+        let format = table.data_format();
+        let columns = table.columns();
+        let mut rowspans = vec![0; columns.len()];
+        self.push(delimiter.as_str());
+        for row in table.head() {
+            let line = table_row(row, columns, &mut rowspans, format);
+            self.push_text(&line);
+            // A blank line after the first row makes it the header row.
+            self.push("");
+        }
+        for row in table.body().iter().chain(table.foot()) {
+            let line = table_row(row, columns, &mut rowspans, format);
+            self.push_text(&line);
+        }
+        self.push(delimiter);
+    }
+}
+
+/// Returns the entries of an attribute line: the style with the id, role and
+/// option shorthands, the other positional attributes, then the named ones.
+///
+/// The options of `implied_options` are left out since the parser adds them,
+/// and so are the attributes unless `has_attributes`.
+fn attribute_entries(
+    id: Option<&str>,
+    reftext: Option<&[Inline]>,
+    metadata: Option<&BlockMetadata>,
+    style: Option<&str>,
+    implied_options: &[&str],
+    has_attributes: bool,
+) -> Vec<String> {
+    let attributes = metadata.filter(|_| has_attributes);
+    let style = attributes.and_then(|m| m.attribute("1")).or(style);
+    let roles = metadata.map_or(&[][..], |m| m.roles());
+    let options: Vec<&str> = metadata
+        .map_or(&[][..], |m| m.options())
+        .iter()
+        .map(String::as_str)
+        .filter(|o| !implied_options.contains(o))
+        .collect();
+
+    // A style which has to be quoted cannot be followed by shorthands.
+    let has_shorthands = style.is_none_or(is_shorthand_value);
+    let mut first = style.map_or_else(String::new, |s| match has_shorthands {
+        true => s.to_owned(),
+        false => quote(s),
+    });
+    let mut named = Vec::with_capacity(0);
+    if let Some(id) = id {
+        match has_shorthands && is_shorthand_value(id) {
+            true => first.push_str(&format!("#{id}")),
+            false => named.push(format!("id={}", quote(id))),
+        }
+    }
+    if has_shorthands && roles.iter().all(|r| is_shorthand_value(r)) {
+        roles.iter().for_each(|r| first.push_str(&format!(".{r}")));
+    } else {
+        named.push(format!("role={}", quote(&roles.join(" "))));
+    }
+    if has_shorthands && options.iter().all(|o| is_shorthand_value(o)) {
+        options
+            .iter()
+            .for_each(|o| first.push_str(&format!("%{o}")));
+    } else {
+        named.push(format!("opts={}", quote(&options.join(","))));
+    }
+
+    let mut positional = Vec::with_capacity(0);
+    for (name, value) in attributes.map(sorted_attributes).unwrap_or_default() {
+        match name.parse::<usize>() {
+            Ok(1) => {}
+            Ok(position) => {
+                // The positions without a value are left empty.
+                positional.resize(position - 2, String::new());
+                positional.push(quote(value));
+            }
+            Err(_) => named.push(format!("{name}={}", quote(value))),
+        }
+    }
+    if let Some(reftext) = reftext {
+        named.push(format!("reftext={}", quote(&inline_markup(reftext))));
+    }
+
+    let mut entries = Vec::with_capacity(0);
+    if !first.is_empty() || !positional.is_empty() {
+        entries.push(first);
+    }
+    entries.extend(positional);
+    entries.extend(named);
+
+    entries
+}
+
+/// Returns the attributes of `metadata`, the positional ones first in order.
+fn sorted_attributes(metadata: &BlockMetadata) -> Vec<(&str, &str)> {
+    let mut attributes: Vec<_> = metadata
+        .attributes()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    attributes.sort_by_key(|(name, _)| (name.parse::<usize>().unwrap_or(usize::MAX), *name));
+
+    attributes
+}
+
+/// Returns whether `value` can be written as an `#id`, `.role` or `%option` shorthand.
+fn is_shorthand_value(value: &str) -> bool {
+    !value.is_empty()
+        && !value.contains(|c: char| {
+            c.is_whitespace() || matches!(c, '#' | '.' | '%' | ',' | '"' | '\'' | '=')
+        })
+}
+
+/// Quotes the value of an attribute list entry when it would not be read back as is.
+fn quote(value: &str) -> String {
+    let name_end = value.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'));
+    let looks_named = name_end.is_some_and(|end| end > 0 && value[end..].starts_with('='));
+    let needs_quotes = looks_named
+        || value.contains([',', '"'])
+        || value.starts_with('\'')
+        || value.trim() != value;
+
+    let value = escape_references(value);
+    match needs_quotes {
+        true => format!("\"{}\"", value.replace('"', "\\\"")),
+        false => value,
+    }
+}
+
+/// Returns the text of the verbatim nodes of a listing, literal, passthrough
+/// or stem block.
+fn verbatim_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(literal) | Inline::Charref(literal) | Inline::Raw(literal) => {
+                literal.value().to_owned()
+            }
+            Inline::Span(_) => inline_markup(std::slice::from_ref(inline)),
+        })
+        .collect()
+}
+
+/// Returns the markup of `inlines`, escaping the marks and the attribute
+/// references in their text so that it is parsed back into the same nodes.
+fn inline_markup(inlines: &[Inline]) -> String {
+    // Each character is paired with whether it comes from a text node.
+    let mut chars = Vec::new();
+    push_inline_chars(inlines, &mut chars);
+
+    // An escape changes what the following marks match, so the marks which
+    // would open a span are escaped one at a time.
+    loop {
+        let text: String = chars.iter().map(|(c, _)| c).collect();
+        let opening = Inline::span_openings(&text)
+            .into_iter()
+            .find(|&i| chars[i].1 && (i == 0 || chars[i - 1] != ('\\', false)));
+        match opening {
+            Some(i) => chars.insert(i, ('\\', false)),
+            None => return escape_references(&text),
+        }
+    }
+}
+
+fn push_inline_chars(inlines: &[Inline], chars: &mut Vec<(char, bool)>) {
+    for inline in inlines {
+        match inline {
+            Inline::Span(span) => {
+                let variant = span.variant();
+                let count = match span.form() {
+                    SpanForm::Unconstrained if variant.has_unconstrained_pair() => 2,
+                    _ => 1,
+                };
+                let marks = vec![(variant.mark(), false); count];
+                chars.extend(&marks);
+                push_inline_chars(span.inlines(), chars);
+                chars.extend(&marks);
+            }
+            Inline::Text(text) => chars.extend(text.value().chars().map(|c| (c, true))),
+            Inline::Charref(literal) | Inline::Raw(literal) => {
+                chars.extend(literal.value().chars().map(|c| (c, false)))
+            }
+        }
+    }
+}
+
+/// Escapes the attribute references in `text`, which are then kept as is.
+fn escape_references(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '{' && Attributes::find_reference(&chars, i).is_some() {
+            escaped.push('\\');
+        }
+        escaped.push(*c);
+    }
+
+    escaped
+}
+
+/// Writes a row of a table in its data format, tracking in `rowspans` the
+/// number of rows each column is still spanned over.
+fn table_row(
+    row: &[TableCell],
+    columns: &[TableColumn],
+    rowspans: &mut [usize],
+    format: DataFormat,
+) -> String {
+    let mut cells = Vec::with_capacity(row.len());
+    let mut column = 0;
+    for cell in row {
+        while rowspans.get(column).is_some_and(|r| *r > 0) {
+            column += 1;
+        }
+        let spec = cell_spec(cell, columns.get(column).cloned().unwrap_or_default());
+        for rowspan in rowspans.iter_mut().skip(column).take(cell.colspan()) {
+            *rowspan = cell.rowspan();
+        }
+        column += cell.colspan();
+
+        let text = cell_text(cell);
+        cells.push(match format {
+            DataFormat::Psv(separator) => format!(
+                "{spec}{separator}{}",
+                text.replace(separator, &format!("\\{separator}"))
+            ),
+            DataFormat::Csv(separator) => csv_value(&text, separator),
+            DataFormat::Dsv(separator) => text.replace(separator, &format!("\\{separator}")),
+        });
+    }
+    rowspans.iter_mut().for_each(|r| *r = r.saturating_sub(1));
+
+    match format {
+        DataFormat::Psv(_) => cells.join(" "),
+        DataFormat::Csv(separator) | DataFormat::Dsv(separator) => {
+            cells.join(&separator.to_string())
+        }
+    }
+}
+
+/// Returns the specifier of a cell, with what differs from its column.
+fn cell_spec(cell: &TableCell, column: TableColumn) -> String {
+    let mut spec = String::new();
+    match (cell.colspan(), cell.rowspan()) {
+        (1, 1) => {}
+        (colspan, 1) => spec.push_str(&format!("{colspan}+")),
+        (1, rowspan) => spec.push_str(&format!(".{rowspan}+")),
+        (colspan, rowspan) => spec.push_str(&format!("{colspan}.{rowspan}+")),
+    }
+    if cell.halign() != column.halign() {
+        spec.push(match cell.halign() {
+            HorizontalAlignment::Left => '<',
+            HorizontalAlignment::Center => '^',
+            HorizontalAlignment::Right => '>',
+        });
+    }
+    if cell.valign() != column.valign() {
+        spec.push('.');
+        spec.push(match cell.valign() {
+            VerticalAlignment::Top => '<',
+            VerticalAlignment::Middle => '^',
+            VerticalAlignment::Bottom => '>',
+        });
+    }
+    if cell.style() != column.style() {
+        spec.push(match cell.style() {
+            CellStyle::Asciidoc => 'a',
+            CellStyle::Default => 'd',
+            CellStyle::Emphasis => 'e',
+            CellStyle::Header => 'h',
+            CellStyle::Literal => 'l',
+            CellStyle::Monospaced => 'm',
+            CellStyle::Strong => 's',
+        });
+    }
+
+    spec
+}
+
+fn cell_text(cell: &TableCell) -> String {
+    if let Some(blocks) = cell.blocks() {
+        let mut writer = Writer::default();
+        writer.blocks(blocks);
+
+        return writer.lines.join("\n");
+    }
+
+    match cell.style() {
+        CellStyle::Literal => verbatim_text(cell.inlines().unwrap_or_default()),
+        _ => inline_markup(cell.inlines().unwrap_or_default()),
+    }
+}
+
+/// Quotes a comma-separated value which contains a separator, a quote or a line break.
+fn csv_value(text: &str, separator: char) -> String {
+    match text.contains([separator, '"', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parser;
+
+    use serde_json::Value;
+
+    /// Returns the ASG of `text`, without the locations which the written
+    /// source does not keep.
+    fn asg(text: &str) -> Value {
+        fn strip_locations(value: &mut Value) {
+            match value {
+                Value::Object(map) => {
+                    map.remove("location");
+                    map.values_mut().for_each(strip_locations);
+                }
+                Value::Array(values) => values.iter_mut().for_each(strip_locations),
+                _ => {}
+            }
+        }
+
+        let doc = Parser::new(text).parse_to_asg().unwrap();
+        let mut value = serde_json::to_value(&doc).unwrap();
+        strip_locations(&mut value);
+
+        value
+    }
+
+    fn assert_round_trip(text: &str) {
+        let written = Parser::new(text).parse_to_asg().unwrap().to_asciidoc();
+
+        assert_eq!(asg(text), asg(&written), "written as:\n{written}");
+    }
+
+    #[test]
+    fn canonical_markup() {
+        let doc = Parser::new(
+            "= Guide\nJane Doe <jane@example.org>\n:toc:\n\n[[intro,Intro]]\n.About\n[.lead]\nSome *bold* text.\n\n== Usage\n\n* [x] one\n+\n----\ncode\n----\n** nested\n* two",
+        )
+        .parse_to_asg()
+        .unwrap();
+
+        assert_eq!(
+            "= Guide\n:author: Jane Doe\n:email: jane@example.org\n:toc:\n\n.About\n[#intro.lead,reftext=Intro]\nSome *bold* text.\n\n== Usage\n\n* [x] one\n+\n----\ncode\n----\n** nested\n* two\n",
+            doc.to_asciidoc()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(
+            r#"= Document Title
+Author Name <author@example.org>
+v1.0, 2024-01-01: Remark
+:source-language: rust
+:description: A long \
+value
+
+Preamble with _emphasis_, `code`, #mark#, E=mc^2^ and H~2~O.
+Kept as text: {undefined}, \{amp}, {description}, 2 * 3, *x*y and \**y**.
+
+[#main-section.role1.role2%opt]
+== First Section
+
+NOTE: An admonition paragraph.
+
+[TIP]
+A styled admonition.
+
+[source,rust]
+NOTE: An admonition paragraph with another style.
+
+[WARNING]
+====
+Delimited admonition.
+
+* with a list
+====
+
+.Listing title
+[source,python,linenums]
+----
+def f():
+    return "*not bold*" <1>
+----
+<1> A callout.
+
+----
+fn default_language() {}
+----
+
+[verse,Poet,"Book, Chapter"]
+____
+Roses are *red*
+Violets are blue
+____
+
+[stem]
+++++
+sqrt(4) = 2
+++++
+
+=== Subsection
+
+. first
+.. nested with \*escaped* text
+. second
++
+A continued paragraph.
+
+//
+
+[loweralpha,start=3]
+. third
+. fourth
+
+Term:: Definition
+Other term::
+Another:: One more
++
+....
+literal
+....
+
+[qanda]
+What?:: This.
+
+image::images/sunset.jpg[Sunset, 300, 200, role=thumb]
+
+'''
+
+<<<
+
+[cols="1,2a",options="header"]
+|===
+|Name |Value
+
+|one |Some _text_ with a \| pipe
+|two 2+|
+
+* item
+|===
+
+[%header,format=csv]
+|===
+A,B
+"with, comma",2
+|===
+
+.Sidebar
+****
+Sidebar content.
+
+--
+Open block.
+--
+****
+"#,
+        );
+    }
+}